@@ -41,6 +41,12 @@ pub enum HybridSubcommands {
 
     /// Start the hybrid node in development mode
     Node,
+
+    /// Inspect a compiled RISC-V contract (sections, symbols, sizes and disassembly)
+    Inspect(InspectArgs),
+
+    /// Profile a call to a deployed RISC-V contract per function, as folded stacks
+    Profile(ProfileArgs),
 }
 
 #[derive(Args)]
@@ -87,3 +93,55 @@ pub struct DeployArgs {
     #[clap(long)]
     pub encoded_args: Option<String>,
 }
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Path to a compiled contract (.bin); defaults to the first contract in the out directory
+    pub path: Option<String>,
+
+    /// Path to the output directory containing the compiled contract
+    #[clap(long, default_value = "out")]
+    pub out: String,
+
+    /// Inspect the code deployed at this address instead of a local file
+    #[clap(long)]
+    pub address: Option<String>,
+
+    /// RPC endpoint used to fetch on-chain code
+    #[clap(long, default_value = "http://127.0.0.1:8545")]
+    pub rpc: String,
+
+    /// Only disassemble functions whose name contains this string
+    #[clap(long)]
+    pub function: Option<String>,
+
+    /// Skip the disassembly listing
+    #[clap(long)]
+    pub no_disasm: bool,
+}
+
+#[derive(Args)]
+pub struct ProfileArgs {
+    /// Calldata of the profiled call (hex encoded, with or without 0x prefix)
+    pub calldata: String,
+
+    /// Address of the deployed contract to call
+    #[clap(long)]
+    pub address: String,
+
+    /// Sender of the call
+    #[clap(long)]
+    pub from: Option<String>,
+
+    /// RPC endpoint of a hybrid node
+    #[clap(long, default_value = "http://127.0.0.1:8545")]
+    pub rpc: String,
+
+    /// File the folded stacks are written to, for `flamegraph.pl` or `inferno-flamegraph`
+    #[clap(long, default_value = "profile.folded")]
+    pub output: String,
+
+    /// Number of functions listed in the summary
+    #[clap(long, default_value = "20")]
+    pub top: usize,
+}
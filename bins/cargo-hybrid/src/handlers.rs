@@ -1,6 +1,8 @@
 //! Handlers for the cargo-cli command
-use crate::command::{BuildArgs, DeployArgs, NewArgs};
-use crate::utils::deploy_riscv_bytecode;
+use crate::command::{BuildArgs, DeployArgs, InspectArgs, NewArgs, ProfileArgs};
+use crate::inspect::{print_report, ContractElf};
+use crate::profile::{print_report as print_profile, Profile};
+use crate::utils::{deploy_riscv_bytecode, fetch_code, trace_profile};
 use alloy::primitives::hex;
 use anyhow::{anyhow, Result};
 use colored::Colorize;
@@ -316,3 +318,91 @@ pub fn deploy_contract(args: &DeployArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Inspect a compiled contract, either from the out directory or deployed on-chain
+pub fn inspect_contract(args: &InspectArgs) -> Result<()> {
+    let bytecode = match (&args.address, &args.path) {
+        (Some(address), _) => {
+            info!(
+                "Fetching code of {} from {}",
+                address.bold(),
+                args.rpc.bold()
+            );
+            let rt = tokio::runtime::Runtime::new()?;
+            rt.block_on(async { fetch_code(&args.rpc, address).await })?
+        }
+        (None, Some(path)) => fs::read(path)?,
+        (None, None) => {
+            let output_dir = std::env::current_dir()?.join(&args.out);
+            let bin_path = fs::read_dir(&output_dir)
+                .map_err(|_| {
+                    anyhow!(
+                        "Output directory '{}' not found. Run 'cargo hybrid build' first.",
+                        args.out
+                    )
+                })?
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .find(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "bin"))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No compiled contracts found in '{}'. Run 'cargo hybrid build' first.",
+                        args.out
+                    )
+                })?;
+            info!("Inspecting {}", bin_path.display().to_string().bold());
+            fs::read(bin_path)?
+        }
+    };
+
+    if bytecode.first() != Some(&0xff) {
+        return Err(anyhow!(
+            "Not a RISC-V contract: bytecode is missing the 0xFF prefix"
+        ));
+    }
+
+    let elf = ContractElf::parse(&bytecode)?;
+    print_report(&elf, args.function.as_deref(), !args.no_disasm);
+
+    Ok(())
+}
+
+/// Profile a call to a deployed contract and write the folded stacks
+pub fn profile_contract(args: &ProfileArgs) -> Result<()> {
+    let calldata = hex::decode(args.calldata.trim_start_matches("0x"))
+        .map_err(|e| anyhow!("Failed to decode calldata: {}", e))?;
+
+    info!(
+        "Profiling call to {} on {}",
+        args.address.bold(),
+        args.rpc.bold()
+    );
+
+    let rt = tokio::runtime::Runtime::new()?;
+    let trace = rt.block_on(async {
+        trace_profile(&args.rpc, &args.address, args.from.as_deref(), calldata).await
+    })?;
+    let profile = Profile::from_trace(trace)?;
+
+    if profile
+        .frames
+        .iter()
+        .all(|frame| frame.folded_stacks.is_empty())
+    {
+        return Err(anyhow!(
+            "No RISC-V frames were profiled. Is '{}' a RISC-V contract?",
+            args.address
+        ));
+    }
+
+    print_profile(&profile, args.top);
+
+    fs::write(&args.output, profile.folded())?;
+    println!(
+        "\n📊 {} {}\n",
+        "Folded stacks written to".green().bold(),
+        args.output.cyan()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! ELF inspection of compiled RISC-V contracts
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use goblin::elf::{section_header, Elf};
+use hybrid_syscalls::Syscall;
+use hybrid_vm::setup::function_symbols;
+use rvemu::{disasm::disassemble, profile::Symbol};
+
+/// The register holding the syscall id on `ecall` (t0).
+const SYSCALL_REG: u64 = 5;
+
+/// A section of the contract ELF
+pub struct SectionInfo {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+    pub flags: u64,
+    /// File contents, empty for sections without data (e.g. `.bss`)
+    pub data: Vec<u8>,
+}
+
+impl SectionInfo {
+    pub fn is_executable(&self) -> bool {
+        self.flags & section_header::SHF_EXECINSTR as u64 != 0
+    }
+
+    fn flags_str(&self) -> String {
+        let mut flags = String::new();
+        if self.flags & section_header::SHF_ALLOC as u64 != 0 {
+            flags.push('A');
+        }
+        if self.flags & section_header::SHF_WRITE as u64 != 0 {
+            flags.push('W');
+        }
+        if self.is_executable() {
+            flags.push('X');
+        }
+        flags
+    }
+}
+
+/// The parts of a contract ELF the inspector and profiler work with
+pub struct ContractElf {
+    pub entry: u64,
+    pub sections: Vec<SectionInfo>,
+    /// Function symbols sorted by address
+    pub functions: Vec<Symbol>,
+}
+
+impl ContractElf {
+    /// Parse contract bytecode, with or without the `0xFF` RISC-V prefix
+    pub fn parse(bytecode: &[u8]) -> Result<Self> {
+        let elf_data = match bytecode.split_first() {
+            Some((0xff, elf_data)) => elf_data,
+            _ => bytecode,
+        };
+        let elf = Elf::parse(elf_data).map_err(|e| anyhow!("Invalid contract ELF: {}", e))?;
+
+        let sections = elf
+            .section_headers
+            .iter()
+            .filter(|sh| sh.sh_type != section_header::SHT_NULL)
+            .map(|sh| {
+                let data = if sh.sh_type == section_header::SHT_NOBITS {
+                    Vec::new()
+                } else {
+                    elf_data
+                        .get(sh.sh_offset as usize..(sh.sh_offset + sh.sh_size) as usize)
+                        .unwrap_or_default()
+                        .to_vec()
+                };
+                SectionInfo {
+                    name: elf.shdr_strtab.get_at(sh.sh_name).unwrap_or("").to_string(),
+                    address: sh.sh_addr,
+                    size: sh.sh_size,
+                    flags: sh.sh_flags,
+                    data,
+                }
+            })
+            .collect();
+
+        let mut functions =
+            function_symbols(elf_data).map_err(|e| anyhow!("Invalid contract ELF: {}", e))?;
+        functions.sort_by_key(|f| f.address);
+        functions.dedup_by_key(|f| f.address);
+
+        Ok(Self {
+            entry: elf.header.e_entry,
+            sections,
+            functions,
+        })
+    }
+
+    /// The function containing `address`, if any
+    pub fn function_at(&self, address: u64) -> Option<&Symbol> {
+        let index = self
+            .functions
+            .partition_point(|f| f.address <= address)
+            .checked_sub(1)?;
+        let function = &self.functions[index];
+        (address < function.address + function.size.max(1)).then_some(function)
+    }
+}
+
+/// Print sections, symbols, size breakdown and the annotated disassembly of a contract
+pub fn print_report(elf: &ContractElf, function_filter: Option<&str>, disasm: bool) {
+    println!("{}", "Header".green().bold());
+    println!("  entrypoint: {:#x}", elf.entry);
+    if let Some(function) = elf.function_at(elf.entry) {
+        println!("  entry symbol: {}", function.name.cyan());
+    }
+
+    println!("\n{}", "Sections".green().bold());
+    println!("  {:<24} {:>12} {:>10}  flags", "name", "address", "size");
+    for section in &elf.sections {
+        println!(
+            "  {:<24} {:>#12x} {:>10}  {}",
+            section.name,
+            section.address,
+            section.size,
+            section.flags_str()
+        );
+    }
+
+    println!("\n{}", "Symbols".green().bold());
+    for function in &elf.functions {
+        println!(
+            "  {:>#12x} {:>8}  {}",
+            function.address, function.size, function.name
+        );
+    }
+
+    let text_size: u64 = elf
+        .sections
+        .iter()
+        .filter(|s| s.is_executable())
+        .map(|s| s.size)
+        .sum();
+    let mut by_size = elf.functions.clone();
+    by_size.sort_by(|a, b| b.size.cmp(&a.size));
+
+    println!("\n{}", "Size by function".green().bold());
+    println!("  total executable size: {} bytes", text_size);
+    for function in by_size.iter().filter(|f| f.size > 0) {
+        let share = function.size as f64 * 100.0 / text_size.max(1) as f64;
+        println!("  {:>8} {:>6.2}%  {}", function.size, share, function.name);
+    }
+
+    if !disasm {
+        return;
+    }
+
+    println!("\n{}", "Disassembly".green().bold());
+    for section in elf.sections.iter().filter(|s| s.is_executable()) {
+        println!("\n{} {}:", "section".bold(), section.name.cyan());
+        print_disassembly(elf, section, function_filter);
+    }
+}
+
+fn print_disassembly(elf: &ContractElf, section: &SectionInfo, function_filter: Option<&str>) {
+    let mut current_function: Option<u64> = None;
+    let mut syscall_id: Option<i64> = None;
+
+    for inst in disassemble(&section.data, section.address) {
+        let function = elf.function_at(inst.address);
+        if let Some(filter) = function_filter {
+            if !function.is_some_and(|f| f.name.contains(filter)) {
+                continue;
+            }
+        }
+
+        // Label function starts and forget register tracking across function boundaries
+        if function.map(|f| f.address) != current_function {
+            current_function = function.map(|f| f.address);
+            syscall_id = None;
+            if let Some(function) = function {
+                println!("\n{:016x} <{}>:", function.address, function.name.yellow());
+            }
+        }
+
+        let annotation = if inst.is_ecall() {
+            Some(match syscall_id {
+                Some(id) => match u8::try_from(id)
+                    .ok()
+                    .and_then(|id| Syscall::try_from(id).ok())
+                {
+                    Some(syscall) => format!("syscall {:#04x} ({})", id, syscall),
+                    None => format!("unknown syscall {:#x}", id),
+                },
+                None => "syscall (id not resolved)".to_string(),
+            })
+        } else {
+            inst.target
+                .and_then(|target| elf.function_at(target))
+                .filter(|f| Some(f.address) != current_function)
+                .map(|f| format!("<{}>", f.name))
+        };
+
+        if let Some(id) = inst.loaded_immediate(SYSCALL_REG) {
+            syscall_id = Some(id);
+        }
+
+        let raw = if inst.size == 2 {
+            format!("{:04x}    ", inst.raw)
+        } else {
+            format!("{:08x}", inst.raw)
+        };
+        match annotation {
+            Some(annotation) => println!(
+                "  {:>8x}:  {}  {:<40} {}",
+                inst.address,
+                raw,
+                inst.to_string(),
+                format!("# {}", annotation).bright_black()
+            ),
+            None => println!("  {:>8x}:  {}  {}", inst.address, raw, inst),
+        }
+    }
+}
@@ -1,12 +1,14 @@
 //! cargo-hybrid cli
 mod command;
 mod handlers;
+mod inspect;
+mod profile;
 mod utils;
 
 use anyhow::Result;
 use clap::Parser;
 use command::{BuildArgs, Cli, Commands, HybridSubcommands};
-use handlers::{build_contract, create_new_project, deploy_contract, start_node};
+use handlers::{build_contract, create_new_project, deploy_contract, inspect_contract, start_node};
 use utils::init_logger;
 
 fn main() -> Result<()> {
@@ -30,6 +32,8 @@ fn main() -> Result<()> {
             )?,
             HybridSubcommands::Deploy(args) => deploy_contract(&args)?,
             HybridSubcommands::Node => start_node()?,
+            HybridSubcommands::Inspect(args) => inspect_contract(&args)?,
+            HybridSubcommands::Profile(args) => profile_contract(&args)?,
         },
         None => {
             println!("Usage: cargo hybrid <COMMAND>");
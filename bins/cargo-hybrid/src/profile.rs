@@ -0,0 +1,112 @@
+//! Per-function gas profiles of RISC-V contracts, collected through the node's hybrid tracer
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub use hybrid_ethereum::tracer::{StackCost, HYBRID_TRACER_NAME};
+
+/// A call frame of the hybrid tracer's output
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfiledFrame {
+    pub depth: usize,
+    pub address: String,
+    pub kind: String,
+    pub instructions: u64,
+    pub instruction_gas: u64,
+    #[serde(default)]
+    pub folded_stacks: BTreeMap<String, StackCost>,
+}
+
+/// Output of the hybrid tracer with profiling enabled
+#[derive(Debug, Deserialize)]
+pub struct Profile {
+    pub frames: Vec<ProfiledFrame>,
+}
+
+impl Profile {
+    pub fn from_trace(trace: serde_json::Value) -> Result<Self> {
+        serde_json::from_value(trace).map_err(|e| anyhow!("Unexpected trace format: {}", e))
+    }
+
+    /// Folded stacks of every RISC-V frame, rooted at the executed contract's address
+    pub fn folded_stacks(&self) -> BTreeMap<String, StackCost> {
+        let mut folded: BTreeMap<String, StackCost> = BTreeMap::new();
+        for frame in &self.frames {
+            for (stack, cost) in &frame.folded_stacks {
+                folded
+                    .entry(format!("{};{}", frame.address, stack))
+                    .or_default()
+                    .add(*cost);
+            }
+        }
+        folded
+    }
+
+    /// The profile in the folded stack format understood by `flamegraph.pl` and `inferno`
+    pub fn folded(&self) -> String {
+        self.folded_stacks()
+            .iter()
+            .map(|(stack, cost)| format!("{} {}\n", stack, cost.gas))
+            .collect()
+    }
+}
+
+/// Cost of a function summed over all frames: (self, total including callees)
+fn function_costs(profile: &Profile) -> Vec<(String, StackCost, StackCost)> {
+    let mut functions: BTreeMap<&str, (StackCost, StackCost)> = BTreeMap::new();
+    for frame in &profile.frames {
+        for (stack, cost) in &frame.folded_stacks {
+            let names: Vec<&str> = stack.split(';').collect();
+            for (depth, name) in names.iter().enumerate() {
+                let (self_cost, total_cost) = functions.entry(*name).or_default();
+                // Recursive functions are only charged once per stack
+                if !names[..depth].contains(name) {
+                    total_cost.add(*cost);
+                }
+                if depth == names.len() - 1 {
+                    self_cost.add(*cost);
+                }
+            }
+        }
+    }
+
+    let mut functions: Vec<_> = functions
+        .into_iter()
+        .map(|(name, (self_cost, total_cost))| (name.to_string(), self_cost, total_cost))
+        .collect();
+    functions.sort_by(|a, b| b.1.gas.cmp(&a.1.gas));
+    functions
+}
+
+/// Print the RISC-V frames and the most expensive functions of a profile
+pub fn print_report(profile: &Profile, top: usize) {
+    println!("{}", "Frames".green().bold());
+    for frame in &profile.frames {
+        println!(
+            "  {}{} {:<5} {:>10} instructions {:>12} gas",
+            "  ".repeat(frame.depth),
+            frame.address,
+            frame.kind,
+            frame.instructions,
+            frame.instruction_gas
+        );
+    }
+
+    let functions = function_costs(profile);
+    let total_gas: u64 = profile.folded_stacks().values().map(|cost| cost.gas).sum();
+
+    println!("\n{}", "Functions (by self gas)".green().bold());
+    println!(
+        "  {:>12} {:>7} {:>12} {:>12}  function",
+        "self gas", "self %", "total gas", "instructions"
+    );
+    for (name, self_cost, total_cost) in functions.iter().take(top) {
+        let share = self_cost.gas as f64 * 100.0 / total_gas.max(1) as f64;
+        println!(
+            "  {:>12} {:>6.2}% {:>12} {:>12}  {}",
+            self_cost.gas, share, total_cost.gas, self_cost.instructions, name
+        );
+    }
+}
@@ -7,6 +7,8 @@ use alloy::{
     signers::local::PrivateKeySigner,
 };
 
+use crate::profile::HYBRID_TRACER_NAME;
+
 /// Initialize the logger with a nice formatted output
 pub fn init_logger() {
     use tracing_subscriber::{fmt, EnvFilter};
@@ -16,6 +18,41 @@ pub fn init_logger() {
     fmt::fmt().with_env_filter(filter).with_target(false).init();
 }
 
+/// Fetch the code deployed at `address`
+pub async fn fetch_code(rpc_url: &str, address: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let code = provider.get_code_at(address.parse::<Address>()?).await?;
+
+    Ok(code.to_vec())
+}
+
+/// Trace a call with the hybrid tracer, profiling every RISC-V frame
+pub async fn trace_profile(
+    rpc_url: &str,
+    to: &str,
+    from: Option<&str>,
+    calldata: Vec<u8>,
+) -> Result<serde_json::Value, anyhow::Error> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+
+    let mut tx = TransactionRequest::default()
+        .with_to(to.parse::<Address>()?)
+        .with_input(calldata);
+    if let Some(from) = from {
+        tx = tx.with_from(from.parse::<Address>()?);
+    }
+
+    let opts = serde_json::json!({
+        "tracer": HYBRID_TRACER_NAME,
+        "tracerConfig": { "profile": true },
+    });
+    let trace = provider
+        .raw_request("debug_traceCall".into(), (tx, "latest", opts))
+        .await?;
+
+    Ok(trace)
+}
+
 /// The function is use to deploy a RISC-V smart contract to a hybrid node
 pub async fn deploy_riscv_bytecode(
     rpc_url: &str,
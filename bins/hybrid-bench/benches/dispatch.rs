@@ -0,0 +1,116 @@
+//! Selector dispatch benchmark for `#[contract]`
+//!
+//! Runs the RISC-V runtime of `contracts/erc20` through the Hybrid VM, as `vm_comparison` does,
+//! built before and after the dispatch table and fixed-layout argument decoding:
+//! - `match_abi_decode`: `dispatch/before.bin.runtime`, a `match` on the selector, then
+//!   `abi_decode_params` for the arguments
+//! - `table_decode_word`: `dispatch/after.bin.runtime`, a binary search of the sorted selector
+//!   table, then each argument read at its fixed offset
+//!
+//! Both are built by `make dispatch-assets`. The gas each call uses with both builds, and the gas
+//! saved, is printed before they are measured.
+use alloy_sol_types::SolValue;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use hybrid_bench::{hybrid_vm_bench::run_with_hybrid_vm, RUNS};
+use revm::primitives::{hex, Address, U256};
+use sha3::{Digest, Keccak256};
+use std::fs;
+
+/// Builds of `contracts/erc20`, by the dispatch `#[contract]` generated for them
+const BUILDS: &[(&str, &str)] = &[
+    ("match_abi_decode", "before"),
+    ("table_decode_word", "after"),
+];
+
+fn load_erc20_runtime(build: &str) -> String {
+    let path = format!(
+        "{}/src/assets/cargo-hybrid/dispatch/{build}.bin.runtime",
+        env!("CARGO_MANIFEST_DIR"),
+    );
+    let data = fs::read(&path)
+        .unwrap_or_else(|err| panic!("{path}: {err}, run `make dispatch-assets` first"));
+
+    // The VM runs code prefixed with `0xFF` as RISC-V
+    format!("ff{}", hex::encode(data))
+}
+
+/// Calldata of the calls benchmarked, by method. They all succeed against empty storage.
+fn erc20_calls() -> Vec<(&'static str, String)> {
+    let alice = Address::repeat_byte(0xa1);
+    let bob = Address::repeat_byte(0xb0);
+    let amount = U256::from(1_000_000u64);
+
+    let call = |signature: &str, args: Vec<u8>| {
+        let mut calldata = Keccak256::digest(signature.as_bytes())[..4].to_vec();
+        calldata.extend(args);
+        hex::encode(calldata)
+    };
+    vec![
+        (
+            "approve",
+            call(
+                "approve(address,uint256)",
+                (bob, amount).abi_encode_params(),
+            ),
+        ),
+        (
+            "allowance",
+            call(
+                "allowance(address,address)",
+                (alice, bob).abi_encode_params(),
+            ),
+        ),
+        (
+            "balance_of",
+            call("balance_of(address)", (alice,).abi_encode_params()),
+        ),
+        ("total_supply", call("total_supply()", Vec::new())),
+    ]
+}
+
+/// Benchmark group comparing both builds on the ERC20 calls
+fn bench_erc20_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("erc20_dispatch");
+    let runtimes: Vec<_> = BUILDS
+        .iter()
+        .map(|&(dispatch, build)| (dispatch, load_erc20_runtime(build)))
+        .collect();
+
+    for (method, calldata) in erc20_calls() {
+        let gas_used: Vec<_> = runtimes
+            .iter()
+            .map(|(_, code)| run_with_hybrid_vm(code, 1, &calldata))
+            .collect();
+        let (before, after) = (gas_used[0], gas_used[1]);
+        println!(
+            "{method}: {before} gas -> {after} gas, {} saved ({:.1}%)",
+            before as i64 - after as i64,
+            (before as f64 - after as f64) * 100.0 / before as f64,
+        );
+
+        for (dispatch, code) in &runtimes {
+            group.bench_with_input(
+                BenchmarkId::new(*dispatch, method),
+                &(code.as_str(), calldata.as_str()),
+                |b, &(code, data)| {
+                    b.iter(|| {
+                        run_with_hybrid_vm(black_box(code), black_box(RUNS), black_box(data))
+                    });
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default()
+        .sample_size(10)
+        .measurement_time(std::time::Duration::from_secs(3))
+        .warm_up_time(std::time::Duration::from_secs(1));
+    targets = bench_erc20_dispatch
+);
+
+criterion_main!(benches);
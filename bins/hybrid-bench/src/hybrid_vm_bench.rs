@@ -12,7 +12,8 @@ use hybrid_vm::{
 use revm::primitives::TxKind;
 use std::hint::black_box;
 
-pub fn run_with_hybrid_vm(contract_code: &str, runs: u64, calldata: &str) {
+/// Calls `contract_code` with `calldata` `runs` times, returning the gas used by each call
+pub fn run_with_hybrid_vm(contract_code: &str, runs: u64, calldata: &str) -> u64 {
     let rich_acc_address = address!("1000000000000000000000000000000000000000");
     let bytes = hex::decode(contract_code).unwrap();
     let raw_bytecode = Bytecode::new_raw(bytes.clone().into());
@@ -29,8 +30,11 @@ pub fn run_with_hybrid_vm(contract_code: &str, runs: u64, calldata: &str) {
 
     let mut h_evm = HybridEvm(evm);
 
+    let mut gas_used = 0;
     for _ in 0..runs {
         let result = black_box(h_evm.replay()).unwrap();
         assert!(result.result.is_success(), "{:?}", result.result);
+        gas_used = result.result.gas_used();
     }
+    gas_used
 }
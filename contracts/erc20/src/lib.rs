@@ -4,7 +4,7 @@
 use alloy_core::primitives::{Address, U256};
 use core::default::Default;
 use hybrid_contract::hstd::*;
-use hybrid_derive::{contract, payable, storage, Error, Event};
+use hybrid_derive::{contract, guard, payable, storage, Error, Event};
 extern crate alloc;
 
 // -- EVENTS -------------------------------------------------------------------
@@ -75,11 +75,9 @@ impl ERC20 {
 
     // -- STATE MODIFYING FUNCTIONS --------------------------------------------
     #[payable]
+    #[guard(only_owner)]
     pub fn mint(&mut self, to: Address, amount: U256) -> Result<bool, ERC20Error> {
         // Perform sanity checks
-        if msg_sender() != self.owner.read() {
-            return Err(ERC20Error::OnlyOwner);
-        };
         if amount == U256::ZERO {
             return Err(ERC20Error::ZeroAmount);
         };
@@ -193,13 +191,11 @@ impl ERC20 {
         Ok(true)
     }
 
+    #[guard(only_owner)]
     pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<bool, ERC20Error> {
         let from = msg_sender();
 
         // Perform safety check
-        if from != self.owner.read() {
-            return Err(ERC20Error::OnlyOwner);
-        };
         if from == new_owner {
             return Err(ERC20Error::SelfTransfer);
         };
@@ -228,4 +224,12 @@ impl ERC20 {
     pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
         self.allowance_of[owner][spender].read()
     }
+
+    // -- GUARDS ---------------------------------------------------------------
+    fn only_owner(&self) -> Result<(), ERC20Error> {
+        match msg_sender() == self.owner.read() {
+            true => Ok(()),
+            false => Err(ERC20Error::OnlyOwner),
+        }
+    }
 }
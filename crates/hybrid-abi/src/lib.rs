@@ -0,0 +1,391 @@
+//! ABI and storage primitives shared by contracts (`hybrid-contract`), the contract macros
+//! (`hybrid-derive`) and the compiler (`hybrid-compile`), so each computes them the same way.
+//!
+//! - [`Keccak`]: a `const` Keccak-256 hasher, for selectors and storage locations computed
+//!   at compile time
+//! - [`erc7201_root`]: the storage location of an ERC-7201 namespace
+//! - [`ValueType`]: the Solidity value type of a Rust type, as used by selectors, the JSON ABI
+//!   and the storage layout
+//!
+//! ## Features
+//!
+//! - `syn`: [`ValueType::from_type`], mapping parsed Rust types
+
+#![no_std]
+
+#[cfg(feature = "syn")]
+extern crate alloc;
+
+use core::fmt;
+
+/// A Solidity value type.
+///
+/// Rust types are mapped by name, the way `alloy_primitives` names them:
+///
+/// | Rust                                   | Solidity           |
+/// |----------------------------------------|--------------------|
+/// | `Address`                              | `address`          |
+/// | `bool`, `Bool`                         | `bool`             |
+/// | `String`, `str`                        | `string`           |
+/// | `Bytes`                                | `bytes`            |
+/// | `Function`                             | `function`         |
+/// | `u8`..`u128`, `U8`..`U256`             | `uint8`..`uint256` |
+/// | `i8`..`i128`, `I8`..`I256`             | `int8`..`int256`   |
+/// | `B<bits>` (`B32`, `B256`, ...)         | `bytes<bits / 8>`  |
+/// | `FixedBytes<N>`                        | `bytes<N>`         |
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueType {
+    Address,
+    Bool,
+    String,
+    Bytes,
+    Function,
+    /// `bytes<N>`, with the size in bytes
+    FixedBytes(usize),
+    /// `uint<N>`, with the size in bits
+    Uint(usize),
+    /// `int<N>`, with the size in bits
+    Int(usize),
+}
+
+impl ValueType {
+    /// Maps the name of a non-generic Rust type, e.g. `U256` or `B32`.
+    pub fn from_rust_name(name: &str) -> Option<Self> {
+        match name {
+            "Address" => return Some(Self::Address),
+            "bool" | "Bool" => return Some(Self::Bool),
+            "String" | "str" => return Some(Self::String),
+            "Bytes" => return Some(Self::Bytes),
+            "Function" => return Some(Self::Function),
+            _ => {}
+        }
+
+        let (prefix, bits) = (name.get(..1)?, &name[1..]);
+        if bits.starts_with('0') {
+            return None;
+        }
+        let bits: usize = bits.parse().ok()?;
+        // A whole number of bytes, from 1 to 32
+        let bytes = bits / 8;
+        if bytes * 8 != bits || !(1..=32).contains(&bytes) {
+            return None;
+        }
+        match prefix {
+            "U" => Some(Self::Uint(bits)),
+            "I" => Some(Self::Int(bits)),
+            "u" if bits <= 128 => Some(Self::Uint(bits)),
+            "i" if bits <= 128 => Some(Self::Int(bits)),
+            "B" => Some(Self::FixedBytes(bytes)),
+            _ => None,
+        }
+    }
+
+    /// Maps `FixedBytes<size>`.
+    pub fn fixed_bytes(size: usize) -> Option<Self> {
+        (1..=32).contains(&size).then_some(Self::FixedBytes(size))
+    }
+
+    /// Maps a parsed Rust type. Only the last segment of a path is considered, so
+    /// `alloy_core::primitives::U256` maps like `U256`.
+    #[cfg(feature = "syn")]
+    pub fn from_type(ty: &syn::Type) -> Option<Self> {
+        use alloc::string::ToString;
+
+        let syn::Type::Path(type_path) = ty else {
+            return None;
+        };
+        let segment = type_path.path.segments.last()?;
+        match &segment.arguments {
+            syn::PathArguments::None => Self::from_rust_name(&segment.ident.to_string()),
+            syn::PathArguments::AngleBracketed(args) if segment.ident == "FixedBytes" => {
+                match args.args.first()? {
+                    syn::GenericArgument::Const(syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(size),
+                        ..
+                    })) => Self::fixed_bytes(size.base10_parse().ok()?),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Number of bytes the value occupies when packed in storage, or `None` for the dynamic
+    /// `string` and `bytes`, which are not stored in place.
+    pub fn packed_size(&self) -> Option<usize> {
+        match self {
+            Self::Address => Some(20),
+            Self::Bool => Some(1),
+            Self::Function => Some(24),
+            Self::FixedBytes(size) => Some(*size),
+            Self::Uint(bits) | Self::Int(bits) => Some(bits / 8),
+            Self::String | Self::Bytes => None,
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    /// Writes the Solidity name, e.g. `uint256` or `bytes4`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address => f.write_str("address"),
+            Self::Bool => f.write_str("bool"),
+            Self::String => f.write_str("string"),
+            Self::Bytes => f.write_str("bytes"),
+            Self::Function => f.write_str("function"),
+            Self::FixedBytes(size) => write!(f, "bytes{}", size),
+            Self::Uint(bits) => write!(f, "uint{}", bits),
+            Self::Int(bits) => write!(f, "int{}", bits),
+        }
+    }
+}
+
+/// Computes the ERC-7201 storage root of a namespace id:
+/// `keccak256(abi.encode(uint256(keccak256(id)) - 1)) & ~bytes32(uint256(0xff))`.
+///
+/// The lowest byte is cleared so that fields can be placed at `root + offset` without
+/// colliding with other namespaces.
+///
+/// # Returns
+/// The root as a big-endian 32-byte word
+pub const fn erc7201_root(namespace: &str) -> [u8; 32] {
+    let mut id_hash = Keccak::new().update(namespace.as_bytes()).finalize();
+
+    // Subtract one from the big-endian hash
+    let mut i = 32;
+    while i > 0 {
+        i -= 1;
+        let (byte, borrow) = id_hash[i].overflowing_sub(1);
+        id_hash[i] = byte;
+        if !borrow {
+            break;
+        }
+    }
+
+    let mut root = Keccak::new().update(&id_hash).finalize();
+    root[31] = 0;
+    root
+}
+
+/// A `const` Keccak-256 hasher, absorbing input byte by byte.
+///
+/// ```rust
+/// use hybrid_abi::Keccak;
+///
+/// const HASH: [u8; 32] = Keccak::new().update(b"transfer").update(b"(address,uint256)").finalize();
+/// assert_eq!(HASH[..4], [0xa9, 0x05, 0x9c, 0xbb]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Keccak {
+    state: [u64; 25],
+    offset: usize,
+}
+
+impl Default for Keccak {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keccak {
+    /// Creates a hasher with no input absorbed.
+    pub const fn new() -> Self {
+        Self {
+            state: [0; 25],
+            offset: 0,
+        }
+    }
+
+    /// Absorbs `bytes`, following the input absorbed so far.
+    pub const fn update(mut self, bytes: &[u8]) -> Self {
+        let mut i = 0;
+        while i < bytes.len() {
+            self.state[self.offset / 8] ^= (bytes[i] as u64) << (8 * (self.offset % 8));
+            self.offset += 1;
+            if self.offset == RATE {
+                self.state = keccak_f(self.state);
+                self.offset = 0;
+            }
+            i += 1;
+        }
+        self
+    }
+
+    /// Pads the input and returns the hash.
+    pub const fn finalize(mut self) -> [u8; 32] {
+        self.state[self.offset / 8] ^= 0x01 << (8 * (self.offset % 8));
+        self.state[(RATE - 1) / 8] ^= 0x80 << (8 * ((RATE - 1) % 8));
+        let state = keccak_f(self.state);
+
+        let mut hash = [0u8; 32];
+        let mut i = 0;
+        while i < 32 {
+            hash[i] = (state[i / 8] >> (8 * (i % 8))) as u8;
+            i += 1;
+        }
+        hash
+    }
+}
+
+/// Keccak-256 rate, in bytes.
+const RATE: usize = 136;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+const ROTATIONS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// The Keccak-f[1600] permutation.
+const fn keccak_f(mut state: [u64; 25]) -> [u64; 25] {
+    let mut round = 0;
+    while round < 24 {
+        // θ
+        let mut columns = [0u64; 5];
+        let mut x = 0;
+        while x < 5 {
+            columns[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+            x += 1;
+        }
+        x = 0;
+        while x < 5 {
+            let t = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            let mut y = 0;
+            while y < 25 {
+                state[y + x] ^= t;
+                y += 5;
+            }
+            x += 1;
+        }
+
+        // ρ and π
+        let mut last = state[1];
+        let mut i = 0;
+        while i < 24 {
+            let lane = PI_LANES[i];
+            let next = state[lane];
+            state[lane] = last.rotate_left(ROTATIONS[i]);
+            last = next;
+            i += 1;
+        }
+
+        // χ
+        let mut y = 0;
+        while y < 25 {
+            let row = [
+                state[y],
+                state[y + 1],
+                state[y + 2],
+                state[y + 3],
+                state[y + 4],
+            ];
+            x = 0;
+            while x < 5 {
+                state[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+                x += 1;
+            }
+            y += 5;
+        }
+
+        // ι
+        state[0] ^= ROUND_CONSTANTS[round];
+        round += 1;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::string::ToString;
+
+    fn word(bytes: &[u8]) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word.copy_from_slice(bytes);
+        word
+    }
+
+    #[test]
+    fn test_keccak() {
+        // keccak256("")
+        assert_eq!(
+            Keccak::new().finalize(),
+            word(&[
+                0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7,
+                0x03, 0xc0, 0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04,
+                0x5d, 0x85, 0xa4, 0x70
+            ])
+        );
+
+        // Input longer than the rate, split across updates
+        let input = [0xab; 300];
+        assert_eq!(
+            Keccak::new()
+                .update(&input[..7])
+                .update(&input[7..])
+                .finalize(),
+            Keccak::new().update(&input).finalize()
+        );
+    }
+
+    #[test]
+    fn test_value_type() {
+        let name = |name: &str| ValueType::from_rust_name(name).map(|ty| ty.to_string());
+        assert_eq!(name("Address").as_deref(), Some("address"));
+        assert_eq!(name("str").as_deref(), Some("string"));
+        assert_eq!(name("u64").as_deref(), Some("uint64"));
+        assert_eq!(name("I128").as_deref(), Some("int128"));
+        assert_eq!(name("U256").as_deref(), Some("uint256"));
+        assert_eq!(name("u256"), None);
+        assert_eq!(name("U7"), None);
+        assert_eq!(name("U0"), None);
+        assert_eq!(name("Vec"), None);
+        assert_eq!(ValueType::fixed_bytes(33), None);
+        assert_eq!(ValueType::Address.packed_size(), Some(20));
+        assert_eq!(ValueType::String.packed_size(), None);
+    }
+
+    #[test]
+    fn test_erc7201_root() {
+        // The example of ERC-7201
+        assert_eq!(
+            erc7201_root("example.main"),
+            word(&[
+                0x18, 0x3a, 0x61, 0x25, 0xc3, 0x88, 0x40, 0x42, 0x4c, 0x4a, 0x85, 0xfa, 0x12, 0xba,
+                0xb2, 0xab, 0x60, 0x6c, 0x4b, 0x6d, 0x0e, 0x7c, 0xc7, 0x3c, 0x0c, 0x06, 0xba, 0x53,
+                0x00, 0xea, 0xb5, 0x00
+            ])
+        );
+    }
+}
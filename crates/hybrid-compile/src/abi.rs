@@ -0,0 +1,857 @@
+//! # Contract ABI
+//!
+//! Generation of the JSON ABI describing how to call a contract, in the format `solc --abi`
+//! produces, so frontends, `ethers`/`alloy` bindings and block explorers can interact with
+//! Hybrid contracts without hand-written ABIs.
+//!
+//! ## How It Works
+//!
+//! The ABI is derived from the contract source, the same way the contract itself is found:
+//! 1. **Source Parsing**: `src/lib.rs` and the modules it declares are parsed with `syn`
+//! 2. **Function Discovery**: The `pub` methods of the `#[contract]` impl become functions
+//!    and `new` becomes the constructor
+//! 3. **Event and Error Discovery**: `#[derive(Event)]` structs and `#[derive(Error)]` enums
+//!    are described with the signatures the derive macros hash
+//! 4. **Type Resolution**: `#[derive(SolStruct)]` structs become `tuple` parameters with one
+//!    component per field, `#[derive(SolEnum)]` enums become `uint8` and value types are
+//!    named with [`ValueType`]
+//!
+//! Functions keep their Rust names, as the `#[contract]` dispatch computes selectors from
+//! them, unless renamed with `#[selector(name = "...")]`. Methods with a raw
+//! `#[selector(0x...)]` have no signature to describe and are left out. State mutability follows the receiver: `&self` methods are `view`, `#[payable]`
+//! methods are `payable` and the rest are `nonpayable`. `#[fallback]` and `#[receive]`
+//! methods become the `fallback` and `receive` entries.
+//!
+//! ## Output Format
+//!
+//! ```json
+//! [
+//!   { "type": "constructor", "inputs": [{ "name": "owner", "type": "address" }], "stateMutability": "nonpayable" },
+//!   { "type": "function", "name": "balance_of", "inputs": [{ "name": "owner", "type": "address" }], "outputs": [{ "name": "", "type": "uint256" }], "stateMutability": "view" },
+//!   { "type": "event", "name": "Transfer", "inputs": [{ "name": "from", "type": "address", "indexed": true }], "anonymous": false },
+//!   { "type": "error", "name": "ZeroAmount", "inputs": [] }
+//! ]
+//! ```
+use hybrid_abi::ValueType;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+use syn::{
+    Attribute, Fields, FnArg, GenericArgument, ImplItem, ImplItemMethod, Item, ItemEnum, ItemImpl,
+    ItemStruct, Lit, Meta, NestedMeta, Pat, PathArguments, ReturnType, Type,
+};
+
+use crate::utils::{crate_items, source_items, SourceItem};
+
+/// An entry of a contract's JSON ABI.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AbiItem {
+    /// The contract's constructor, `new`
+    Constructor {
+        /// Constructor arguments
+        inputs: Vec<Param>,
+        /// Whether the constructor accepts ether
+        #[serde(rename = "stateMutability")]
+        state_mutability: StateMutability,
+    },
+    /// A public method of the `#[contract]` impl
+    Function {
+        /// Method name, as hashed into the selector
+        name: String,
+        /// Method arguments, without the receiver
+        inputs: Vec<Param>,
+        /// Return value, empty for methods returning `()`
+        outputs: Vec<Param>,
+        /// Whether the method reads, writes or accepts ether
+        #[serde(rename = "stateMutability")]
+        state_mutability: StateMutability,
+    },
+    /// The `#[fallback]` method, called for calldata matching no function
+    Fallback {
+        /// Whether the fallback accepts ether
+        #[serde(rename = "stateMutability")]
+        state_mutability: StateMutability,
+    },
+    /// The `#[receive]` method, called for plain ether transfers
+    Receive {
+        /// Always `payable`
+        #[serde(rename = "stateMutability")]
+        state_mutability: StateMutability,
+    },
+    /// A `#[derive(Event)]` struct
+    Event {
+        /// Event name
+        name: String,
+        /// Event fields, in declaration order
+        inputs: Vec<EventParam>,
+        /// Whether the event omits its signature topic
+        anonymous: bool,
+    },
+    /// A variant of a `#[derive(Error)]` enum
+    Error {
+        /// Variant name
+        name: String,
+        /// Variant fields
+        inputs: Vec<Param>,
+    },
+}
+
+/// How a function interacts with the chain state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateMutability {
+    /// Reads state, may not modify it
+    View,
+    /// Modifies state, rejects ether
+    NonPayable,
+    /// Modifies state, accepts ether
+    Payable,
+}
+
+/// A function, constructor or error parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Param {
+    /// Parameter name, empty for return values and tuple fields
+    pub name: String,
+    /// Solidity type, `tuple` (or `tuple[]`, ...) for tuples
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// Fields of a tuple type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<Param>>,
+    /// Name of a `SolStruct` or `SolEnum` type, as `struct Name` or `enum Name`
+    #[serde(rename = "internalType", skip_serializing_if = "Option::is_none")]
+    pub internal_type: Option<String>,
+}
+
+/// An event parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EventParam {
+    #[serde(flatten)]
+    pub param: Param,
+    /// Whether the field is a topic rather than part of the log data
+    pub indexed: bool,
+}
+
+/// Builds the ABI of the contract whose root module is at `file_path`.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the contract's `src/lib.rs`
+///
+/// # Returns
+///
+/// Returns `Ok(None)` if the crate has no `#[contract]` impl, or an error if a module cannot
+/// be parsed or a parameter has a type without a Solidity equivalent.
+pub fn find_abi(file_path: &Path) -> Result<Option<Vec<AbiItem>>, anyhow::Error> {
+    abi_from_items(&crate_items(file_path)?)
+}
+
+/// The `#[derive(SolStruct)]` structs and `#[derive(SolEnum)]` enums of a contract.
+#[derive(Default)]
+struct UserTypes<'a> {
+    structs: HashMap<String, &'a ItemStruct>,
+    enums: HashSet<String>,
+}
+
+/// Builds the ABI of the contract declared in `source`.
+pub fn abi_from_source(source: &str) -> Result<Option<Vec<AbiItem>>, anyhow::Error> {
+    abi_from_items(&source_items(source, "src/lib.rs")?)
+}
+
+/// Builds the ABI of the contract declared by the items of a crate.
+fn abi_from_items(items: &[SourceItem]) -> Result<Option<Vec<AbiItem>>, anyhow::Error> {
+    let mut types = UserTypes::default();
+    for SourceItem { item, .. } in items {
+        match item {
+            Item::Struct(item_struct) if derives(&item_struct.attrs, "SolStruct") => {
+                types
+                    .structs
+                    .insert(item_struct.ident.to_string(), item_struct);
+            }
+            Item::Enum(item_enum) if derives(&item_enum.attrs, "SolEnum") => {
+                types.enums.insert(item_enum.ident.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    let mut contract = None;
+    let mut events = Vec::new();
+    let mut errors = Vec::new();
+    for SourceItem { item, .. } in items {
+        match item {
+            Item::Impl(item_impl)
+                if item_impl.attrs.iter().any(|a| is_attribute(a, "contract")) =>
+            {
+                contract = Some(item_impl)
+            }
+            Item::Struct(item_struct) if derives(&item_struct.attrs, "Event") => {
+                events.push(event_item(item_struct, &types)?)
+            }
+            Item::Enum(item_enum) if derives(&item_enum.attrs, "Error") => {
+                errors.extend(error_items(item_enum, &types)?)
+            }
+            _ => {}
+        }
+    }
+
+    let Some(contract) = contract else {
+        return Ok(None);
+    };
+
+    let mut abi = contract_items(contract, &types)?;
+    abi.extend(events);
+    abi.extend(errors);
+    Ok(Some(abi))
+}
+
+/// Describes the constructor, public methods, fallback and receive of a `#[contract]` impl.
+fn contract_items(item_impl: &ItemImpl, types: &UserTypes) -> Result<Vec<AbiItem>, anyhow::Error> {
+    let mut constructor = None;
+    let mut functions = Vec::new();
+    let mut special = Vec::new();
+
+    for item in &item_impl.items {
+        let ImplItem::Method(method) = item else {
+            continue;
+        };
+        let payable = method.attrs.iter().any(|a| is_attribute(a, "payable"));
+
+        if method.attrs.iter().any(|a| is_attribute(a, "fallback")) {
+            special.push(AbiItem::Fallback {
+                state_mutability: match payable {
+                    true => StateMutability::Payable,
+                    false => StateMutability::NonPayable,
+                },
+            });
+        } else if method.attrs.iter().any(|a| is_attribute(a, "receive")) {
+            special.push(AbiItem::Receive {
+                state_mutability: StateMutability::Payable,
+            });
+        } else if method.sig.ident == "new" {
+            constructor = Some(AbiItem::Constructor {
+                inputs: inputs(method, types)?,
+                state_mutability: match payable {
+                    true => StateMutability::Payable,
+                    false => StateMutability::NonPayable,
+                },
+            });
+        } else if let syn::Visibility::Public(_) = method.vis {
+            let Some(name) = function_name(method) else {
+                continue;
+            };
+            let state_mutability = match method.sig.receiver() {
+                _ if payable => StateMutability::Payable,
+                Some(FnArg::Receiver(receiver)) if receiver.mutability.is_none() => {
+                    StateMutability::View
+                }
+                _ => StateMutability::NonPayable,
+            };
+            functions.push(AbiItem::Function {
+                name,
+                inputs: inputs(method, types)?,
+                outputs: outputs(&method.sig.output, types)?,
+                state_mutability,
+            });
+        }
+    }
+
+    Ok(constructor
+        .into_iter()
+        .chain(functions)
+        .chain(special)
+        .collect())
+}
+
+/// The name a `pub` method is dispatched under: that of its `#[selector(name = "...")]`, else
+/// its Rust name. `None` for a raw `#[selector(0x...)]`.
+fn function_name(method: &ImplItemMethod) -> Option<String> {
+    let Some(attr) = method.attrs.iter().find(|a| is_attribute(a, "selector")) else {
+        return Some(method.sig.ident.to_string());
+    };
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => list.nested.iter().find_map(|nested| match nested {
+            NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("name") => {
+                match &pair.lit {
+                    Lit::Str(name) => Some(name.value()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Describes the typed arguments of a method, skipping the receiver.
+fn inputs(method: &ImplItemMethod, types: &UserTypes) -> Result<Vec<Param>, anyhow::Error> {
+    method
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(pat_type) => Some(pat_type),
+            FnArg::Receiver(_) => None,
+        })
+        .map(|pat_type| {
+            let name = match &*pat_type.pat {
+                Pat::Ident(pat) => pat.ident.to_string(),
+                _ => String::new(),
+            };
+            param(name, &pat_type.ty, types)
+        })
+        .collect()
+}
+
+/// Describes the value returned on success: the `T` of `Result<T, E>`, `CallResult<T, E>` or
+/// `Option<T>`.
+fn outputs(output: &ReturnType, types: &UserTypes) -> Result<Vec<Param>, anyhow::Error> {
+    let ReturnType::Type(_, ty) = output else {
+        return Ok(Vec::new());
+    };
+
+    let ty = match generic_args(ty) {
+        Some((name, args)) if matches!(name.as_str(), "Result" | "CallResult" | "Option") => args
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("missing {} type argument", name))?,
+        _ => (**ty).clone(),
+    };
+
+    match &ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Ok(Vec::new()),
+        ty => Ok(vec![param(String::new(), ty, types)?]),
+    }
+}
+
+/// Describes a `#[derive(Event)]` struct.
+fn event_item(item_struct: &ItemStruct, types: &UserTypes) -> Result<AbiItem, anyhow::Error> {
+    let Fields::Named(fields) = &item_struct.fields else {
+        anyhow::bail!("event {} must have named fields", item_struct.ident);
+    };
+
+    let inputs = fields
+        .named
+        .iter()
+        .map(|field| {
+            Ok(EventParam {
+                param: param(field.ident.as_ref().unwrap().to_string(), &field.ty, types)?,
+                indexed: field.attrs.iter().any(|a| is_attribute(a, "indexed")),
+            })
+        })
+        .collect::<Result<_, anyhow::Error>>()?;
+
+    Ok(AbiItem::Event {
+        name: item_struct.ident.to_string(),
+        inputs,
+        anonymous: item_struct.attrs.iter().any(is_anonymous),
+    })
+}
+
+/// Describes the variants of a `#[derive(Error)]` enum, one error each.
+fn error_items(item_enum: &ItemEnum, types: &UserTypes) -> Result<Vec<AbiItem>, anyhow::Error> {
+    item_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let inputs = match &variant.fields {
+                Fields::Unit => Vec::new(),
+                Fields::Unnamed(fields) => fields
+                    .unnamed
+                    .iter()
+                    .map(|field| param(String::new(), &field.ty, types))
+                    .collect::<Result<_, _>>()?,
+                Fields::Named(_) => anyhow::bail!(
+                    "error {}::{} must not have named fields",
+                    item_enum.ident,
+                    variant.ident
+                ),
+            };
+            Ok(AbiItem::Error {
+                name: variant.ident.to_string(),
+                inputs,
+            })
+        })
+        .collect()
+}
+
+/// Describes a value of type `ty`, with the type mapping `#[contract]` uses for selectors.
+fn param(name: String, ty: &Type, types: &UserTypes) -> Result<Param, anyhow::Error> {
+    match ty {
+        Type::Tuple(tuple) => {
+            let components = tuple
+                .elems
+                .iter()
+                .map(|elem| param(String::new(), elem, types))
+                .collect::<Result<_, _>>()?;
+            Ok(tuple_param(name, components, None))
+        }
+        Type::Array(array) => {
+            let size: usize = match &array.len {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: Lit::Int(size),
+                    ..
+                }) => size.base10_parse()?,
+                _ => anyhow::bail!("array length must be an integer literal"),
+            };
+            Ok(array_param(
+                name,
+                param(String::new(), &array.elem, types)?,
+                &format!("[{}]", size),
+            ))
+        }
+        Type::Path(_) => {
+            let (type_name, args) =
+                generic_args(ty).ok_or_else(|| anyhow::anyhow!("unsupported type"))?;
+            if type_name == "Vec" {
+                let inner = args
+                    .first()
+                    .ok_or_else(|| anyhow::anyhow!("missing Vec type argument"))?;
+                return Ok(array_param(name, param(String::new(), inner, types)?, "[]"));
+            }
+
+            if let Some(item_struct) = types.structs.get(&type_name) {
+                let components = item_struct
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let name = field
+                            .ident
+                            .as_ref()
+                            .map(ToString::to_string)
+                            .unwrap_or_default();
+                        param(name, &field.ty, types)
+                    })
+                    .collect::<Result<_, _>>()?;
+                let internal_type = format!("struct {}", type_name);
+                return Ok(tuple_param(name, components, Some(internal_type)));
+            }
+
+            let (ty, internal_type) = match types.enums.contains(&type_name) {
+                true => ("uint8".to_string(), Some(format!("enum {}", type_name))),
+                false => match ValueType::from_type(ty) {
+                    Some(value) => (value.to_string(), None),
+                    None => anyhow::bail!("unsupported type {}", type_name),
+                },
+            };
+            Ok(Param {
+                name,
+                ty,
+                components: None,
+                internal_type,
+            })
+        }
+        _ => anyhow::bail!("unsupported type"),
+    }
+}
+
+/// Describes a tuple, or a `SolStruct` encoded as one.
+fn tuple_param(name: String, components: Vec<Param>, internal_type: Option<String>) -> Param {
+    Param {
+        name,
+        ty: "tuple".to_string(),
+        components: Some(components),
+        internal_type,
+    }
+}
+
+/// Describes an array of `inner`, with `suffix` `[]` or `[N]`.
+fn array_param(name: String, inner: Param, suffix: &str) -> Param {
+    Param {
+        name,
+        ty: format!("{}{}", inner.ty, suffix),
+        components: inner.components,
+        internal_type: inner.internal_type.map(|ty| format!("{}{}", ty, suffix)),
+    }
+}
+
+/// Returns the name of a type path and its generic type arguments.
+fn generic_args(ty: &Type) -> Option<(String, Vec<Type>)> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Some((segment.ident.to_string(), args))
+}
+
+/// Checks whether an attribute is `#[name]` or `#[name(...)]`.
+fn is_attribute(attr: &Attribute, name: &str) -> bool {
+    attr.path.segments.len() == 1 && attr.path.segments[0].ident == name
+}
+
+/// Checks whether an attribute is `#[event(anonymous)]`.
+fn is_anonymous(attr: &Attribute) -> bool {
+    is_attribute(attr, "event")
+        && attr
+            .parse_args::<syn::Ident>()
+            .is_ok_and(|ident| ident == "anonymous")
+}
+
+/// Checks whether `#[derive(...)]` attributes include the derive macro `name`.
+fn derives(attrs: &[Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| is_attribute(attr, "derive"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(list) => list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(meta) => {
+                    meta.path().segments.last().is_some_and(|s| s.ident == name)
+                }
+                _ => false,
+            }),
+            _ => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::keccak256;
+    use serde_json::json;
+
+    const ERC20: &str = r#"
+        #[derive(Event)]
+        pub struct Transfer {
+            #[indexed]
+            pub from: Address,
+            #[indexed]
+            pub to: Address,
+            pub amount: U256,
+        }
+
+        #[derive(Error)]
+        pub enum ERC20Error {
+            OnlyOwner,
+            InsufficientBalance(U256),
+        }
+
+        #[storage]
+        pub struct ERC20 {
+            balance_of: Mapping<Address, Slot<U256>>,
+        }
+
+        #[contract]
+        impl ERC20 {
+            pub fn new(owner: Address) -> Self {
+                ERC20::default()
+            }
+
+            #[payable]
+            pub fn mint(&mut self, to: Address, amount: U256) -> Result<bool, ERC20Error> {
+                Ok(true)
+            }
+
+            pub fn transfer(&mut self, to: Address, amount: U256) -> Result<(), ERC20Error> {
+                Ok(())
+            }
+
+            pub fn balance_of(&self, owner: Address) -> U256 {
+                self.balance_of[owner].read()
+            }
+
+            pub fn holders(&self) -> Option<(Vec<Address>, [B256; 2])> {
+                None
+            }
+
+            fn internal(&self) {}
+        }
+    "#;
+
+    fn abi() -> serde_json::Value {
+        serde_json::to_value(abi_from_source(ERC20).unwrap().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_erc20_abi() {
+        let abi = abi();
+        assert_eq!(
+            abi[0],
+            json!({
+                "type": "constructor",
+                "inputs": [{ "name": "owner", "type": "address" }],
+                "stateMutability": "nonpayable"
+            })
+        );
+        assert_eq!(
+            abi[1],
+            json!({
+                "type": "function",
+                "name": "mint",
+                "inputs": [
+                    { "name": "to", "type": "address" },
+                    { "name": "amount", "type": "uint256" }
+                ],
+                "outputs": [{ "name": "", "type": "bool" }],
+                "stateMutability": "payable"
+            })
+        );
+        assert_eq!(abi[2]["outputs"], json!([]));
+        assert_eq!(abi[2]["stateMutability"], "nonpayable");
+        assert_eq!(abi[3]["stateMutability"], "view");
+
+        // Private methods are not part of the ABI
+        let names: Vec<_> = abi
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|item| item["name"].as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "mint",
+                "transfer",
+                "balance_of",
+                "holders",
+                "Transfer",
+                "OnlyOwner",
+                "InsufficientBalance"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tuple_output() {
+        assert_eq!(
+            abi()[4]["outputs"],
+            json!([{
+                "name": "",
+                "type": "tuple",
+                "components": [
+                    { "name": "", "type": "address[]" },
+                    { "name": "", "type": "bytes32[2]" }
+                ]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_call_result_output() {
+        let source = r#"
+            #[contract]
+            impl Router {
+                pub fn swap(&mut self, amount: U256) -> CallResult<U256, PoolError> {
+                    self.pool().swap(amount)
+                }
+            }
+        "#;
+        let abi = serde_json::to_value(abi_from_source(source).unwrap().unwrap()).unwrap();
+        assert_eq!(
+            abi[0]["outputs"],
+            json!([{ "name": "", "type": "uint256" }])
+        );
+    }
+
+    #[test]
+    fn test_events_and_errors() {
+        let abi = abi();
+        assert_eq!(
+            abi[5],
+            json!({
+                "type": "event",
+                "name": "Transfer",
+                "inputs": [
+                    { "name": "from", "type": "address", "indexed": true },
+                    { "name": "to", "type": "address", "indexed": true },
+                    { "name": "amount", "type": "uint256", "indexed": false }
+                ],
+                "anonymous": false
+            })
+        );
+        assert_eq!(
+            abi[6],
+            json!({ "type": "error", "name": "OnlyOwner", "inputs": [] })
+        );
+        assert_eq!(abi[7]["inputs"], json!([{ "name": "", "type": "uint256" }]));
+    }
+
+    #[test]
+    fn test_selectors_match_signatures() {
+        // The selector tools derive from the ABI is the one `#[contract]` dispatches on
+        let abi = abi_from_source(ERC20).unwrap().unwrap();
+        let AbiItem::Function { name, inputs, .. } = &abi[1] else {
+            panic!("expected a function");
+        };
+        let types: Vec<_> = inputs.iter().map(|p| p.ty.as_str()).collect();
+        let signature = format!("{}({})", name, types.join(","));
+        assert_eq!(signature, "mint(address,uint256)");
+        assert_eq!(keccak256(signature)[..4], [0x40, 0xc1, 0x0f, 0x19]);
+    }
+
+    #[test]
+    fn test_fixed_bytes_sized_in_bits() {
+        let abi = abi_from_source(
+            r#"
+            #[contract]
+            impl Registry {
+                pub fn register(&mut self, id: B32, root: B256, owner: FixedBytes<20>) {}
+            }
+            "#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&abi[0]).unwrap()["inputs"],
+            json!([
+                { "name": "id", "type": "bytes4" },
+                { "name": "root", "type": "bytes32" },
+                { "name": "owner", "type": "bytes20" }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_selector_overrides() {
+        let abi = abi_from_source(
+            r#"
+            #[contract]
+            impl Token {
+                #[selector(name = "transfer")]
+                pub fn transfer_with_data(&mut self, to: Address, data: Bytes) {}
+
+                #[selector(0x23b872dd)]
+                pub fn move_from(&mut self, from: Address, to: Address) {}
+            }
+            "#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(abi.len(), 1);
+        assert!(matches!(&abi[0], AbiItem::Function { name, .. } if name == "transfer"));
+    }
+
+    #[test]
+    fn test_anonymous_event() {
+        let abi = abi_from_source(
+            r#"
+            #[derive(Event)]
+            #[event(anonymous)]
+            pub struct Ping {
+                #[indexed]
+                pub from: Address,
+            }
+
+            #[contract]
+            impl Pinger {}
+            "#,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(matches!(
+            abi[0],
+            AbiItem::Event {
+                anonymous: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_fallback_and_receive() {
+        let abi = abi_from_source(
+            r#"
+            #[contract]
+            impl Proxy {
+                #[fallback]
+                pub fn fallback(&mut self, input: &[u8]) -> Bytes {
+                    Bytes::new()
+                }
+
+                #[receive]
+                fn receive(&mut self) {}
+            }
+            "#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(abi).unwrap(),
+            json!([
+                { "type": "fallback", "stateMutability": "nonpayable" },
+                { "type": "receive", "stateMutability": "payable" }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_struct_and_enum_params() {
+        let abi = abi_from_source(
+            r#"
+            #[contract]
+            impl Exchange {
+                pub fn open(&mut self, orders: Vec<Order>) -> Side {
+                    Side::Long
+                }
+            }
+
+            #[derive(SolStruct)]
+            pub struct Order {
+                pub owner: Address,
+                pub side: Side,
+            }
+
+            #[derive(SolEnum, Clone, Copy)]
+            pub enum Side {
+                Long,
+                Short,
+            }
+            "#,
+        )
+        .unwrap()
+        .unwrap();
+        let abi = serde_json::to_value(abi).unwrap();
+        assert_eq!(
+            abi[0]["inputs"],
+            json!([{
+                "name": "orders",
+                "type": "tuple[]",
+                "components": [
+                    { "name": "owner", "type": "address" },
+                    { "name": "side", "type": "uint8", "internalType": "enum Side" }
+                ],
+                "internalType": "struct Order[]"
+            }])
+        );
+        assert_eq!(
+            abi[0]["outputs"],
+            json!([{ "name": "", "type": "uint8", "internalType": "enum Side" }])
+        );
+    }
+
+    #[test]
+    fn test_no_contract() {
+        assert!(abi_from_source("pub struct NotAContract;")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_unsupported_type() {
+        let err = abi_from_source(
+            r#"
+            #[contract]
+            impl Token {
+                pub fn set(&mut self, value: HashMap<u8, u8>) {}
+            }
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unsupported type"));
+    }
+}
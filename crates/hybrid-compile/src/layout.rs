@@ -0,0 +1,655 @@
+//! # Storage Layout
+//!
+//! Generation of the `storageLayout` artifact describing where a contract keeps its state.
+//!
+//! The layout is written in the format `solc --storage-layout` uses, so tools built for
+//! Solidity contracts (indexers, upgrade-safety checkers, `eth_getStorageAt` explorers) can
+//! decode the state of Hybrid contracts as well.
+//!
+//! ## How It Works
+//!
+//! The `#[storage]` macro only sees the tokens of the struct it annotates, so it cannot tell
+//! how many slots a `Slot<S>` of a `StorageStruct` spans. The layout is therefore derived
+//! from the contract source instead:
+//! 1. **Source Parsing**: `src/lib.rs` and the modules it declares are parsed with `syn`
+//!    (see [`crate::utils::crate_items`])
+//! 2. **Struct Discovery**: The `#[storage]` struct and every `#[derive(StorageStruct)]`
+//!    struct are collected
+//! 3. **Slot Allocation**: Fields are placed with the same rules as `#[storage]` and
+//!    `StorageStruct`, rooted at the ERC-7201 location of the namespace, if any
+//!
+//! Value types are named with [`ValueType`], the mapping the macros use for selectors.
+//!
+//! ## Output Format
+//!
+//! ```json
+//! {
+//!   "storage": [
+//!     { "contract": "src/lib.rs:ERC20", "label": "balances", "offset": 0, "slot": "0", "type": "t_mapping(t_address,t_uint256)" }
+//!   ],
+//!   "types": {
+//!     "t_address": { "encoding": "inplace", "label": "address", "numberOfBytes": "20" },
+//!     "t_mapping(t_address,t_uint256)": { "encoding": "mapping", "key": "t_address", "label": "mapping(address => uint256)", "numberOfBytes": "32", "value": "t_uint256" },
+//!     "t_uint256": { "encoding": "inplace", "label": "uint256", "numberOfBytes": "32" }
+//!   }
+//! }
+//! ```
+//!
+//! Entries carry no `astId`, as there is no Solidity AST to refer to.
+use alloy_primitives::U256;
+use hybrid_abi::ValueType;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+use syn::{
+    Attribute, Fields, GenericArgument, Item, ItemStruct, Lit, Meta, NestedMeta, PathArguments,
+    Type,
+};
+
+use crate::utils::{crate_items, source_items, SourceItem};
+
+/// A contract's storage layout, in the format of `solc`'s `storageLayout` output.
+#[derive(Debug, Serialize)]
+pub struct StorageLayout {
+    /// State variables, in declaration order
+    pub storage: Vec<StorageEntry>,
+    /// Descriptions of the types referenced by the entries, keyed by type identifier
+    pub types: BTreeMap<String, TypeDescription>,
+}
+
+/// Location of a state variable or struct member.
+#[derive(Debug, Serialize)]
+pub struct StorageEntry {
+    /// Declaring struct, as `<file>:<name>`
+    pub contract: String,
+    /// Field name
+    pub label: String,
+    /// Offset in bytes from the lower-order end of the slot
+    pub offset: usize,
+    /// Slot number, as a decimal string
+    pub slot: String,
+    /// Type identifier, a key of [`StorageLayout::types`]
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// Description of a type referenced by the layout.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeDescription {
+    /// How the value is stored: `inplace`, `mapping`, `dynamic_array` or `bytes`
+    pub encoding: &'static str,
+    /// Solidity name of the type
+    pub label: String,
+    /// Bytes used in the slot(s) holding the value, as a decimal string
+    pub number_of_bytes: String,
+    /// Key type of a mapping
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Value type of a mapping
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Element type of a dynamic array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Members of a struct, with slots relative to the struct's first slot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<StorageEntry>>,
+}
+
+/// Builds the storage layout of the contract whose root module is at `file_path`.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the contract's `src/lib.rs`
+///
+/// # Returns
+///
+/// Returns `Ok(None)` if the crate declares no `#[storage]` struct, or an error if a module
+/// cannot be parsed or a field has a type whose layout is unknown.
+pub fn find_storage_layout(file_path: &Path) -> Result<Option<StorageLayout>, anyhow::Error> {
+    storage_layout_from_items(&crate_items(file_path)?)
+}
+
+/// Builds the storage layout declared in `source`, naming structs after `file_name`.
+pub fn storage_layout_from_source(
+    source: &str,
+    file_name: &str,
+) -> Result<Option<StorageLayout>, anyhow::Error> {
+    storage_layout_from_items(&source_items(source, file_name)?)
+}
+
+/// Builds the storage layout declared by the items of a crate.
+fn storage_layout_from_items(items: &[SourceItem]) -> Result<Option<StorageLayout>, anyhow::Error> {
+    let mut storage = None;
+    let mut structs = HashMap::new();
+    for SourceItem { file, item } in items {
+        if let Item::Struct(item_struct) = item {
+            if let Some(attr) = item_struct
+                .attrs
+                .iter()
+                .find(|a| is_attribute(a, "storage"))
+            {
+                storage = Some((storage_namespace(attr)?, file.as_str(), item_struct));
+            } else if derives_storage_struct(&item_struct.attrs) {
+                structs.insert(item_struct.ident.to_string(), (file.as_str(), item_struct));
+            }
+        }
+    }
+
+    let Some((namespace, file_name, item_struct)) = storage else {
+        return Ok(None);
+    };
+
+    let mut builder = LayoutBuilder {
+        structs: &structs,
+        types: BTreeMap::new(),
+    };
+
+    // Fields take consecutive slots from the root, as `#[storage]` allocates them
+    let root = namespace
+        .as_deref()
+        .map(|namespace| U256::from_be_bytes(hybrid_abi::erc7201_root(namespace)))
+        .unwrap_or_default();
+    let mut storage = Vec::new();
+    let mut slot = 0;
+    for (label, ty) in named_fields(item_struct)? {
+        let (ty, slots) = builder.storage_type(&ty)?;
+
+        // A `Slot` holds the ABI encoding of its value, which left-aligns fixed bytes
+        let description = &builder.types[&ty];
+        let offset = match description.encoding {
+            "inplace" if description.label.starts_with("bytes") => {
+                32 - description.number_of_bytes.parse::<usize>()?
+            }
+            _ => 0,
+        };
+
+        storage.push(StorageEntry {
+            contract: format!("{}:{}", file_name, item_struct.ident),
+            label,
+            offset,
+            slot: root.wrapping_add(U256::from(slot)).to_string(),
+            ty,
+        });
+        slot += slots;
+    }
+
+    Ok(Some(StorageLayout {
+        storage,
+        types: builder.types,
+    }))
+}
+
+/// Resolves Rust storage types to their Solidity type descriptions.
+struct LayoutBuilder<'a> {
+    /// Structs deriving `StorageStruct` and the files declaring them, by name
+    structs: &'a HashMap<String, (&'a str, &'a ItemStruct)>,
+    /// Types resolved so far
+    types: BTreeMap<String, TypeDescription>,
+}
+
+impl LayoutBuilder<'_> {
+    /// Resolves a storage type (`Slot`, `Mapping`, `StorageVec`, ...).
+    ///
+    /// # Returns
+    ///
+    /// The type identifier and the number of slots the type spans
+    fn storage_type(&mut self, ty: &Type) -> Result<(String, usize), anyhow::Error> {
+        let (name, args) = type_path(ty)?;
+        match (name.as_str(), args.as_slice()) {
+            ("Slot", [value]) => {
+                let (value_name, _) = type_path(value)?;
+                if self.structs.contains_key(&value_name) {
+                    self.struct_type(&value_name)
+                } else {
+                    Ok((self.value_type(value)?.0, 1))
+                }
+            }
+            ("Mapping", [key, value]) => {
+                let key = self.value_type(key)?.0;
+                let (value, _) = self.storage_type(value)?;
+                let label = format!(
+                    "mapping({} => {})",
+                    self.types[&key].label, self.types[&value].label
+                );
+                let id = format!("t_mapping({},{})", key, value);
+                self.insert(&id, "mapping", label, 32, |t| {
+                    t.key = Some(key);
+                    t.value = Some(value);
+                });
+                Ok((id, 1))
+            }
+            ("StorageVec", [element]) => {
+                let base = self.value_type(element)?.0;
+                let label = format!("{}[]", self.types[&base].label);
+                let id = format!("t_array({})dyn_storage", base);
+                self.insert(&id, "dynamic_array", label, 32, |t| t.base = Some(base));
+                Ok((id, 1))
+            }
+            ("StorageString", []) => {
+                self.insert("t_string_storage", "bytes", "string".into(), 32, |_| {});
+                Ok(("t_string_storage".into(), 1))
+            }
+            ("StorageBytes", []) => {
+                self.insert("t_bytes_storage", "bytes", "bytes".into(), 32, |_| {});
+                Ok(("t_bytes_storage".into(), 1))
+            }
+            _ => anyhow::bail!("unsupported storage type: {}", name),
+        }
+    }
+
+    /// Resolves a struct deriving `StorageStruct`, packing its fields like the derive does.
+    ///
+    /// # Returns
+    ///
+    /// The type identifier and the number of slots the struct spans
+    fn struct_type(&mut self, name: &str) -> Result<(String, usize), anyhow::Error> {
+        let (file_name, item_struct) = self.structs[name];
+        let contract = format!("{}:{}", file_name, name);
+
+        let mut members = Vec::new();
+        let (mut slot, mut offset) = (0, 0);
+        for (label, ty) in named_fields(item_struct)? {
+            let (ty, size) = self.value_type(&ty)?;
+            if offset + size > 32 {
+                slot += 1;
+                offset = 0;
+            }
+            members.push(StorageEntry {
+                contract: contract.clone(),
+                label,
+                offset,
+                slot: slot.to_string(),
+                ty,
+            });
+            offset += size;
+        }
+        let slots = if offset == 0 { slot } else { slot + 1 };
+
+        let id = format!("t_struct({})_storage", name);
+        self.insert(
+            &id,
+            "inplace",
+            format!("struct {}", name),
+            slots * 32,
+            |t| t.members = Some(members),
+        );
+        Ok((id, slots))
+    }
+
+    /// Resolves a value type stored in place.
+    ///
+    /// # Returns
+    ///
+    /// The type identifier and the size of the value in bytes
+    fn value_type(&mut self, ty: &Type) -> Result<(String, usize), anyhow::Error> {
+        let value_type = ValueType::from_type(ty);
+        let Some((label, size)) =
+            value_type.and_then(|value| Some((value.to_string(), value.packed_size()?)))
+        else {
+            anyhow::bail!("unsupported value type: {}", type_path(ty)?.0);
+        };
+
+        let id = format!("t_{}", label);
+        self.insert(&id, "inplace", label, size, |_| {});
+        Ok((id, size))
+    }
+
+    /// Registers a type description, unless a type with the same identifier already is.
+    fn insert(
+        &mut self,
+        id: &str,
+        encoding: &'static str,
+        label: String,
+        number_of_bytes: usize,
+        extend: impl FnOnce(&mut TypeDescription),
+    ) {
+        if self.types.contains_key(id) {
+            return;
+        }
+        let mut description = TypeDescription {
+            encoding,
+            label,
+            number_of_bytes: number_of_bytes.to_string(),
+            key: None,
+            value: None,
+            base: None,
+            members: None,
+        };
+        extend(&mut description);
+        self.types.insert(id.to_string(), description);
+    }
+}
+
+/// Returns the fields of a struct with named fields, or `_0`, `_1`, ... for a tuple struct.
+///
+/// `#[immutable]` fields live in the runtime code rather than in storage, so they are skipped.
+fn named_fields(item_struct: &ItemStruct) -> Result<Vec<(String, Type)>, anyhow::Error> {
+    match &item_struct.fields {
+        Fields::Named(fields) => Ok(fields
+            .named
+            .iter()
+            .filter(|f| !f.attrs.iter().any(|a| is_attribute(a, "immutable")))
+            .map(|f| (f.ident.as_ref().unwrap().to_string(), f.ty.clone()))
+            .collect()),
+        Fields::Unnamed(fields) => Ok(fields
+            .unnamed
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (format!("_{}", i), f.ty.clone()))
+            .collect()),
+        Fields::Unit => Ok(Vec::new()),
+    }
+}
+
+/// Returns the name of a type path and its generic type arguments.
+fn type_path(ty: &Type) -> Result<(String, Vec<Type>), anyhow::Error> {
+    let segment = last_segment(ty).ok_or_else(|| anyhow::anyhow!("unsupported type"))?;
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok((segment.ident.to_string(), args))
+}
+
+/// Returns the last segment of a type path, e.g. `Slot<U256>` for `hstd::Slot<U256>`.
+fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last(),
+        _ => None,
+    }
+}
+
+/// Checks whether an attribute is `#[name]` or `#[name(...)]`.
+fn is_attribute(attr: &Attribute, name: &str) -> bool {
+    attr.path.segments.len() == 1 && attr.path.segments[0].ident == name
+}
+
+/// Checks whether a struct has `#[derive(StorageStruct)]` among its attributes.
+fn derives_storage_struct(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| is_attribute(attr, "derive"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(list) => list.nested.iter().any(|nested| match nested {
+                NestedMeta::Meta(meta) => meta
+                    .path()
+                    .segments
+                    .last()
+                    .is_some_and(|s| s.ident == "StorageStruct"),
+                _ => false,
+            }),
+            _ => false,
+        })
+}
+
+/// Extracts the namespace of a `#[storage(namespace = "...")]` attribute, if any.
+fn storage_namespace(attr: &Attribute) -> Result<Option<String>, anyhow::Error> {
+    if attr.tokens.is_empty() {
+        return Ok(None);
+    }
+    match attr.parse_meta()? {
+        Meta::List(list) => match list.nested.first() {
+            Some(NestedMeta::Meta(Meta::NameValue(pair))) if pair.path.is_ident("namespace") => {
+                match &pair.lit {
+                    Lit::Str(namespace) => Ok(Some(namespace.value())),
+                    _ => anyhow::bail!("storage namespace must be a string"),
+                }
+            }
+            _ => anyhow::bail!("unsupported storage attribute"),
+        },
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(source: &str) -> StorageLayout {
+        storage_layout_from_source(source, "src/lib.rs")
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_erc20_layout() {
+        let layout = layout(
+            r#"
+            #[storage]
+            pub struct ERC20 {
+                total_supply: Slot<U256>,
+                balance_of: Mapping<Address, Slot<U256>>,
+                allowance_of: Mapping<Address, Mapping<Address, Slot<U256>>>,
+                owner: Slot<Address>,
+            }
+            "#,
+        );
+
+        let entries: Vec<_> = layout
+            .storage
+            .iter()
+            .map(|e| (e.label.as_str(), e.slot.as_str(), e.offset, e.ty.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("total_supply", "0", 0, "t_uint256"),
+                ("balance_of", "1", 0, "t_mapping(t_address,t_uint256)"),
+                (
+                    "allowance_of",
+                    "2",
+                    0,
+                    "t_mapping(t_address,t_mapping(t_address,t_uint256))"
+                ),
+                ("owner", "3", 0, "t_address"),
+            ]
+        );
+
+        let nested = &layout.types["t_mapping(t_address,t_mapping(t_address,t_uint256))"];
+        assert_eq!(nested.encoding, "mapping");
+        assert_eq!(
+            nested.label,
+            "mapping(address => mapping(address => uint256))"
+        );
+        assert_eq!(layout.types["t_address"].number_of_bytes, "20");
+    }
+
+    #[test]
+    fn test_struct_layout() {
+        let layout = layout(
+            r#"
+            #[derive(StorageStruct)]
+            pub struct Position {
+                pub owner: Address,
+                pub expiry: u64,
+                pub active: bool,
+                pub amount: U256,
+            }
+
+            #[storage]
+            pub struct Vault {
+                config: Slot<Position>,
+                positions: Mapping<Address, Slot<Position>>,
+                holders: StorageVec<Address>,
+                name: StorageString,
+                selector: Slot<B32>,
+            }
+            "#,
+        );
+
+        let slots: Vec<_> = layout.storage.iter().map(|e| e.slot.as_str()).collect();
+        assert_eq!(slots, ["0", "2", "3", "4", "5"]);
+        assert_eq!(layout.storage[4].offset, 28);
+
+        let position = &layout.types["t_struct(Position)_storage"];
+        assert_eq!(position.number_of_bytes, "64");
+        let members: Vec<_> = position
+            .members
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|m| (m.label.as_str(), m.slot.as_str(), m.offset))
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("owner", "0", 0),
+                ("expiry", "0", 20),
+                ("active", "0", 28),
+                ("amount", "1", 0)
+            ]
+        );
+
+        assert_eq!(
+            layout.types["t_array(t_address)dyn_storage"]
+                .base
+                .as_deref(),
+            Some("t_address")
+        );
+        assert_eq!(layout.types["t_string_storage"].encoding, "bytes");
+    }
+
+    #[test]
+    fn test_namespaced_layout() {
+        let layout = layout(
+            r#"
+            #[storage(namespace = "example.main")]
+            pub struct Main {
+                owner: Slot<Address>,
+                supply: Slot<u128>,
+            }
+            "#,
+        );
+
+        let root = U256::from_str_radix(
+            "183a6125c38840424c4a85fa12bab2ab606c4b6d0e7cc73c0c06ba5300eab500",
+            16,
+        )
+        .unwrap();
+        assert_eq!(layout.storage[0].slot, root.to_string());
+        assert_eq!(layout.storage[1].slot, (root + U256::from(1)).to_string());
+        assert_eq!(layout.types["t_uint128"].number_of_bytes, "16");
+    }
+
+    #[test]
+    fn test_immutables_layout() {
+        let layout = layout(
+            r#"
+            #[storage]
+            pub struct Token {
+                #[immutable]
+                owner: Address,
+                total_supply: Slot<U256>,
+                #[immutable]
+                decimals: u64,
+                balance_of: Mapping<Address, Slot<U256>>,
+            }
+            "#,
+        );
+
+        let entries: Vec<_> = layout
+            .storage
+            .iter()
+            .map(|e| (e.label.as_str(), e.slot.as_str()))
+            .collect();
+        assert_eq!(entries, [("total_supply", "0"), ("balance_of", "1")]);
+    }
+
+    #[test]
+    fn test_no_storage() {
+        let source = "pub struct Plain { value: u64 }";
+        assert!(storage_layout_from_source(source, "src/lib.rs")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_struct_in_module_file() {
+        let dir = std::env::temp_dir().join(format!("hybrid-layout-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("src/lib.rs"),
+            r#"
+            mod position;
+            use position::Position;
+
+            #[storage]
+            pub struct Vault {
+                config: Slot<Position>,
+                selector: Slot<B256>,
+            }
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("src/position.rs"),
+            r#"
+            #[derive(StorageStruct)]
+            pub struct Position {
+                pub owner: Address,
+                pub amount: U256,
+            }
+            "#,
+        )
+        .unwrap();
+
+        let layout = find_storage_layout(&dir.join("src/lib.rs"))
+            .unwrap()
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let slots: Vec<_> = layout.storage.iter().map(|e| e.slot.as_str()).collect();
+        assert_eq!(slots, ["0", "2"]);
+        assert_eq!(layout.storage[0].contract, "src/lib.rs:Vault");
+        assert_eq!(layout.storage[1].ty, "t_bytes32");
+
+        let members = layout.types["t_struct(Position)_storage"]
+            .members
+            .as_ref()
+            .unwrap();
+        assert_eq!(members[0].contract, "src/position.rs:Position");
+    }
+
+    #[test]
+    fn test_struct_in_inline_module() {
+        let layout = layout(
+            r#"
+            mod types {
+                #[derive(StorageStruct)]
+                pub struct Limits {
+                    pub min: u128,
+                    pub max: u128,
+                }
+            }
+
+            #[storage]
+            pub struct Pool {
+                limits: Slot<types::Limits>,
+            }
+            "#,
+        );
+
+        assert_eq!(
+            layout.types["t_struct(Limits)_storage"].number_of_bytes,
+            "32"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_type() {
+        let source = "#[storage] pub struct Custom { value: MyStorage<U256> }";
+        assert!(storage_layout_from_source(source, "src/lib.rs").is_err());
+    }
+}
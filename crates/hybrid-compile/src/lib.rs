@@ -16,9 +16,11 @@
 //!
 //! ## Architecture
 //!
-//! The crate is organized into three main modules:
+//! The crate is organized into the following modules:
 //! - [`primitives`]: Core data structures and compilation logic
 //! - [`utils`]: Utility functions for contract discovery and parsing
+//! - [`layout`]: Storage layout generation for off-chain tooling
+//! - [`abi`]: JSON ABI generation for off-chain tooling
 //!
 //! ## Usage
 //!
@@ -58,11 +60,17 @@
 //! Both stages target `riscv64imac-unknown-none-elf` and use Rust's `build-std` feature
 //! for no-std compatibility.
 //!
+//! Alongside the bytecode, `out/<package>.storage.json` describes the contract's storage in
+//! the format of `solc --storage-layout` (see [`layout`]), and `out/<package>.abi.json` holds
+//! the contract's JSON ABI in the format of `solc --abi` (see [`abi`]).
+//!
 //! ## Error Handling
 //!
 //! All functions return `Result<(), anyhow::Error>` for comprehensive error reporting.
 //! Common errors include missing dependencies, invalid contract structure, and compilation failures.
 
+pub mod abi;
+pub mod layout;
 pub mod primitives;
 pub mod utils;
 
@@ -70,7 +78,7 @@ use colored::Colorize;
 use indicatif::ProgressBar;
 use primitives::Contract;
 use std::{fs, path::Path};
-use tracing::info;
+use tracing::{info, warn};
 use utils::obtain_contract_by_path;
 
 /// Compiles a Rust smart contract to RISC-V bytecode for deployment on EVM-compatible blockchains.
@@ -110,6 +118,8 @@ pub fn run_contract_compilation(
         println!("\n✅ {}\n", "Contract syntax check passed!".green().bold());
     } else {
         fs::write(deploy_path, deploy_bytecode)?;
+        write_storage_layout(&contract, &output_dir)?;
+        write_abi(&contract, &output_dir)?;
         pb.finish_with_message("Contract build completed successfully!".green().to_string());
         println!(
             "\n✅ {} to {}\n",
@@ -154,3 +164,43 @@ pub fn run_contract_compilation_runtime(
 
     Ok(())
 }
+
+/// Writes the storage layout of a contract to `<output_dir>/<package>.storage.json`.
+///
+/// Contracts without a `#[storage]` struct get no layout file. A layout that cannot be
+/// derived (e.g. a field of a custom storage type) is reported as a warning rather than
+/// failing the build.
+fn write_storage_layout(contract: &Contract, output_dir: &Path) -> Result<(), anyhow::Error> {
+    let lib_path = contract.path.join("src").join("lib.rs");
+    match layout::find_storage_layout(&lib_path) {
+        Ok(Some(layout)) => {
+            let layout_path = output_dir.join(format!("{}.storage.json", contract.name.package));
+            fs::write(layout_path, serde_json::to_string_pretty(&layout)?)?;
+        }
+        Ok(None) => {}
+        Err(e) => warn!(
+            "Unable to derive the storage layout of {}: {}",
+            contract.name.ident, e
+        ),
+    }
+
+    Ok(())
+}
+
+/// Writes the JSON ABI of a contract to `<output_dir>/<package>.abi.json`.
+///
+/// Contracts without a `#[contract]` impl get no ABI file. An ABI that cannot be derived
+/// is reported as a warning rather than failing the build.
+fn write_abi(contract: &Contract, output_dir: &Path) -> Result<(), anyhow::Error> {
+    let lib_path = contract.path.join("src").join("lib.rs");
+    match abi::find_abi(&lib_path) {
+        Ok(Some(abi)) => {
+            let abi_path = output_dir.join(format!("{}.abi.json", contract.name.package));
+            fs::write(abi_path, serde_json::to_string_pretty(&abi)?)?;
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Unable to derive the ABI of {}: {}", contract.name.ident, e),
+    }
+
+    Ok(())
+}
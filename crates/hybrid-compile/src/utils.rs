@@ -10,6 +10,7 @@
 //!
 //! - [`obtain_contract_by_path`]: Main entry point for contract discovery
 //! - [`find_contract_ident`]: Extracts contract identifier from Rust source code
+//! - [`crate_items`]: Collects the items of every module of a contract crate
 //!
 //! ## Contract Discovery Process
 //!
@@ -197,3 +198,112 @@ fn extract_ident(item_impl: &ItemImpl) -> Option<String> {
         _ => None,
     }
 }
+
+/// An item of a contract crate and the file declaring it.
+pub struct SourceItem {
+    /// The declaring file, relative to the crate root (e.g. `src/position.rs`)
+    pub file: String,
+    /// The item
+    pub item: Item,
+}
+
+/// Collects the items of every module of the crate whose root module is at `lib_path`.
+///
+/// `mod name;` declarations are followed to `name.rs` or `name/mod.rs`, and inline
+/// `mod name { ... }` blocks are descended into, so types declared outside `src/lib.rs` are
+/// found. Declared modules without a file, such as a
+/// `#[cfg(test)] mod tests;` that was never written, are skipped.
+///
+/// # Arguments
+///
+/// * `lib_path` - Path to the contract's `src/lib.rs`
+///
+/// # Returns
+///
+/// The items in module order, or an error if a module file cannot be read or parsed.
+pub fn crate_items(lib_path: &Path) -> Result<Vec<SourceItem>, anyhow::Error> {
+    let root = lib_path
+        .parent()
+        .and_then(Path::parent)
+        .unwrap_or_else(|| Path::new(""));
+    let mut items = Vec::new();
+    collect_file(lib_path, root, &mut items)?;
+    Ok(items)
+}
+
+/// Collects the items of `source`, descending into its inline modules, without following
+/// `mod name;` declarations to other files.
+pub fn source_items(source: &str, file_name: &str) -> Result<Vec<SourceItem>, anyhow::Error> {
+    let mut items = Vec::new();
+    collect_items(
+        syn::parse_file(source)?.items,
+        file_name,
+        None,
+        Path::new(""),
+        &mut items,
+    )?;
+    Ok(items)
+}
+
+/// Parses the module file at `path` and collects its items.
+fn collect_file(
+    path: &Path,
+    root: &Path,
+    items: &mut Vec<SourceItem>,
+) -> Result<(), anyhow::Error> {
+    let source = fs::read_to_string(path)?;
+    let file = syn::parse_file(&source)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+    let file_name = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string();
+
+    // Submodules of `lib.rs` and `mod.rs` live next to them, those of `name.rs` in `name/`
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let module_dir = match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some("lib" | "mod" | "main") | None => dir.to_path_buf(),
+        Some(stem) => dir.join(stem),
+    };
+
+    collect_items(file.items, &file_name, Some(root), &module_dir, items)
+}
+
+/// Collects `file_items`, declared in `file_name`, following module declarations to files
+/// under `module_dir` when `root` is given.
+fn collect_items(
+    file_items: Vec<Item>,
+    file_name: &str,
+    root: Option<&Path>,
+    module_dir: &Path,
+    items: &mut Vec<SourceItem>,
+) -> Result<(), anyhow::Error> {
+    for item in file_items {
+        let Item::Mod(item_mod) = item else {
+            items.push(SourceItem {
+                file: file_name.to_string(),
+                item,
+            });
+            continue;
+        };
+
+        let name = item_mod.ident.to_string();
+        match (item_mod.content, root) {
+            (Some((_, content)), _) => {
+                collect_items(content, file_name, root, &module_dir.join(&name), items)?
+            }
+            (None, Some(root)) => {
+                let candidates = [
+                    module_dir.join(format!("{}.rs", name)),
+                    module_dir.join(&name).join("mod.rs"),
+                ];
+                if let Some(path) = candidates.into_iter().find(|path| path.is_file()) {
+                    collect_file(&path, root, items)?;
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(())
+}
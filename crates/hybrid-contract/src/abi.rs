@@ -0,0 +1,159 @@
+//! # ABI Helpers
+//!
+//! Compile-time helpers for the code generated by `#[contract]` and `#[interface]`.
+//!
+//! Selectors of methods whose arguments are all primitive types are hashed when the macros
+//! expand. Arguments of user-defined types (`#[derive(SolStruct)]`, `#[derive(SolEnum)]`)
+//! are only known to the compiler, so their selectors are computed in a `const` from the
+//! Solidity name of the argument tuple instead:
+//!
+//! ```rust,no_run
+//! use hybrid_contract::abi::selector;
+//!
+//! const TRANSFER: u32 = selector("transfer", "(address,uint256)");
+//! assert_eq!(TRANSFER, 0xa9059cbb);
+//! ```
+//!
+//! Contracts with many methods dispatch through a [`dispatch_table`], sorted at compile time
+//! and searched with [`dispatch_index`], and arguments that each fit a word are read in
+//! place with [`decode_word`] instead of going through the ABI decoder.
+
+use alloy_sol_types::{abi::token::WordToken, SolType, SolValue};
+use hybrid_abi::Keccak;
+
+/// Computes a function selector at compile time.
+///
+/// # Arguments
+/// * `name` - The function name
+/// * `params` - The Solidity name of the argument tuple, parentheses included
+///
+/// # Returns
+/// The first 4 bytes of `keccak256(name ++ params)`, as a big-endian `u32`
+pub const fn selector(name: &str, params: &str) -> u32 {
+    let hash = Keccak::new()
+        .update(name.as_bytes())
+        .update(params.as_bytes())
+        .finalize();
+    u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]])
+}
+
+/// Builds the selector table of a contract's dispatch at compile time.
+///
+/// # Arguments
+/// * `selectors` - The selectors of the contract's methods, in declaration order
+///
+/// # Returns
+/// Each selector paired with its position in `selectors`, sorted by selector
+///
+/// # Panics
+/// Fails to compile if two selectors are equal.
+pub const fn dispatch_table<const N: usize>(selectors: [u32; N]) -> [(u32, u16); N] {
+    let mut table = [(0, 0); N];
+    let mut i = 0;
+    while i < N {
+        table[i] = (selectors[i], i as u16);
+        i += 1;
+    }
+
+    // Insertion sort, tables are small and this runs once, in the compiler
+    i = 1;
+    while i < N {
+        let mut j = i;
+        while j > 0 && table[j - 1].0 >= table[j].0 {
+            assert!(
+                table[j - 1].0 != table[j].0,
+                "two methods have the same selector"
+            );
+            let entry = table[j];
+            table[j] = table[j - 1];
+            table[j - 1] = entry;
+            j -= 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+/// Finds a selector in a [`dispatch_table`] by binary search.
+///
+/// # Returns
+/// The position of the selector's method, or `None` if no method has this selector
+pub fn dispatch_index(table: &[(u32, u16)], selector: u32) -> Option<u16> {
+    table
+        .binary_search_by_key(&selector, |&(selector, _)| selector)
+        .ok()
+        .map(|i| table[i].1)
+}
+
+/// Decodes the `index`-th argument of calldata whose arguments are all one word long.
+///
+/// Such arguments sit at a fixed offset, so they are read in place and checked like the
+/// ABI decoder would (e.g. an `address` must be left-padded with zeros).
+///
+/// # Panics
+/// Panics if the calldata is too short or the word is not a valid value of `T`.
+pub fn decode_word<T>(calldata: &[u8], index: usize) -> T
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType>,
+    for<'a> T::SolType: SolType<Token<'a> = WordToken>,
+{
+    let word = match calldata.get(index * 32..(index + 1) * 32) {
+        Some(word) => WordToken::from(<[u8; 32]>::try_from(word).unwrap()),
+        None => panic!("abi decode failed"),
+    };
+    if !<T::SolType as SolType>::valid_token(&word) {
+        panic!("abi decode failed");
+    }
+    T::detokenize(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_core::primitives::{keccak256, Address, U256};
+    use std::format;
+
+    #[test]
+    fn test_selector_matches_keccak() {
+        assert_eq!(selector("transfer", "(address,uint256)"), 0xa9059cbb);
+        assert_eq!(selector("totalSupply", "()"), 0x18160ddd);
+
+        // Longer than the sponge rate
+        let params = format!("({})", ["(address,uint256,string)[]"; 8].join(","));
+        let hash = keccak256(format!("batch{}", params));
+        assert_eq!(selector("batch", &params).to_be_bytes(), hash[..4]);
+    }
+
+    #[test]
+    fn test_dispatch_table() {
+        const TABLE: [(u32, u16); 4] =
+            dispatch_table([0xa9059cbb, 0x18160ddd, 0x70a08231, 0x095ea7b3]);
+        assert_eq!(
+            TABLE,
+            [
+                (0x095ea7b3, 3),
+                (0x18160ddd, 1),
+                (0x70a08231, 2),
+                (0xa9059cbb, 0)
+            ]
+        );
+        assert_eq!(dispatch_index(&TABLE, 0xa9059cbb), Some(0));
+        assert_eq!(dispatch_index(&TABLE, 0x095ea7b3), Some(3));
+        assert_eq!(dispatch_index(&TABLE, 0x12345678), None);
+        assert_eq!(dispatch_index(&[], 0x12345678), None);
+    }
+
+    #[test]
+    fn test_decode_word() {
+        let to = Address::repeat_byte(0x11);
+        let calldata = (to, U256::from(7), true).abi_encode_params();
+        assert_eq!(decode_word::<Address>(&calldata, 0), to);
+        assert_eq!(decode_word::<U256>(&calldata, 1), U256::from(7));
+        assert!(decode_word::<bool>(&calldata, 2));
+
+        // Dirty padding is rejected, as by `abi_decode_params`
+        let dirty = U256::MAX.abi_encode();
+        assert!(std::panic::catch_unwind(|| decode_word::<Address>(&dirty, 0)).is_err());
+        assert!(std::panic::catch_unwind(|| decode_word::<U256>(&calldata, 3)).is_err());
+    }
+}
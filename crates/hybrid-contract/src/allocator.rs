@@ -16,6 +16,7 @@
 //! single-threaded or bare-metal environments where such usage is acceptable.
 
 #![allow(static_mut_refs)]
+#![cfg_attr(feature = "test-env", allow(dead_code))]
 use core::alloc::{GlobalAlloc, Layout};
 
 /// The main bump allocator instance.
@@ -112,5 +113,10 @@ unsafe impl GlobalAlloc for HybridAlloc {
 }
 
 /// Sets `HybridAlloc` as the global allocator.
+#[cfg(not(feature = "test-env"))]
 #[global_allocator]
 pub static mut ALLOC: HybridAlloc = HybridAlloc {};
+
+/// Host tests allocate through `std`; storage guards are still never freed.
+#[cfg(feature = "test-env")]
+pub static mut ALLOC: std::alloc::System = std::alloc::System;
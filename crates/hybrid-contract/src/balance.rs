@@ -0,0 +1,175 @@
+//! # Native Balance and Transfers
+//!
+//! This module provides access to ether balances and plain ether transfers, the Rust
+//! counterparts of Solidity's `address.balance`, `address(this).balance`,
+//! `payable(to).transfer(amount)` and `payable(to).send(amount)`.
+//!
+//! All amounts are `U256` values in wei.
+//!
+//! ## Usage
+//! ```rust,no_run
+//! use hybrid_contract::balance::*;
+//! use alloy_core::primitives::{Address, U256};
+//!
+//! // Check balances
+//! let held = self_balance();
+//! let user_funds = balance(user_address);
+//!
+//! // Pay out the whole balance of the contract
+//! transfer(recipient, held);
+//!
+//! // Pay out without reverting if the recipient rejects the ether
+//! if !send(recipient, held) {
+//!     // keep the funds claimable
+//! }
+//! ```
+//!
+//! ## Security Considerations
+//! A transfer hands execution to the recipient, which may be a contract. Like in Solidity,
+//! the recipient only gets the [`TRANSFER_STIPEND`] of gas, enough to log an event but not
+//! to call back into the contract. Still, update state before transferring
+//! (checks-effects-interactions) rather than relying on the stipend.
+
+use alloy_core::primitives::{Address, U256};
+#[cfg(not(feature = "test-env"))]
+use core::arch::asm;
+#[cfg(not(feature = "test-env"))]
+use hybrid_syscalls::Syscall;
+
+use crate::{call::call, revert};
+
+/// Gas available to the recipient of [`transfer`] and [`send`], as with Solidity's
+/// `transfer` and `send`.
+pub const TRANSFER_STIPEND: u64 = 2300;
+
+/// Returns the balance of an account in wei.
+///
+/// This function corresponds to the EVM BALANCE opcode.
+///
+/// # Arguments
+/// * `addr` - The account to query
+///
+/// # Returns
+/// The balance of the account as a U256 (in wei)
+///
+/// # Examples
+/// ```rust,no_run
+/// let funds = balance(msg_sender());
+/// if funds < minimum_stake {
+///     revert();
+/// }
+/// ```
+pub fn balance(addr: Address) -> U256 {
+    #[cfg(feature = "test-env")]
+    return crate::mock::balance(addr);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let bytes = addr.as_slice();
+        let mut third = [0u8; 8];
+        third[4..].copy_from_slice(&bytes[16..20]);
+
+        let (first, second, third) = (
+            u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            u64::from_be_bytes(bytes[8..16].try_into().unwrap()),
+            u64::from_be_bytes(third),
+        );
+
+        let (limb0, limb1, limb2, limb3): (u64, u64, u64, u64);
+        unsafe {
+            asm!(
+                "ecall",
+                inlateout("a0") first => limb0, inlateout("a1") second => limb1,
+                inlateout("a2") third => limb2, lateout("a3") limb3,
+                in("t0") u8::from(Syscall::Balance)
+            );
+        }
+        U256::from_limbs([limb0, limb1, limb2, limb3])
+    }
+}
+
+/// Returns the balance of the executing contract in wei.
+///
+/// This function corresponds to the EVM SELFBALANCE opcode and is cheaper than
+/// calling [`balance`] with the contract's own address.
+///
+/// # Returns
+/// The balance of the contract as a U256 (in wei), including any value sent
+/// with the current call
+///
+/// # Examples
+/// ```rust,no_run
+/// let held = self_balance();
+/// transfer(owner, held);
+/// ```
+pub fn self_balance() -> U256 {
+    #[cfg(feature = "test-env")]
+    return crate::mock::self_balance();
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let (first, second, third, fourth): (u64, u64, u64, u64);
+        unsafe {
+            asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, lateout("a3") fourth, in("t0") u8::from(Syscall::SelfBalance));
+        }
+        U256::from_limbs([first, second, third, fourth])
+    }
+}
+
+/// Sends `amount` wei from the contract to `to`, with no calldata.
+///
+/// If `to` is a contract, its code runs with the transferred value and
+/// [`TRANSFER_STIPEND`] gas, like a call to its `receive` function in Solidity.
+///
+/// # Arguments
+/// * `to` - The recipient of the ether
+/// * `amount` - Amount of wei to send
+///
+/// # Panics
+/// Reverts the contract if its balance is lower than `amount` or the recipient reverts
+///
+/// # Examples
+/// ```rust,no_run
+/// let owed = pending[msg_sender()].read();
+/// pending[msg_sender()].write(U256::ZERO);
+/// transfer(msg_sender(), owed);
+/// ```
+pub fn transfer(to: Address, amount: U256) {
+    if !send(to, amount) {
+        revert();
+    }
+}
+
+/// Sends `amount` wei from the contract to `to`, with no calldata, and returns whether the
+/// transfer succeeded.
+///
+/// Unlike [`transfer`], a failed transfer does not revert the contract, so it can handle
+/// the failure itself, e.g. by keeping the amount claimable.
+///
+/// # Arguments
+/// * `to` - The recipient of the ether
+/// * `amount` - Amount of wei to send
+///
+/// # Returns
+/// `false` if the contract's balance is lower than `amount` or the recipient reverts
+///
+/// # Examples
+/// ```rust,no_run
+/// if !send(winner, prize) {
+///     unclaimed[winner].write(prize);
+/// }
+/// ```
+pub fn send(to: Address, amount: U256) -> bool {
+    if amount > self_balance() {
+        return false;
+    }
+
+    // As with the CALL opcode, the VM adds the stipend to the gas of calls carrying value,
+    // so only a call without value has to request it
+    let gas_limit = if amount.is_zero() {
+        TRANSFER_STIPEND
+    } else {
+        0
+    };
+    call(to, amount, gas_limit, 0, 0)
+}
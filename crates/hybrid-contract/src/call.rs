@@ -9,25 +9,45 @@
 //! - Support for both mutable (`call`) and read-only (`staticcall`) operations
 //! - Automatic ABI encoding/decoding of call data and return values
 //! - Context markers to prevent invalid operations (e.g., state changes in static contexts)
+//! - Optional gas limits, to call untrusted contracts without forwarding all remaining gas
+//! - [`CallResult`] to handle reverts of the callee, decoding its custom errors, `Error(string)`
+//!   and `Panic(uint256)`, like Solidity's `try`/`catch`
 //!
 //! ## Usage
 //! ```rust,no_run
 //! use hybrid_contract::call::*;
-//! use alloy_core::primitives::Address;
+//! use alloy_core::primitives::{Address, U256};
 //!
 //! // Call another contract
 //! let result = call_contract(
 //!     Address::ZERO,
-//!     0, // value in wei
+//!     U256::ZERO, // value in wei
+//!     None, // forward all but one 64th of the remaining gas
 //!     &call_data,
 //!     Some(32) // expected return size
 //! );
+//!
+//! // Call another contract, telling its reverts apart from its return data
+//! let result: CallResult<U256, TokenError> =
+//!     CallResult::decode(try_call_contract(Address::ZERO, U256::ZERO, None, &call_data, None));
+//! match result {
+//!     CallResult::Ok(balance) => { /* the call succeeded */ }
+//!     CallResult::Revert(err) => { /* the callee reverted with a `TokenError` */ }
+//!     CallResult::Error(reason) => { /* `require(condition, reason)` failed */ }
+//!     CallResult::Panic(code) => { /* the callee panicked with a Solidity panic code */ }
+//!     CallResult::Failed(data) => { /* any other revert, or undecodable return data */ }
+//! }
 //! ```
 
 extern crate alloc;
-use alloc::vec::Vec;
+use crate::{error::Error, revert_with_error};
+use alloc::{string::String, vec::Vec};
 use alloy_core::primitives::{Address, Bytes, U256};
-use core::{arch::asm, marker::PhantomData};
+use alloy_sol_types::{SolType, SolValue};
+#[cfg(not(feature = "test-env"))]
+use core::arch::asm;
+use core::marker::PhantomData;
+#[cfg(not(feature = "test-env"))]
 use hybrid_syscalls::Syscall;
 
 /// Marker type for read-only contract call contexts.
@@ -132,18 +152,60 @@ pub trait Contract {
 /// # Arguments
 /// * `addr` - The address of the contract to call
 /// * `value` - Amount of wei to send with the call
+/// * `gas_limit` - Maximum gas the callee may use (None to forward as much as possible)
 /// * `data` - The call data (typically ABI-encoded function call)
 /// * `ret_size` - Expected size of return data (None to auto-detect)
 ///
 /// # Returns
 /// The return data from the called contract
-pub fn call_contract(addr: Address, value: u64, data: &[u8], ret_size: Option<u64>) -> Bytes {
+pub fn call_contract(
+    addr: Address,
+    value: U256,
+    gas_limit: Option<u64>,
+    data: &[u8],
+    ret_size: Option<u64>,
+) -> Bytes {
     // Perform the call without writing return data into (REVM) memory
-    call(addr, value, data.as_ptr() as u64, data.len() as u64);
+    call(
+        addr,
+        value,
+        gas_limit.unwrap_or(u64::MAX),
+        data.as_ptr() as u64,
+        data.len() as u64,
+    );
     // Load call output to memory
     handle_call_output(ret_size)
 }
 
+/// Calls another contract and returns the response data, as `Err` if the call reverted.
+///
+/// Takes the same arguments as [`call_contract`]. The revert data can be decoded with
+/// [`CallResult::decode`].
+///
+/// # Returns
+/// The return data of the called contract, or its revert data if the call failed
+pub fn try_call_contract(
+    addr: Address,
+    value: U256,
+    gas_limit: Option<u64>,
+    data: &[u8],
+    ret_size: Option<u64>,
+) -> Result<Bytes, Bytes> {
+    let success = call(
+        addr,
+        value,
+        gas_limit.unwrap_or(u64::MAX),
+        data.as_ptr() as u64,
+        data.len() as u64,
+    );
+    let output = handle_call_output(ret_size);
+    if success {
+        Ok(output)
+    } else {
+        Err(output)
+    }
+}
+
 /// Low-level contract call via RISC-V system call.
 ///
 /// This function performs the actual EVM CALL operation through a system call
@@ -153,21 +215,36 @@ pub fn call_contract(addr: Address, value: u64, data: &[u8], ret_size: Option<u6
 /// # Arguments
 /// * `addr` - The contract address to call
 /// * `value` - Amount of wei to transfer
+/// * `gas_limit` - Maximum gas to forward, capped by the VM to all but one 64th of the
+///   remaining gas (EIP-150)
 /// * `data_offset` - Memory offset of the call data
 /// * `data_size` - Size of the call data in bytes
 ///
+/// # Returns
+/// Whether the call succeeded, `false` if it reverted or failed
+///
 /// # Safety
 /// Uses inline assembly and assumes the caller has prepared valid call data.
-pub fn call(addr: Address, value: u64, data_offset: u64, data_size: u64) {
-    let addr: U256 = addr.into_word().into();
-    let addr = addr.as_limbs();
-    unsafe {
-        asm!(
-            "ecall",
-            in("a0") addr[0], in("a1") addr[1], in("a2") addr[2],
-            in("a3") value, in("a4") data_offset, in("a5") data_size,
-            in("t0") u8::from(Syscall::Call)
-        );
+pub fn call(addr: Address, value: U256, gas_limit: u64, data_offset: u64, data_size: u64) -> bool {
+    #[cfg(feature = "test-env")]
+    return crate::mock::call(addr, value, gas_limit, data_offset, data_size, false);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let addr: U256 = addr.into_word().into();
+        let addr = addr.as_limbs();
+        // The value is passed by pointer as it does not fit in a single register
+        let value = value.as_limbs();
+        let success: u64;
+        unsafe {
+            asm!(
+                "ecall",
+                inlateout("a0") addr[0] => success, in("a1") addr[1], in("a2") addr[2],
+                in("a3") value.as_ptr() as u64, in("a4") data_offset, in("a5") data_size,
+                in("a6") gas_limit, in("t0") u8::from(Syscall::Call)
+            );
+        }
+        success != 0
     }
 }
 
@@ -179,18 +256,61 @@ pub fn call(addr: Address, value: u64, data_offset: u64, data_size: u64) {
 /// # Arguments
 /// * `addr` - The address of the contract to call
 /// * `value` - Should typically be 0 for static calls
+/// * `gas_limit` - Maximum gas the callee may use (None to forward as much as possible)
 /// * `data` - The call data (typically ABI-encoded function call)
 /// * `ret_size` - Expected size of return data (None to auto-detect)
 ///
 /// # Returns
 /// The return data from the called contract
-pub fn staticcall_contract(addr: Address, value: u64, data: &[u8], ret_size: Option<u64>) -> Bytes {
+pub fn staticcall_contract(
+    addr: Address,
+    value: U256,
+    gas_limit: Option<u64>,
+    data: &[u8],
+    ret_size: Option<u64>,
+) -> Bytes {
     // Perform the staticcall without writing return data into (REVM) memory
-    staticcall(addr, value, data.as_ptr() as u64, data.len() as u64);
+    staticcall(
+        addr,
+        value,
+        gas_limit.unwrap_or(u64::MAX),
+        data.as_ptr() as u64,
+        data.len() as u64,
+    );
     // Load call output to memory
     handle_call_output(ret_size)
 }
 
+/// Performs a static call to another contract and returns the response data, as `Err` if the
+/// call reverted.
+///
+/// Takes the same arguments as [`staticcall_contract`]. The revert data can be decoded with
+/// [`CallResult::decode`].
+///
+/// # Returns
+/// The return data of the called contract, or its revert data if the call failed
+pub fn try_staticcall_contract(
+    addr: Address,
+    value: U256,
+    gas_limit: Option<u64>,
+    data: &[u8],
+    ret_size: Option<u64>,
+) -> Result<Bytes, Bytes> {
+    let success = staticcall(
+        addr,
+        value,
+        gas_limit.unwrap_or(u64::MAX),
+        data.as_ptr() as u64,
+        data.len() as u64,
+    );
+    let output = handle_call_output(ret_size);
+    if success {
+        Ok(output)
+    } else {
+        Err(output)
+    }
+}
+
 /// Handles the retrieval of return data from a contract call.
 ///
 /// This function manages the process of copying return data from the VM's
@@ -242,21 +362,41 @@ fn handle_call_output(ret_size: Option<u64>) -> Bytes {
 /// # Arguments
 /// * `addr` - The contract address to call
 /// * `value` - Should typically be 0 for static calls
+/// * `gas_limit` - Maximum gas to forward, capped by the VM to all but one 64th of the
+///   remaining gas (EIP-150)
 /// * `data_offset` - Memory offset of the call data
 /// * `data_size` - Size of the call data in bytes
 ///
+/// # Returns
+/// Whether the call succeeded, `false` if it reverted or failed
+///
 /// # Safety
 /// Uses inline assembly and assumes the caller has prepared valid call data.
-pub fn staticcall(addr: Address, value: u64, data_offset: u64, data_size: u64) {
-    let addr: U256 = addr.into_word().into();
-    let addr = addr.as_limbs();
-    unsafe {
-        asm!(
-            "ecall",
-            in("a0") addr[0], in("a1") addr[1], in("a2") addr[2],
-            in("a3") value, in("a4") data_offset, in("a5") data_size,
-            in("t0") u8::from(Syscall::StaticCall)
-        );
+pub fn staticcall(
+    addr: Address,
+    value: U256,
+    gas_limit: u64,
+    data_offset: u64,
+    data_size: u64,
+) -> bool {
+    #[cfg(feature = "test-env")]
+    return crate::mock::call(addr, value, gas_limit, data_offset, data_size, true);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let addr: U256 = addr.into_word().into();
+        let addr = addr.as_limbs();
+        let value = value.as_limbs();
+        let success: u64;
+        unsafe {
+            asm!(
+                "ecall",
+                inlateout("a0") addr[0] => success, in("a1") addr[1], in("a2") addr[2],
+                in("a3") value.as_ptr() as u64, in("a4") data_offset, in("a5") data_size,
+                in("a6") gas_limit, in("t0") u8::from(Syscall::StaticCall)
+            );
+        }
+        success != 0
     }
 }
 
@@ -268,12 +408,18 @@ pub fn staticcall(addr: Address, value: u64, data_offset: u64, data_size: u64) {
 /// # Returns
 /// The size in bytes of available return data
 pub fn return_data_size() -> u64 {
-    let size: u64;
-    unsafe {
-        asm!( "ecall", lateout("a0") size, in("t0") u8::from(Syscall::ReturnDataSize));
-    }
+    #[cfg(feature = "test-env")]
+    return crate::mock::return_data_size();
 
-    size
+    #[cfg(not(feature = "test-env"))]
+    {
+        let size: u64;
+        unsafe {
+            asm!( "ecall", lateout("a0") size, in("t0") u8::from(Syscall::ReturnDataSize));
+        }
+
+        size
+    }
 }
 
 /// Copies return data from the VM's buffer to a specified memory location.
@@ -290,11 +436,113 @@ pub fn return_data_size() -> u64 {
 /// The caller must ensure that the destination memory is properly allocated
 /// and that the specified offsets and sizes are valid.
 pub fn return_data_copy(dest_offset: u64, res_offset: u64, res_size: u64) {
-    unsafe {
-        asm!(
-            "ecall",
-            in("a0") dest_offset, in("a1") res_offset, in("a2") res_size, in("t0")
-            u8::from(Syscall::ReturnDataCopy)
-        );
+    #[cfg(feature = "test-env")]
+    return crate::mock::return_data_copy(dest_offset, res_offset, res_size);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        unsafe {
+            asm!(
+                "ecall",
+                in("a0") dest_offset, in("a1") res_offset, in("a2") res_size, in("t0")
+                u8::from(Syscall::ReturnDataCopy)
+            );
+        }
+    }
+}
+
+/// The selector of `Error(string)`, the revert data of `require(condition, reason)`.
+pub const ERROR_STRING_SELECTOR: [u8; 4] = crate::abi::selector("Error", "(string)").to_be_bytes();
+
+/// The selector of `Panic(uint256)`, the revert data of failed assertions, arithmetic overflows
+/// and other Solidity panics.
+pub const PANIC_SELECTOR: [u8; 4] = crate::abi::selector("Panic", "(uint256)").to_be_bytes();
+
+/// The outcome of a call to another contract, decoded from its return or revert data.
+///
+/// Returned by `#[interface]` methods declared with a `CallResult<T, E>` return type, where `E`
+/// is a `#[derive(Error)]` type of the callee. Matching on it handles a failed call the way
+/// Solidity's `try`/`catch` does, without reverting the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallResult<T, E> {
+    /// The call succeeded and returned `T`
+    Ok(T),
+    /// The callee reverted with one of the errors of `E`
+    Revert(E),
+    /// The callee reverted with `Error(string)`, the reason of a failed `require`
+    Error(String),
+    /// The callee reverted with `Panic(uint256)`, the code of a Solidity panic
+    Panic(U256),
+    /// The callee reverted with any other data, possibly empty, or the call succeeded but its
+    /// return data does not decode as `T`
+    Failed(Bytes),
+}
+
+impl<T, E> CallResult<T, E>
+where
+    T: SolValue + From<<T::SolType as SolType>::RustType>,
+    E: Error,
+{
+    /// Decodes the output of [`try_call_contract`] or [`try_staticcall_contract`].
+    ///
+    /// Revert data is decoded as an error of `E` first, then as `Error(string)` and
+    /// `Panic(uint256)`, and is otherwise kept as is.
+    pub fn decode(output: Result<Bytes, Bytes>) -> Self {
+        let data = match output {
+            Ok(data) => {
+                return match T::abi_decode(&data, true) {
+                    Ok(decoded) => CallResult::Ok(decoded),
+                    Err(_) => CallResult::Failed(data),
+                };
+            }
+            Err(data) => data,
+        };
+
+        if let Some(err) = E::try_abi_decode(&data, true) {
+            return CallResult::Revert(err);
+        }
+        let selector: Option<[u8; 4]> = data.get(..4).and_then(|head| head.try_into().ok());
+        let decoded = match selector {
+            Some(ERROR_STRING_SELECTOR) => String::abi_decode(&data[4..], true)
+                .ok()
+                .map(CallResult::Error),
+            Some(PANIC_SELECTOR) => U256::abi_decode(&data[4..], true)
+                .ok()
+                .map(CallResult::Panic),
+            _ => None,
+        };
+        decoded.unwrap_or(CallResult::Failed(data))
+    }
+}
+
+impl<T, E: Error> CallResult<T, E> {
+    /// Returns `true` if the call succeeded.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, CallResult::Ok(_))
+    }
+
+    /// Returns the decoded return value, discarding the failure if the call failed.
+    pub fn ok(self) -> Option<T> {
+        match self {
+            CallResult::Ok(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the decoded return value, or reverts with the revert data of the callee.
+    ///
+    /// This is what Solidity does for a failed external call outside of `try`/`catch`.
+    pub fn or_revert(self) -> T {
+        match self {
+            CallResult::Ok(value) => value,
+            CallResult::Revert(err) => revert_with_error(&err.abi_encode()),
+            CallResult::Error(reason) => {
+                revert_with_error(&[&ERROR_STRING_SELECTOR[..], &reason.abi_encode()].concat())
+            }
+            CallResult::Panic(code) => {
+                revert_with_error(&[&PANIC_SELECTOR[..], &code.abi_encode()].concat())
+            }
+            CallResult::Failed(data) => revert_with_error(&data),
+        }
     }
 }
@@ -27,10 +27,13 @@
 //! ```
 
 extern crate alloc;
-use alloy_core::primitives::{Address, Bytes, U32};
+use alloy_core::primitives::{Address, Bytes, U256, U32};
 use alloy_sol_types::{SolType, SolValue};
-use core::{arch::asm, marker::PhantomData, u64};
+#[cfg(not(feature = "test-env"))]
+use core::arch::asm;
+use core::{marker::PhantomData, u64};
 use ext_alloc::vec::Vec;
+#[cfg(not(feature = "test-env"))]
 use hybrid_syscalls::Syscall;
 
 use crate::{FromBuilder, InitInterface, MethodCtx, ReadWrite};
@@ -145,7 +148,7 @@ where
         let size = init_code.len() as u64;
 
         // TODO: think of an ergonomic API to handle deployments with values
-        create(0, offset, size);
+        create(U256::ZERO, offset, size);
 
         // Get deployment address
         let mut ret_data = Vec::with_capacity(20);
@@ -174,13 +177,21 @@ where
 ///
 /// # Safety
 /// Uses inline assembly and assumes the caller has prepared valid initcode.
-fn create(value: u64, data_offset: u64, data_size: u64) {
-    unsafe {
-        asm!(
-            "ecall",
-            in("a0") value, in("a1") data_offset, in("a2") data_size,
-            in("t0") u8::from(Syscall::Create)
-        );
+fn create(value: U256, data_offset: u64, data_size: u64) {
+    #[cfg(feature = "test-env")]
+    return crate::mock::create(value, data_offset, data_size);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        // The value is passed by pointer as it does not fit in a single register
+        let value = value.as_limbs();
+        unsafe {
+            asm!(
+                "ecall",
+                in("a0") value.as_ptr() as u64, in("a1") data_offset, in("a2") data_size,
+                in("t0") u8::from(Syscall::Create)
+            );
+        }
     }
 }
 
@@ -196,8 +207,14 @@ fn create(value: u64, data_offset: u64, data_size: u64) {
 /// The caller must ensure that the destination memory location can safely
 /// accommodate 20 bytes and that a contract was actually just created.
 fn return_create_address(data_offset: u64) {
-    unsafe {
-        asm!(
-            "ecall", in("a0") data_offset, in("t0") u8::from(Syscall::ReturnCreateAddress));
+    #[cfg(feature = "test-env")]
+    return crate::mock::return_create_address(data_offset);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        unsafe {
+            asm!(
+                "ecall", in("a0") data_offset, in("t0") u8::from(Syscall::ReturnCreateAddress));
+        }
     }
 }
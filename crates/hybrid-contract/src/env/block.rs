@@ -9,6 +9,9 @@
 //! - Chain ID (CHAINID opcode)
 //! - Block gas limit (GASLIMIT opcode)
 //! - Block number (NUMBER opcode)
+//! - Block producer address (COINBASE opcode)
+//! - Beacon chain randomness (PREVRANDAO opcode)
+//! - Blob base fee (BLOBBASEFEE opcode)
 //!
 //! ## Usage
 //! ```rust,no_run
@@ -23,10 +26,11 @@
 //! All functions use inline RISC-V assembly to make system calls to the Hybrid VM.
 //! These are safe to call from any contract context.
 
-use alloy_core::primitives::U256;
-use core::arch::asm;
+use alloy_core::primitives::{Address, U256};
 use hybrid_syscalls::Syscall;
 
+use super::{read_address, read_u64, read_word};
+
 /// Returns the current block timestamp in seconds since Unix epoch.
 ///
 /// This function corresponds to the EVM TIMESTAMP opcode and provides the
@@ -45,14 +49,7 @@ use hybrid_syscalls::Syscall;
 /// }
 /// ```
 pub fn timestamp() -> U256 {
-    let first: u64;
-    let second: u64;
-    let third: u64;
-    let fourth: u64;
-    unsafe {
-        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, lateout("a3") fourth, in("t0") u8::from(Syscall::Timestamp));
-    }
-    U256::from_limbs([first, second, third, fourth])
+    read_word(Syscall::Timestamp)
 }
 
 /// Returns the current block's base fee per gas.
@@ -71,14 +68,7 @@ pub fn timestamp() -> U256 {
 /// let priority_fee = gas_price() - current_base_fee;
 /// ```
 pub fn base_fee() -> U256 {
-    let first: u64;
-    let second: u64;
-    let third: u64;
-    let fourth: u64;
-    unsafe {
-        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, lateout("a3") fourth, in("t0") u8::from(Syscall::BaseFee));
-    }
-    U256::from_limbs([first, second, third, fourth])
+    read_word(Syscall::BaseFee)
 }
 
 /// Returns the current blockchain's chain ID.
@@ -100,11 +90,7 @@ pub fn base_fee() -> U256 {
 /// }
 /// ```
 pub fn chain_id() -> u64 {
-    let id: u64;
-    unsafe {
-        asm!("ecall", lateout("a0") id, in("t0") u8::from(Syscall::ChainId));
-    }
-    id
+    read_u64(Syscall::ChainId)
 }
 
 /// Returns the current block's gas limit.
@@ -125,14 +111,7 @@ pub fn chain_id() -> u64 {
 /// }
 /// ```
 pub fn gas_limit() -> U256 {
-    let first: u64;
-    let second: u64;
-    let third: u64;
-    let fourth: u64;
-    unsafe {
-        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, lateout("a3") fourth, in("t0") u8::from(Syscall::GasLimit));
-    }
-    U256::from_limbs([first, second, third, fourth])
+    read_word(Syscall::GasLimit)
 }
 
 /// Returns the current block number.
@@ -155,12 +134,61 @@ pub fn gas_limit() -> U256 {
 /// }
 /// ```
 pub fn number() -> U256 {
-    let first: u64;
-    let second: u64;
-    let third: u64;
-    let fourth: u64;
-    unsafe {
-        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, lateout("a3") fourth, in("t0") u8::from(Syscall::Number));
-    }
-    U256::from_limbs([first, second, third, fourth])
+    read_word(Syscall::Number)
+}
+
+/// Returns the address of the current block's producer.
+///
+/// This function corresponds to the EVM COINBASE opcode and returns the address
+/// receiving the priority fees of the transactions in the current block.
+///
+/// # Returns
+/// The block producer's fee recipient as an `Address`
+///
+/// # Examples
+/// ```rust,no_run
+/// // Tip the block producer directly
+/// transfer(coinbase(), tip);
+/// ```
+pub fn coinbase() -> Address {
+    read_address(Syscall::Coinbase)
+}
+
+/// Returns the randomness beacon output of the previous block.
+///
+/// This function corresponds to the EVM PREVRANDAO opcode introduced in EIP-4399,
+/// which replaced DIFFICULTY after the merge. The value comes from the beacon
+/// chain's RANDAO and can be biased by block proposers, so it must not be used as
+/// the only source of randomness for valuable outcomes.
+///
+/// # Returns
+/// The previous block's RANDAO mix as a U256
+///
+/// # Examples
+/// ```rust,no_run
+/// let seed = prevrandao();
+/// let winner = seed % U256::from(participants);
+/// ```
+pub fn prevrandao() -> U256 {
+    read_word(Syscall::Prevrandao)
+}
+
+/// Returns the current block's blob base fee.
+///
+/// This function corresponds to the EVM BLOBBASEFEE opcode introduced in EIP-7516
+/// and returns the price per unit of blob gas for blob-carrying (EIP-4844)
+/// transactions in the current block.
+///
+/// # Returns
+/// The blob base fee as a U256 (in wei per blob gas)
+///
+/// # Examples
+/// ```rust,no_run
+/// let blob_fee = blob_base_fee();
+/// if blob_fee > max_blob_fee {
+///     revert(); // Blob data too expensive to post right now
+/// }
+/// ```
+pub fn blob_base_fee() -> U256 {
+    read_word(Syscall::BlobBaseFee)
 }
@@ -13,6 +13,15 @@
 //! - Chain ID
 //! - Block gas limit
 //! - Block number
+//! - Block producer (coinbase)
+//! - Previous block randomness (prevrandao)
+//! - Blob base fee (EIP-7516)
+//!
+//! ### Execution context
+//! Functions at the root of this module describe the running frame:
+//! - [`gas_left`]: Gas remaining in the current call
+//! - [`code_size`]: Size of the executing contract's code
+//! - [`code_copy`]: Copy of the executing contract's code
 //!
 //! ### `msg`
 //! Will provide access to message/transaction context information including:
@@ -28,6 +37,10 @@
 //! // Access block information
 //! let current_time = block::timestamp();
 //! let current_block = block::number();
+//! let producer = block::coinbase();
+//!
+//! // Access execution context
+//! let remaining = hybrid_contract::env::gas_left();
 //!
 //! // Access message information (when implemented)
 //! // let sender = msg::sender();
@@ -38,6 +51,112 @@
 //! All environment functions use system calls to retrieve information from the
 //! Hybrid VM. These calls are safe and do not modify state, only read current
 //! blockchain context.
+//!
+//! ## Testing
+//! With the `test-env` feature enabled, every function in this module reads from the
+//! thread-local environment in [`crate::mock`] instead of issuing a syscall, so
+//! contract logic can be unit tested on the host with chosen block and transaction
+//! values.
+
+#[cfg(not(feature = "test-env"))]
+use alloy_core::primitives::{Address, U256};
+use hybrid_syscalls::Syscall;
 
 pub mod block;
 pub mod msg;
+
+/// Returns the amount of gas still available to the current call.
+///
+/// This function corresponds to the EVM GAS opcode. The value accounts for the
+/// RISC-V instructions executed so far in the current frame.
+///
+/// # Returns
+/// The remaining gas as a u64
+///
+/// # Examples
+/// ```rust,no_run
+/// // Leave enough gas to finish bookkeeping after the external call
+/// let forwarded = gas_left().saturating_sub(50_000);
+/// call_contract(target, U256::ZERO, Some(forwarded), calldata, None);
+/// ```
+pub fn gas_left() -> u64 {
+    read_u64(Syscall::Gas)
+}
+
+/// Returns the size in bytes of the executing contract's code.
+///
+/// This function corresponds to the EVM CODESIZE opcode. For Hybrid contracts the
+/// size covers the whole deployed RISC-V binary, including its prefix byte.
+///
+/// # Returns
+/// The code size in bytes as a u64
+///
+/// # Examples
+/// ```rust,no_run
+/// let size = code_size();
+/// ```
+pub fn code_size() -> u64 {
+    read_u64(Syscall::CodeSize)
+}
+
+/// Copies the executing contract's code, from `offset`, into `dest`.
+///
+/// This function corresponds to the EVM CODECOPY opcode. Offsets count from the start of
+/// the deployed code, prefix byte included, and bytes past its end are copied as zeros.
+///
+/// # Examples
+/// ```rust,no_run
+/// // The last word of the code
+/// let mut word = [0u8; 32];
+/// code_copy(code_size() - 32, &mut word);
+/// ```
+pub fn code_copy(offset: u64, dest: &mut [u8]) {
+    #[cfg(feature = "test-env")]
+    return crate::mock::code_copy(offset, dest);
+
+    #[cfg(not(feature = "test-env"))]
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            in("a0") dest.as_mut_ptr() as u64, in("a1") offset, in("a2") dest.len() as u64,
+            in("t0") u8::from(Syscall::CodeCopy)
+        );
+    }
+}
+
+/// Reads a 256-bit environment value returned in registers a0-a3 (little-endian limbs).
+#[cfg(not(feature = "test-env"))]
+pub(crate) fn read_word(syscall: Syscall) -> U256 {
+    let (first, second, third, fourth): (u64, u64, u64, u64);
+    unsafe {
+        core::arch::asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, lateout("a3") fourth, in("t0") u8::from(syscall));
+    }
+    U256::from_limbs([first, second, third, fourth])
+}
+
+/// Reads an address returned in registers a0-a2, with the last 4 bytes in the low half of a2.
+#[cfg(not(feature = "test-env"))]
+pub(crate) fn read_address(syscall: Syscall) -> Address {
+    let (first, second, third): (u64, u64, u64);
+    unsafe {
+        core::arch::asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, in("t0") u8::from(syscall));
+    }
+    let mut bytes = [0u8; 20];
+    bytes[0..8].copy_from_slice(&first.to_be_bytes());
+    bytes[8..16].copy_from_slice(&second.to_be_bytes());
+    bytes[16..20].copy_from_slice(&third.to_be_bytes()[4..]);
+    Address::from_slice(&bytes)
+}
+
+/// Reads a 64-bit environment value returned in register a0.
+#[cfg(not(feature = "test-env"))]
+pub(crate) fn read_u64(syscall: Syscall) -> u64 {
+    let value: u64;
+    unsafe {
+        core::arch::asm!("ecall", lateout("a0") value, in("t0") u8::from(syscall));
+    }
+    value
+}
+
+#[cfg(feature = "test-env")]
+pub(crate) use crate::mock::{read_address, read_u64, read_word};
@@ -29,8 +29,10 @@
 //! ```
 
 extern crate alloc;
+#[cfg(not(feature = "test-env"))]
 use crate::Syscall;
 use alloc::vec::Vec;
+#[cfg(not(feature = "test-env"))]
 use core::arch::asm;
 
 /// Trait for custom error types that can be used with contract reversion.
@@ -76,6 +78,25 @@ pub trait Error {
     /// # Returns
     /// The decoded error instance
     fn abi_decode(bytes: &[u8], validate: bool) -> Self;
+
+    /// Decodes error data from ABI format, if it holds one of this type's errors.
+    ///
+    /// Used to tell the errors of a callee apart from any other revert data, see
+    /// [`CallResult`](crate::call::CallResult). `#[derive(Error)]` implements it; the default
+    /// implementation defers to [`Error::abi_decode`], so it cannot return `None`.
+    ///
+    /// # Arguments
+    /// * `bytes` - The revert data, starting with the error selector
+    /// * `validate` - Whether to perform validation during decoding
+    ///
+    /// # Returns
+    /// The decoded error instance, or `None` if the data is not one of this type's errors
+    fn try_abi_decode(bytes: &[u8], validate: bool) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(Self::abi_decode(bytes, validate))
+    }
 }
 
 /// Reverts the current contract execution without any error data.
@@ -126,11 +147,17 @@ pub fn revert() -> ! {
 /// # Note
 /// This function never returns - it uses the `!` return type to indicate divergence.
 pub fn revert_with_error(data: &[u8]) -> ! {
-    let (offset, size) = (data.as_ptr() as u64, data.len() as u64);
-    unsafe {
-        asm!("ecall",
-            in("a0") offset, in("a1") size,
-            in("t0") u8::from(Syscall::Revert));
+    #[cfg(feature = "test-env")]
+    crate::mock::revert(data);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let (offset, size) = (data.as_ptr() as u64, data.len() as u64);
+        unsafe {
+            asm!("ecall",
+                in("a0") offset, in("a1") size,
+                in("t0") u8::from(Syscall::Revert));
+        }
+        unreachable!()
     }
-    unreachable!()
 }
@@ -0,0 +1,88 @@
+//! # Guards
+//!
+//! Runtime support for the `#[guard]` and `#[nonreentrant]` attributes of `#[contract]`
+//! methods, which make access control and reentrancy protection declarative.
+//!
+//! ## Guards
+//!
+//! A guard is a contract method checked before the guarded method runs. It returns a
+//! [`Guard`]: a `bool`, reverting without data when `false`, or a `Result<(), E>` with a
+//! `#[derive(Error)]` type, reverting with the encoded error when `Err`.
+//!
+//! ```rust,ignore
+//! #[contract]
+//! impl Token {
+//!     #[guard(only_owner)]
+//!     pub fn mint(&mut self, to: Address, amount: U256) -> Result<bool, TokenError> {
+//!         // Only reached when `only_owner` returned `Ok(())`
+//!     }
+//!
+//!     fn only_owner(&self) -> Result<(), TokenError> {
+//!         match msg_sender() == self.owner.read() {
+//!             true => Ok(()),
+//!             false => Err(TokenError::OnlyOwner),
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Reentrancy Lock
+//!
+//! `#[nonreentrant]` methods take a lock stored at [`REENTRANCY_LOCK_SLOT`] for the duration
+//! of the call, so a `#[nonreentrant]` method cannot be entered again, directly or through
+//! another `#[nonreentrant]` method, until it returns. Reentrant calls revert with
+//! `ReentrancyGuardReentrantCall()`, the error OpenZeppelin's `ReentrancyGuard` uses.
+
+use crate::{error::Error, revert, revert_with_error, sload, sstore};
+use alloy_core::primitives::U256;
+
+/// The storage slot of the reentrancy lock: the ERC-7201 root of the namespace
+/// `hybrid.reentrancy_guard`, so it cannot collide with `#[storage]` fields.
+pub const REENTRANCY_LOCK_SLOT: U256 =
+    U256::from_be_bytes(hybrid_abi::erc7201_root("hybrid.reentrancy_guard"));
+
+/// The revert data of a reentrant call, the selector of `ReentrancyGuardReentrantCall()`.
+pub const REENTRANT_CALL: [u8; 4] =
+    crate::abi::selector("ReentrancyGuardReentrantCall", "()").to_be_bytes();
+
+/// The result of a `#[guard]` method.
+pub trait Guard {
+    /// Reverts the call unless the guard passed.
+    fn check(self);
+}
+
+impl Guard for bool {
+    fn check(self) {
+        if !self {
+            revert();
+        }
+    }
+}
+
+impl<E: Error> Guard for Result<(), E> {
+    fn check(self) {
+        if let Err(err) = self {
+            revert_with_error(&err.abi_encode());
+        }
+    }
+}
+
+/// Takes the reentrancy lock, reverting if it is already held.
+pub fn nonreentrant_enter() {
+    nonreentrant_check();
+    sstore(REENTRANCY_LOCK_SLOT, U256::from(1));
+}
+
+/// Releases the reentrancy lock.
+pub fn nonreentrant_exit() {
+    sstore(REENTRANCY_LOCK_SLOT, U256::ZERO);
+}
+
+/// Reverts if the reentrancy lock is held, without taking it.
+///
+/// Used for `#[nonreentrant]` view methods, which cannot write storage.
+pub fn nonreentrant_check() {
+    if sload(REENTRANCY_LOCK_SLOT) != U256::ZERO {
+        revert_with_error(&REENTRANT_CALL);
+    }
+}
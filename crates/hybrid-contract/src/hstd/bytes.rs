@@ -0,0 +1,344 @@
+//! # Dynamic Bytes and String Storage Implementation
+//!
+//! This module implements `StorageBytes` and `StorageString`, which store variable-length
+//! data with the same encoding Solidity uses for `bytes` and `string` state variables.
+//!
+//! ## Storage Layout
+//!
+//! For a value allocated at slot `p`:
+//! - **Short values** (at most 31 bytes) are stored inline: the data occupies the
+//!   higher-order bytes of slot `p` and the lowest byte holds `length * 2`.
+//! - **Long values** (32 bytes or more) store `length * 2 + 1` in slot `p`, and the data
+//!   in consecutive slots starting at `keccak256(abi_encode(p))`, the last one zero-padded.
+//!
+//! The lowest bit of slot `p` therefore tells both encodings apart. Values written here are
+//! read identically by Solidity contracts and tooling, and vice versa.
+//!
+//! ## Usage
+//!
+//! ```rust,no_run
+//! use hybrid_contract::hstd::{Mapping, StorageString};
+//! use alloy_core::primitives::U256;
+//!
+//! let mut name: StorageString = StorageString::default();
+//! name.write("Hybrid Token".into());
+//! let value = name.read();
+//!
+//! // Like any other `StorageStorable` type, they can be used as mapping values
+//! let mut token_uris: Mapping<U256, StorageString> = Mapping::default();
+//! token_uris[U256::from(1)].write("ipfs://...".into());
+//! ```
+
+use super::*;
+
+use alloc::string::String;
+use alloy_core::primitives::Bytes;
+
+/// Dynamic byte array storage with Solidity's `bytes` encoding.
+///
+/// # Examples
+/// ```rust,no_run
+/// use hybrid_contract::hstd::StorageBytes;
+///
+/// let mut metadata: StorageBytes = StorageBytes::default();
+/// metadata.write(Bytes::from_static(b"\x01\x02\x03"));
+/// assert_eq!(metadata.len(), 3);
+/// ```
+#[derive(Default)]
+pub struct StorageBytes {
+    /// The storage key holding the length (and short data) of this value
+    id: U256,
+}
+
+/// UTF-8 string storage with Solidity's `string` encoding.
+///
+/// # Examples
+/// ```rust,no_run
+/// use hybrid_contract::hstd::StorageString;
+///
+/// let mut uri: StorageString = StorageString::default();
+/// uri.write("https://example.com/metadata.json".into());
+/// let value = uri.read();
+/// ```
+#[derive(Default)]
+pub struct StorageString {
+    /// The storage key holding the length (and short data) of this value
+    id: U256,
+}
+
+impl StorageLayout for StorageBytes {
+    /// Creates a new byte array stored at the specified storage key.
+    ///
+    /// # Arguments
+    /// * `first` - Least significant 64 bits of the storage key
+    /// * `second` - Second 64 bits of the storage key
+    /// * `third` - Third 64 bits of the storage key
+    /// * `fourth` - Most significant 64 bits of the storage key
+    fn allocate(first: u64, second: u64, third: u64, fourth: u64) -> Self {
+        Self {
+            id: U256::from_limbs([first, second, third, fourth]),
+        }
+    }
+}
+
+impl StorageLayout for StorageString {
+    /// Creates a new string stored at the specified storage key.
+    ///
+    /// # Arguments
+    /// * `first` - Least significant 64 bits of the storage key
+    /// * `second` - Second 64 bits of the storage key
+    /// * `third` - Third 64 bits of the storage key
+    /// * `fourth` - Most significant 64 bits of the storage key
+    fn allocate(first: u64, second: u64, third: u64, fourth: u64) -> Self {
+        Self {
+            id: U256::from_limbs([first, second, third, fourth]),
+        }
+    }
+}
+
+impl StorageStorable for StorageBytes {
+    type Value = Bytes;
+
+    /// Reads the byte array stored at the specified key.
+    fn __read(key: U256) -> Self::Value {
+        read_bytes(key).into()
+    }
+
+    /// Writes a byte array at the specified key, clearing any data slots no longer in use.
+    fn __write(key: U256, value: Self::Value) {
+        write_bytes(key, &value);
+    }
+}
+
+impl StorageStorable for StorageString {
+    type Value = String;
+
+    /// Reads the string stored at the specified key.
+    ///
+    /// # Panics
+    /// Reverts the contract if the stored bytes are not valid UTF-8
+    fn __read(key: U256) -> Self::Value {
+        String::from_utf8(read_bytes(key)).unwrap_or_else(|_| revert())
+    }
+
+    /// Writes a string at the specified key, clearing any data slots no longer in use.
+    fn __write(key: U256, value: Self::Value) {
+        write_bytes(key, value.as_bytes());
+    }
+}
+
+impl DirectStorage<Bytes> for StorageBytes {
+    /// Reads the current byte array from storage.
+    fn read(&self) -> Bytes {
+        Self::__read(self.id)
+    }
+
+    /// Replaces the stored byte array.
+    fn write(&mut self, value: Bytes) {
+        Self::__write(self.id, value)
+    }
+}
+
+impl DirectStorage<String> for StorageString {
+    /// Reads the current string from storage.
+    fn read(&self) -> String {
+        Self::__read(self.id)
+    }
+
+    /// Replaces the stored string.
+    fn write(&mut self, value: String) {
+        Self::__write(self.id, value)
+    }
+}
+
+impl StorageBytes {
+    /// Returns the length in bytes, reading only the slot holding it.
+    pub fn len(&self) -> usize {
+        stored_len(sload(self.id))
+    }
+
+    /// Returns `true` if no bytes are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl StorageString {
+    /// Returns the length in bytes, reading only the slot holding it.
+    pub fn len(&self) -> usize {
+        stored_len(sload(self.id))
+    }
+
+    /// Returns `true` if the stored string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns whether the slot holding the length uses the long encoding.
+fn is_long(header: U256) -> bool {
+    header.bit(0)
+}
+
+/// Decodes the length in bytes from the slot holding it.
+fn stored_len(header: U256) -> usize {
+    if is_long(header) {
+        (header >> 1usize).as_limbs()[0] as usize
+    } else {
+        (header.as_limbs()[0] & 0xff) as usize / 2
+    }
+}
+
+/// Computes the storage key of the first data slot of a long value, `keccak256(abi_encode(key))`.
+fn data_key(key: U256) -> U256 {
+    let key_bytes: [u8; 32] = key.to_be_bytes();
+    keccak256(key_bytes.as_ptr() as u64, key_bytes.len() as u64)
+}
+
+/// Reads the bytes stored at `key` in either encoding.
+fn read_bytes(key: U256) -> Vec<u8> {
+    let header = sload(key);
+    let len = stored_len(header);
+
+    if !is_long(header) {
+        let word: [u8; 32] = header.to_be_bytes();
+        return word[..len].to_vec();
+    }
+
+    let base = data_key(key);
+    let mut data = Vec::with_capacity((len + 31) / 32 * 32);
+    for i in 0..(len + 31) / 32 {
+        let word: [u8; 32] = sload(base.wrapping_add(U256::from(i))).to_be_bytes();
+        data.extend_from_slice(&word);
+    }
+    data.truncate(len);
+    data
+}
+
+/// Writes `data` at `key`, picking the short or long encoding by its length.
+fn write_bytes(key: U256, data: &[u8]) {
+    // Data slots of a previous long value beyond the new length are zeroed, as Solidity does
+    let old_header = sload(key);
+    let old_slots = if is_long(old_header) {
+        (stored_len(old_header) + 31) / 32
+    } else {
+        0
+    };
+
+    let new_slots = if data.len() < 32 {
+        let mut word = [0u8; 32];
+        word[..data.len()].copy_from_slice(data);
+        word[31] = (data.len() * 2) as u8;
+        sstore(key, U256::from_be_bytes(word));
+        0
+    } else {
+        sstore(key, U256::from(data.len() * 2 + 1));
+
+        let base = data_key(key);
+        for (i, chunk) in data.chunks(32).enumerate() {
+            let mut word = [0u8; 32];
+            word[..chunk.len()].copy_from_slice(chunk);
+            sstore(base.wrapping_add(U256::from(i)), U256::from_be_bytes(word));
+        }
+        (data.len() + 31) / 32
+    };
+
+    if old_slots > new_slots {
+        let base = data_key(key);
+        for i in new_slots..old_slots {
+            sstore(base.wrapping_add(U256::from(i)), U256::ZERO);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-env"))]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    /// Storage key of the `i`-th data slot of a long value at slot `p`, `keccak256(p) + i`.
+    fn data_slot(p: u64, i: u64) -> U256 {
+        let hash = alloy_core::primitives::keccak256(U256::from(p).to_be_bytes::<32>());
+        U256::from_be_bytes(hash.0) + U256::from(i)
+    }
+
+    /// A slot holding `data` in its higher-order bytes and `low` in its lowest byte.
+    fn word(data: &[u8], low: u8) -> U256 {
+        let mut word = [0u8; 32];
+        word[..data.len()].copy_from_slice(data);
+        word[31] = low;
+        U256::from_be_bytes(word)
+    }
+
+    #[test]
+    fn test_short_value() {
+        mock::reset();
+        let mut name = StorageString::allocate(1, 0, 0, 0);
+        assert!(name.is_empty());
+        assert_eq!(name.read(), "");
+
+        name.write("Hybrid Token".into());
+        assert_eq!(name.read(), "Hybrid Token");
+        assert_eq!(name.len(), 12);
+        // Stored inline with `len * 2` in the lowest byte
+        assert_eq!(mock::storage(U256::from(1)), word(b"Hybrid Token", 24));
+
+        // 31 bytes is the longest inline value
+        let data = Bytes::from([0xab; 31]);
+        let mut bytes = StorageBytes::allocate(2, 0, 0, 0);
+        bytes.write(data.clone());
+        assert_eq!(bytes.read(), data);
+        assert_eq!(mock::storage(U256::from(2)), word(&data, 62));
+        assert_eq!(mock::storage(data_slot(2, 0)), U256::ZERO);
+    }
+
+    #[test]
+    fn test_long_value() {
+        mock::reset();
+        // Exactly 32 bytes already uses the long encoding
+        let data = Bytes::from([0xcd; 32]);
+        let mut bytes = StorageBytes::allocate(2, 0, 0, 0);
+        bytes.write(data.clone());
+        assert_eq!(bytes.read(), data);
+        assert_eq!(mock::storage(U256::from(2)), U256::from(32 * 2 + 1));
+        assert_eq!(mock::storage(data_slot(2, 0)), U256::from_be_slice(&data));
+        assert_eq!(mock::storage(data_slot(2, 1)), U256::ZERO);
+
+        // The last data slot is zero-padded
+        let data: Bytes = (0..70u8).collect();
+        bytes.write(data.clone());
+        assert_eq!(bytes.read(), data);
+        assert_eq!(bytes.len(), 70);
+        assert_eq!(mock::storage(U256::from(2)), U256::from(70 * 2 + 1));
+        assert_eq!(
+            mock::storage(data_slot(2, 1)),
+            U256::from_be_slice(&data[32..64])
+        );
+        assert_eq!(mock::storage(data_slot(2, 2)), word(&data[64..], 0));
+    }
+
+    #[test]
+    fn test_shrink_long_to_short() {
+        mock::reset();
+        let mut uri = StorageString::allocate(4, 0, 0, 0);
+        uri.write("https://example.com/metadata/0001.json".repeat(2));
+        assert_eq!(uri.len(), 76);
+
+        uri.write("ipfs://x".into());
+        assert_eq!(uri.read(), "ipfs://x");
+        assert_eq!(mock::storage(U256::from(4)), word(b"ipfs://x", 16));
+        // The data slots of the long value are cleared
+        for i in 0..3 {
+            assert_eq!(mock::storage(data_slot(4, i)), U256::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_invalid_utf8_reverts() {
+        mock::reset();
+        let mut bytes = StorageBytes::allocate(0, 0, 0, 0);
+        bytes.write(Bytes::from_static(&[0xff, 0xfe]));
+        let string = StorageString::allocate(0, 0, 0, 0);
+        assert!(mock::try_call(|| string.read()).is_err());
+    }
+}
@@ -16,6 +16,8 @@
 //! - Type-safe key-value storage with automatic ABI encoding/decoding
 //! - Keccak256-based key derivation matching Solidity's behavior
 //! - Support for nested mappings (mapping of mappings)
+//! - Support for vectors as values (`Mapping<K, StorageVec<T>>`)
+//! - Lazy field access for struct values (`Mapping<K, Slot<S>>` with `S: StorageStruct`)
 //! - Guard-based access pattern for memory management
 //! - Integration with the storage layout system
 //!
@@ -190,8 +192,6 @@ where
 pub struct MappingGuard<V>
 where
     V: StorageStorable,
-    V::Value:
-        SolValue + core::convert::From<<<V::Value as SolValue>::SolType as SolType>::RustType>,
 {
     /// The computed storage key for this mapping entry
     storage_key: U256,
@@ -202,8 +202,6 @@ where
 impl<V> MappingGuard<V>
 where
     V: StorageStorable,
-    V::Value:
-        SolValue + core::convert::From<<<V::Value as SolValue>::SolType as SolType>::RustType>,
 {
     /// Creates a new mapping guard for the specified storage key.
     ///
@@ -223,8 +221,6 @@ where
 impl<V> IndirectStorage<V> for MappingGuard<V>
 where
     V: StorageStorable,
-    V::Value:
-        SolValue + core::convert::From<<<V::Value as SolValue>::SolType as SolType>::RustType>,
 {
     /// Writes a value to storage at the location managed by this guard.
     ///
@@ -261,6 +257,36 @@ where
     }
 }
 
+/// Provides lazy access to the fields of a struct stored in a mapping.
+///
+/// # Examples
+/// ```rust,no_run
+/// let positions: Mapping<Address, Slot<Position>> = Mapping::default();
+/// let expiry = positions[owner].expiry().read();  // Only reads the slot holding `expiry`
+/// ```
+impl<S: StorageStruct> Deref for MappingGuard<Slot<S>> {
+    type Target = S::Fields;
+
+    fn deref(&self) -> &Self::Target {
+        let fields = S::__fields(self.storage_key);
+
+        // Manually handle memory using the global allocator
+        unsafe {
+            let layout = Layout::new::<S::Fields>();
+
+            // Allocate using the `GLOBAL` fixed memory allocator
+            #[allow(static_mut_refs)]
+            let ptr = GLOBAL.alloc(layout) as *mut S::Fields;
+
+            // Write the accessors to the allocated memory
+            ptr.write(fields);
+
+            // Return a reference with 'static lifetime (`GLOBAL` never deallocates)
+            &*ptr
+        }
+    }
+}
+
 /// Index implementation for read-only access to mapping values.
 ///
 /// This implementation allows immutable access to mapping values using the index syntax.
@@ -270,9 +296,6 @@ impl<K, V> Index<K> for Mapping<K, V>
 where
     K: SolValue + 'static,
     V: StorageStorable + 'static,
-    V::Value: SolValue
-        + core::convert::From<<<V::Value as SolValue>::SolType as SolType>::RustType>
-        + 'static,
 {
     type Output = MappingGuard<V>;
 
@@ -330,9 +353,6 @@ impl<K, V> IndexMut<K> for Mapping<K, V>
 where
     K: SolValue + 'static,
     V: StorageStorable + 'static,
-    V::Value: SolValue
-        + core::convert::From<<<V::Value as SolValue>::SolType as SolType>::RustType>
-        + 'static,
 {
     /// Provides mutable access to a mapping value by key.
     ///
@@ -534,3 +554,76 @@ where
         }
     }
 }
+
+/// Index implementation for mappings of storage vectors.
+impl<K, T> Index<K> for Mapping<K, StorageVec<T>>
+where
+    K: SolValue + 'static,
+    T: 'static,
+{
+    type Output = StorageVec<T>;
+
+    /// Provides read-only access to the vector stored under `key`.
+    ///
+    /// As in Solidity, the vector's length lives at the derived mapping key and its elements
+    /// at `keccak256` of that key.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// let orders: Mapping<Address, StorageVec<U256>> = Mapping::default();
+    /// let count = orders[user_address].len();
+    /// ```
+    fn index(&self, key: K) -> &Self::Output {
+        let [first, second, third, fourth] = *self.encode_key(key).as_limbs();
+        let vec = StorageVec::<T>::allocate(first, second, third, fourth);
+
+        // Manually handle memory using the global allocator
+        unsafe {
+            let layout = Layout::new::<StorageVec<T>>();
+
+            // Allocate using the `GLOBAL` fixed memory allocator
+            #[allow(static_mut_refs)]
+            let ptr = GLOBAL.alloc(layout) as *mut StorageVec<T>;
+
+            // Write the vector to the allocated memory
+            ptr.write(vec);
+
+            // Return a reference with 'static lifetime (`GLOBAL` never deallocates)
+            &*ptr
+        }
+    }
+}
+
+/// Mutable index implementation for mappings of storage vectors.
+impl<K, T> IndexMut<K> for Mapping<K, StorageVec<T>>
+where
+    K: SolValue + 'static,
+    T: 'static,
+{
+    /// Provides mutable access to the vector stored under `key`.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// let mut orders: Mapping<Address, StorageVec<U256>> = Mapping::default();
+    /// orders[user_address].push(U256::from(42));
+    /// ```
+    fn index_mut(&mut self, key: K) -> &mut Self::Output {
+        let [first, second, third, fourth] = *self.encode_key(key).as_limbs();
+        let vec = StorageVec::<T>::allocate(first, second, third, fourth);
+
+        // Manually handle memory using the global allocator
+        unsafe {
+            let layout = Layout::new::<StorageVec<T>>();
+
+            // Allocate using the `GLOBAL` fixed memory allocator
+            #[allow(static_mut_refs)]
+            let ptr = GLOBAL.alloc(layout) as *mut StorageVec<T>;
+
+            // Write the vector to the allocated memory
+            ptr.write(vec);
+
+            // Return a reference with 'static lifetime (`GLOBAL` never deallocates)
+            &mut *ptr
+        }
+    }
+}
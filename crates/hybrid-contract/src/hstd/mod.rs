@@ -32,14 +32,45 @@
 //! let balance = balances[user_address].read();
 //! ```
 //!
+//! ### `StorageVec<T>`
+//! A dynamic array with Solidity's storage layout, usable on its own or as a mapping value.
+//! ```rust,no_run
+//! let mut holders: StorageVec<Address> = StorageVec::default();
+//! holders.push(user_address);
+//! let first = holders.get(0);
+//! ```
+//!
+//! ### `StorageBytes` and `StorageString`
+//! Variable-length data with Solidity's short (inline) and long (hashed) encoding.
+//! ```rust,no_run
+//! let mut name: StorageString = StorageString::default();
+//! name.write("Hybrid Token".into());
+//! let value = name.read();
+//! ```
+//!
+//! ### Storage Structs
+//! Structs deriving `StorageStruct` are packed over consecutive slots with Solidity's rules
+//! and can be held by a `Slot`, with lazy access to individual fields.
+//! ```rust,no_run
+//! #[derive(StorageStruct)]
+//! pub struct Position {
+//!     pub owner: Address,
+//!     pub expiry: u64,
+//! }
+//!
+//! let mut position: Slot<Position> = Slot::default();
+//! position.expiry().write(1_700_000_000);
+//! let owner = position.owner().read();
+//! ```
+//!
 //! ## Storage Layout Requirements
 //!
 //! All storage types must implement specific traits based on their usage:
 //!
 //! 1. **`StorageLayout`**: Required for all storage types to enable allocation by storage macros
-//! 2. **Direct access types** (like `Slot`): Must implement `StorageStorable` and `DirectStorage`
+//! 2. **Direct access types** (like `Slot` and `StorageString`): Must implement `StorageStorable` and `DirectStorage`
 //! 3. **Indirect access types** (like mapping guards): Must implement `IndirectStorage`
-//! 4. **Container types** (like `Mapping`): May only implement `StorageLayout` and provide access via guards
+//! 4. **Container types** (like `Mapping` and `StorageVec`): May only implement `StorageLayout` and provide access via guards
 //!
 //! ## ABI Encoding
 //!
@@ -70,12 +101,24 @@ use alloy_sol_types::{SolType, SolValue};
 extern crate alloc;
 use alloc::vec::Vec;
 
+mod bytes;
+pub use bytes::{StorageBytes, StorageString};
+
+mod packed;
+pub use packed::{
+    decode_field, encode_field, load_words, pack, packed_size, read_packed, store_words,
+    write_packed, PackedPosition, StorageField,
+};
+
 mod mapping;
 pub use mapping::Mapping;
 
 mod slot;
 pub use slot::Slot;
 
+mod vec;
+pub use vec::{StorageVec, StorageVecIter};
+
 /// Trait for storage types that require allocation in the contract's storage layout.
 ///
 /// This trait enables storage types to be automatically allocated by storage macros.
@@ -101,8 +144,13 @@ pub use slot::Slot;
 ///     }
 /// }
 /// ```
-// TODO: enhance `storage` macro to handle complex types (like tuples or custom structs)
 pub trait StorageLayout {
+    /// Number of consecutive storage slots the type occupies from its storage key.
+    ///
+    /// The `storage` macro uses it to place the next field. Types storing their data at hashed
+    /// locations (like `Mapping`) only occupy their base slot.
+    const SLOTS: usize = 1;
+
     /// Allocates a storage type with the given storage key.
     ///
     /// # Arguments
@@ -123,7 +171,7 @@ pub trait StorageLayout {
 /// values when they are written to or read from storage.
 ///
 /// # Type Parameters
-/// * `Value` - The Rust type that this storage can hold, must be ABI-encodable
+/// * `Value` - The Rust type that this storage can hold
 ///
 /// # Safety
 /// Implementations of this trait directly interact with the EVM storage system
@@ -131,8 +179,7 @@ pub trait StorageLayout {
 /// to avoid data corruption.
 pub trait StorageStorable {
     /// The type of value this storage can hold
-    type Value: SolValue
-        + core::convert::From<<<Self::Value as SolValue>::SolType as SolType>::RustType>;
+    type Value;
 
     /// Reads a value from storage at the specified key.
     ///
@@ -202,8 +249,6 @@ where
 pub trait IndirectStorage<V>
 where
     V: StorageStorable,
-    V::Value:
-        SolValue + core::convert::From<<<V::Value as SolValue>::SolType as SolType>::RustType>,
 {
     /// Reads the value from the storage location managed by this guard.
     ///
@@ -217,3 +262,48 @@ where
     /// * `value` - The new value to store
     fn write(&mut self, value: V::Value);
 }
+
+/// Values that can be held by a [`Slot`].
+///
+/// Implemented for every `SolValue` type, which occupies a single slot, and for structs by
+/// `#[derive(StorageStruct)]`, which lays their fields out over consecutive slots.
+pub trait StorageValue: Sized {
+    /// Number of consecutive storage slots a value occupies.
+    const SLOTS: usize = 1;
+
+    /// Reads a value stored from the specified key.
+    fn __read(key: U256) -> Self;
+
+    /// Writes a value from the specified key.
+    fn __write(key: U256, value: Self);
+}
+
+/// Structs stored with Solidity's layout, implemented by `#[derive(StorageStruct)]`.
+///
+/// Fields are packed into consecutive slots following Solidity's rules (see
+/// [`pack`]), and can be accessed lazily through [`StorageStruct::Fields`] without
+/// reading or writing the whole struct:
+///
+/// ```rust,no_run
+/// #[derive(StorageStruct)]
+/// pub struct Position {
+///     pub owner: Address, // slot 0
+///     pub expiry: u64,    // slot 0
+///     pub active: bool,   // slot 0
+///     pub amount: U256,   // slot 1
+/// }
+///
+/// let mut position: Slot<Position> = Slot::default();
+/// position.owner().write(owner);  // Only touches slot 0
+/// let amount = position.amount().read();
+///
+/// let positions: Mapping<Address, Slot<Position>> = Mapping::default();
+/// let expiry = positions[owner].expiry().read();
+/// ```
+pub trait StorageStruct: StorageValue {
+    /// Accessors for the individual fields of a struct stored at a given key.
+    type Fields;
+
+    /// Returns the field accessors of the struct stored at `key`.
+    fn __fields(key: U256) -> Self::Fields;
+}
@@ -0,0 +1,276 @@
+//! # Packed Value Encoding
+//!
+//! Solidity packs value types smaller than a word into shared storage slots: values are laid
+//! out in order, each one starting at the lower-order end of the remaining space, and a value
+//! that does not fit in what is left of a slot moves to the next one. A `bool` followed by an
+//! `Address` and a `u64` thus share a single slot, while a `U256` always takes one of its own.
+//!
+//! This module implements that encoding for `StorageVec` elements and for the fields of
+//! structs deriving `StorageStruct`. Only static value types encoded in a single ABI word are
+//! supported; any other type fails to compile when its packed size is requested.
+//!
+//! ```rust,no_run
+//! // (Address, u64, bool, U256) -> slot 0: [bool | u64 | Address], slot 1: [U256]
+//! const LAYOUT: ([PackedPosition; 4], usize) = pack([20, 8, 1, 32]);
+//! assert_eq!(LAYOUT.1, 2);
+//! ```
+
+use core::ops::Range;
+
+use super::*;
+
+/// Location of a packed value relative to a base storage key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedPosition {
+    /// Index of the slot holding the value, counted from the base key
+    pub slot: usize,
+    /// Offset in bytes from the lower-order end of the slot
+    pub offset: usize,
+    /// Size of the value in bytes
+    pub size: usize,
+}
+
+impl PackedPosition {
+    /// Storage key of the slot holding the value.
+    pub(crate) fn key(&self, base: U256) -> U256 {
+        base.wrapping_add(U256::from(self.slot))
+    }
+
+    /// Byte range of the value in the big-endian encoding of its slot.
+    pub(crate) fn range(&self) -> Range<usize> {
+        let end = 32 - self.offset;
+        end - self.size..end
+    }
+}
+
+/// A single packed value in storage, such as a field of a struct deriving `StorageStruct`.
+///
+/// Reading or writing a field only touches the slot holding it.
+pub struct StorageField<V> {
+    /// Storage key the position is relative to
+    base: U256,
+    /// Position of the value
+    position: PackedPosition,
+    /// Phantom data to maintain type information about the value type
+    _pd: PhantomData<V>,
+}
+
+impl<V> StorageField<V>
+where
+    V: SolValue + core::convert::From<<<V as SolValue>::SolType as SolType>::RustType>,
+{
+    /// Creates an accessor for the value at `position` relative to `base`.
+    pub fn new(base: U256, position: PackedPosition) -> Self {
+        Self {
+            base,
+            position,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Reads the value from storage.
+    pub fn read(&self) -> V {
+        read_packed(self.base, self.position)
+    }
+
+    /// Writes the value, preserving the other values sharing its slot.
+    pub fn write(&mut self, value: V) {
+        write_packed(self.base, self.position, &value)
+    }
+}
+
+/// Returns the number of bytes `V` occupies when packed in storage.
+///
+/// # Panics
+/// Fails to compile when used in a constant for a type that is not a static value type
+/// encoded in a single ABI word (tuples, arrays, `String`, `Bytes`, ...).
+pub const fn packed_size<V: SolValue>() -> usize {
+    match (
+        <V::SolType as SolType>::ENCODED_SIZE,
+        <V::SolType as SolType>::PACKED_ENCODED_SIZE,
+    ) {
+        (Some(32), Some(size)) if size > 0 && size <= 32 => size,
+        _ => panic!("only static value types fitting in a single word can be packed"),
+    }
+}
+
+/// Lays out values of the given packed sizes in order, following Solidity's packing rules.
+///
+/// # Returns
+/// The position of each value and the number of slots used
+pub const fn pack<const N: usize>(sizes: [usize; N]) -> ([PackedPosition; N], usize) {
+    let mut positions = [PackedPosition {
+        slot: 0,
+        offset: 0,
+        size: 0,
+    }; N];
+
+    let (mut slot, mut offset, mut i) = (0, 0, 0);
+    while i < N {
+        if offset + sizes[i] > 32 {
+            slot += 1;
+            offset = 0;
+        }
+        positions[i] = PackedPosition {
+            slot,
+            offset,
+            size: sizes[i],
+        };
+        offset += sizes[i];
+        i += 1;
+    }
+
+    (positions, if offset == 0 { slot } else { slot + 1 })
+}
+
+/// Reads the value at `position` from storage.
+pub fn read_packed<V>(base: U256, position: PackedPosition) -> V
+where
+    V: SolValue + core::convert::From<<<V as SolValue>::SolType as SolType>::RustType>,
+{
+    let word: [u8; 32] = sload(position.key(base)).to_be_bytes();
+    decode::<V>(&word[position.range()])
+}
+
+/// Writes a value at `position`, preserving the other values sharing its slot.
+pub fn write_packed<V: SolValue>(base: U256, position: PackedPosition, value: &V) {
+    write_packed_bytes(base, position, &value.abi_encode_packed());
+}
+
+/// Writes raw packed bytes at `position`, preserving the other values sharing its slot.
+pub(crate) fn write_packed_bytes(base: U256, position: PackedPosition, packed: &[u8]) {
+    let key = position.key(base);
+    let mut word: [u8; 32] = if position.size == 32 {
+        [0u8; 32]
+    } else {
+        sload(key).to_be_bytes()
+    };
+    word[position.range()].copy_from_slice(packed);
+    sstore(key, U256::from_be_bytes(word));
+}
+
+/// Loads consecutive slots starting at `base`, one per entry of `words`.
+pub fn load_words(base: U256, words: &mut [[u8; 32]]) {
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = sload(base.wrapping_add(U256::from(i))).to_be_bytes();
+    }
+}
+
+/// Stores `words` in consecutive slots starting at `base`.
+pub fn store_words(base: U256, words: &[[u8; 32]]) {
+    for (i, word) in words.iter().enumerate() {
+        sstore(base.wrapping_add(U256::from(i)), U256::from_be_bytes(*word));
+    }
+}
+
+/// Decodes the value at `position` from slots loaded with [`load_words`].
+pub fn decode_field<V>(words: &[[u8; 32]], position: PackedPosition) -> V
+where
+    V: SolValue + core::convert::From<<<V as SolValue>::SolType as SolType>::RustType>,
+{
+    decode::<V>(&words[position.slot][position.range()])
+}
+
+/// Encodes a value at `position` into slots later written with [`store_words`].
+pub fn encode_field<V: SolValue>(words: &mut [[u8; 32]], position: PackedPosition, value: &V) {
+    words[position.slot][position.range()].copy_from_slice(&value.abi_encode_packed());
+}
+
+/// Decodes a value from its packed bytes.
+fn decode<V>(packed: &[u8]) -> V
+where
+    V: SolValue + core::convert::From<<<V as SolValue>::SolType as SolType>::RustType>,
+{
+    // Rebuild the ABI word from the packed bytes: fixed bytes are left-aligned, signed
+    // integers sign-extended and everything else right-aligned
+    let sol_name = <V::SolType as SolType>::SOL_NAME;
+    let mut encoded = [0u8; 32];
+    if sol_name.starts_with("bytes") {
+        encoded[..packed.len()].copy_from_slice(packed);
+    } else {
+        if sol_name.starts_with("int") && packed[0] & 0x80 != 0 {
+            encoded = [0xff; 32];
+        }
+        encoded[32 - packed.len()..].copy_from_slice(packed);
+    }
+
+    V::abi_decode(&encoded, false).unwrap_or_else(|_| revert())
+}
+
+#[cfg(all(test, feature = "test-env"))]
+mod tests {
+    use super::*;
+    use crate::mock;
+    use alloy_core::primitives::{Address, FixedBytes};
+
+    fn position(slot: usize, offset: usize, size: usize) -> PackedPosition {
+        PackedPosition { slot, offset, size }
+    }
+
+    #[test]
+    fn test_pack() {
+        // (Address, u64, bool, U256, u128, u128, u8)
+        let (positions, slots) = pack([20, 8, 1, 32, 16, 16, 1]);
+        assert_eq!(
+            positions,
+            [
+                position(0, 0, 20),
+                position(0, 20, 8),
+                position(0, 28, 1),
+                // Does not fit in the 3 bytes left of slot 0
+                position(1, 0, 32),
+                position(2, 0, 16),
+                position(2, 16, 16),
+                position(3, 0, 1),
+            ]
+        );
+        assert_eq!(slots, 4);
+
+        // A full last slot does not start another one
+        assert_eq!(pack([16, 16]).1, 1);
+        assert_eq!(packed_size::<Address>(), 20);
+        assert_eq!(packed_size::<bool>(), 1);
+        assert_eq!(packed_size::<U256>(), 32);
+    }
+
+    #[test]
+    fn test_write_packed_keeps_neighbours() {
+        mock::reset();
+        let base = U256::from(7);
+        let (positions, _) = pack([20, 8, 1]);
+        let owner = Address::repeat_byte(0xa1);
+
+        write_packed(base, positions[0], &owner);
+        write_packed(base, positions[1], &u64::MAX);
+        write_packed(base, positions[2], &true);
+
+        let expected = U256::from_be_slice(owner.as_slice())
+            | U256::from(u64::MAX) << 160
+            | U256::from(1) << 224;
+        assert_eq!(mock::storage(base), expected);
+
+        // Overwriting the middle value leaves the others intact
+        write_packed(base, positions[1], &5u64);
+        assert_eq!(read_packed::<Address>(base, positions[0]), owner);
+        assert_eq!(read_packed::<u64>(base, positions[1]), 5);
+        assert!(read_packed::<bool>(base, positions[2]));
+    }
+
+    #[test]
+    fn test_read_packed_sign_and_alignment() {
+        mock::reset();
+        let base = U256::from(1);
+        let (positions, _) = pack([2, 4, 16]);
+
+        write_packed(base, positions[0], &-2i16);
+        write_packed(base, positions[1], &FixedBytes([1, 2, 3, 4]));
+        write_packed(base, positions[2], &-1i128);
+
+        assert_eq!(read_packed::<i16>(base, positions[0]), -2);
+        assert_eq!(
+            read_packed::<FixedBytes<4>>(base, positions[1]),
+            FixedBytes([1, 2, 3, 4])
+        );
+        assert_eq!(read_packed::<i128>(base, positions[2]), -1);
+    }
+}
@@ -41,7 +41,12 @@
 
 use super::*;
 
-use core::ops::{Add, AddAssign, Sub, SubAssign};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ops::{Add, AddAssign, Deref, Sub, SubAssign},
+};
+
+use crate::allocator::ALLOC as GLOBAL;
 
 /// A type-safe wrapper for a single storage slot in the Hybrid VM.
 ///
@@ -81,7 +86,10 @@ pub struct Slot<V> {
     _pd: PhantomData<V>,
 }
 
-impl<V> StorageLayout for Slot<V> {
+impl<V: StorageValue> StorageLayout for Slot<V> {
+    /// A slot spans as many slots as its value occupies (more than one for structs).
+    const SLOTS: usize = V::SLOTS;
+
     /// Creates a new slot with the specified storage key.
     ///
     /// This method is typically called by storage allocation macros to assign
@@ -103,12 +111,34 @@ impl<V> StorageLayout for Slot<V> {
     }
 }
 
-impl<V> StorageStorable for Slot<V>
+impl<V: StorageValue> StorageStorable for Slot<V> {
+    type Value = V;
+
+    /// Reads a value from storage at the specified key.
+    ///
+    /// # Arguments
+    /// * `key` - The storage key to read from
+    ///
+    /// # Returns
+    /// The decoded value from storage
+    fn __read(key: U256) -> Self::Value {
+        V::__read(key)
+    }
+
+    /// Writes a value to storage at the specified key.
+    ///
+    /// # Arguments
+    /// * `key` - The storage key to write to
+    /// * `value` - The value to encode and store
+    fn __write(key: U256, value: Self::Value) {
+        V::__write(key, value)
+    }
+}
+
+impl<V> StorageValue for V
 where
     V: SolValue + core::convert::From<<<V as SolValue>::SolType as SolType>::RustType>,
 {
-    type Value = V;
-
     /// Reads a value from storage at the specified key.
     ///
     /// This method performs an SLOAD operation to retrieve the raw bytes from storage,
@@ -122,7 +152,7 @@ where
     ///
     /// # Panics
     /// Reverts the contract if ABI decoding fails, indicating corrupted storage data
-    fn __read(key: U256) -> Self::Value {
+    fn __read(key: U256) -> Self {
         let bytes: [u8; 32] = sload(key).to_be_bytes();
         V::abi_decode(&bytes, false).unwrap_or_else(|_| revert())
     }
@@ -135,7 +165,7 @@ where
     /// # Arguments
     /// * `key` - The storage key to write to
     /// * `value` - The value to encode and store
-    fn __write(key: U256, value: Self::Value) {
+    fn __write(key: U256, value: Self) {
         let bytes = value.abi_encode();
         let mut padded = [0u8; 32];
         padded[..bytes.len()].copy_from_slice(&bytes);
@@ -179,6 +209,37 @@ where
     }
 }
 
+/// Provides lazy access to the fields of a struct held by the slot.
+///
+/// # Examples
+/// ```rust,no_run
+/// let mut position: Slot<Position> = Slot::default();
+/// position.owner().write(owner);
+/// let expiry = position.expiry().read();
+/// ```
+impl<V: StorageStruct> Deref for Slot<V> {
+    type Target = V::Fields;
+
+    fn deref(&self) -> &Self::Target {
+        let fields = V::__fields(self.id);
+
+        // Manually handle memory using the global allocator
+        unsafe {
+            let layout = Layout::new::<V::Fields>();
+
+            // Allocate using the `GLOBAL` fixed memory allocator
+            #[allow(static_mut_refs)]
+            let ptr = GLOBAL.alloc(layout) as *mut V::Fields;
+
+            // Write the accessors to the allocated memory
+            ptr.write(fields);
+
+            // Return a reference with 'static lifetime (`GLOBAL` never deallocates)
+            &*ptr
+        }
+    }
+}
+
 /// Arithmetic and comparison trait implementations to improve developer experience.
 /// These traits allow slots to be used naturally in arithmetic expressions and comparisons.
 
@@ -0,0 +1,327 @@
+//! # Solidity-like Dynamic Array Storage Implementation
+//!
+//! This module implements the `StorageVec<T>` type, which provides a growable list of values
+//! in persistent storage with the same layout Solidity uses for dynamic storage arrays
+//! (`T[] storage`).
+//!
+//! ## Storage Layout
+//!
+//! For a vector allocated at slot `p`:
+//! ```text
+//! length        = sload(p)
+//! element_slot  = keccak256(abi_encode(p)) + (index / elements_per_slot)
+//! ```
+//!
+//! Elements are packed like Solidity packs them (see `hstd::packed`): values whose packed size
+//! is at most 16 bytes share a slot (e.g. 32 `bool`s or 2 `u128`s per slot), filling it from
+//! the lower-order bytes upwards, while larger values (`Address`, `U256`, `B256`, ...) occupy a slot each. Data written
+//! by a `StorageVec` is therefore readable by Solidity tooling, and contracts migrated from
+//! Solidity keep their existing array storage.
+//!
+//! Only static value types fitting a single word are supported as elements; using a tuple,
+//! `String` or `Bytes` fails to compile.
+//!
+//! ## Usage
+//!
+//! ```rust,no_run
+//! use hybrid_contract::hstd::{Mapping, StorageVec};
+//! use alloy_core::primitives::{Address, U256};
+//!
+//! let mut holders: StorageVec<Address> = StorageVec::default();
+//! holders.push(Address::ZERO);
+//! assert_eq!(holders.len(), 1);
+//! assert_eq!(holders.get(0), Some(Address::ZERO));
+//!
+//! for holder in holders.iter() {
+//!     // ...
+//! }
+//!
+//! // Vectors nest inside mappings
+//! let mut orders: Mapping<Address, StorageVec<U256>> = Mapping::default();
+//! orders[user_address].push(U256::from(42));
+//! let last = orders[user_address].pop();
+//! ```
+
+use super::packed::write_packed_bytes;
+use super::*;
+
+/// A Solidity-compatible dynamic array in persistent storage.
+///
+/// # Type Parameters
+/// * `T` - The element type. Must be a static ABI type encoded in a single word (integers,
+///   `bool`, `Address`, fixed bytes).
+///
+/// # Examples
+/// ```rust,no_run
+/// use hybrid_contract::hstd::StorageVec;
+/// use alloy_core::primitives::U256;
+///
+/// let mut proposals: StorageVec<U256> = StorageVec::default();
+/// proposals.push(U256::from(1));
+/// proposals.set(0, U256::from(2));
+/// assert_eq!(proposals.pop(), Some(U256::from(2)));
+/// assert!(proposals.is_empty());
+/// ```
+#[derive(Default)]
+pub struct StorageVec<T> {
+    /// The storage key holding the length of this vector
+    id: U256,
+    /// Phantom data to maintain type information about the element type
+    _pd: PhantomData<T>,
+}
+
+impl<T> StorageLayout for StorageVec<T> {
+    /// Creates a new vector whose length is stored at the specified storage key.
+    ///
+    /// # Arguments
+    /// * `first` - Least significant 64 bits of the storage key
+    /// * `second` - Second 64 bits of the storage key
+    /// * `third` - Third 64 bits of the storage key
+    /// * `fourth` - Most significant 64 bits of the storage key
+    ///
+    /// # Returns
+    /// A new `StorageVec<T>` instance configured with the provided storage key
+    fn allocate(first: u64, second: u64, third: u64, fourth: u64) -> Self {
+        Self {
+            id: U256::from_limbs([first, second, third, fourth]),
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<T> StorageVec<T>
+where
+    T: SolValue + core::convert::From<<<T as SolValue>::SolType as SolType>::RustType>,
+{
+    /// Number of bytes an element occupies in storage, checked when the vector is used.
+    const ELEMENT_SIZE: usize = packed_size::<T>();
+
+    /// Number of elements sharing a storage slot.
+    const ELEMENTS_PER_SLOT: usize = 32 / Self::ELEMENT_SIZE;
+
+    /// Returns the number of elements in the vector.
+    pub fn len(&self) -> usize {
+        sload(self.id).as_limbs()[0] as usize
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(read_packed(self.data_key(), Self::position(index)))
+    }
+
+    /// Overwrites the element at `index`.
+    ///
+    /// # Panics
+    /// Reverts the contract if `index` is out of bounds
+    pub fn set(&mut self, index: usize, value: T) {
+        if index >= self.len() {
+            revert();
+        }
+        write_packed(self.data_key(), Self::position(index), &value);
+    }
+
+    /// Appends an element to the end of the vector.
+    pub fn push(&mut self, value: T) {
+        let len = self.len();
+        write_packed(self.data_key(), Self::position(len), &value);
+        sstore(self.id, U256::from(len + 1));
+    }
+
+    /// Removes the last element and returns it, or `None` if the vector is empty.
+    ///
+    /// The freed storage is zeroed, as Solidity's `pop()` does.
+    pub fn pop(&mut self) -> Option<T> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+
+        let base = self.data_key();
+        let position = Self::position(len - 1);
+        let value = read_packed(base, position);
+        write_packed_bytes(base, position, &[0u8; 32][..Self::ELEMENT_SIZE]);
+        sstore(self.id, U256::from(len - 1));
+        Some(value)
+    }
+
+    /// Returns an iterator over the elements, reading each one from storage as it is yielded.
+    pub fn iter(&self) -> StorageVecIter<'_, T> {
+        StorageVecIter {
+            base: self.data_key(),
+            index: 0,
+            len: self.len(),
+            _vec: self,
+        }
+    }
+
+    /// Computes the storage key of the first element, `keccak256(abi_encode(id))`.
+    fn data_key(&self) -> U256 {
+        let id_bytes: [u8; 32] = self.id.to_be_bytes();
+        keccak256(id_bytes.as_ptr() as u64, id_bytes.len() as u64)
+    }
+
+    /// Returns the position of the element at `index`, relative to the first element's slot.
+    fn position(index: usize) -> PackedPosition {
+        PackedPosition {
+            slot: index / Self::ELEMENTS_PER_SLOT,
+            offset: (index % Self::ELEMENTS_PER_SLOT) * Self::ELEMENT_SIZE,
+            size: Self::ELEMENT_SIZE,
+        }
+    }
+}
+
+/// Iterator over the elements of a [`StorageVec`], returned by [`StorageVec::iter`].
+///
+/// The length is read once when the iterator is created.
+pub struct StorageVecIter<'a, T> {
+    /// Storage key of the first element
+    base: U256,
+    /// Index of the next element to yield
+    index: usize,
+    /// Length of the vector when iteration started
+    len: usize,
+    /// Ties the iterator to the vector it reads from
+    _vec: &'a StorageVec<T>,
+}
+
+impl<T> Iterator for StorageVecIter<'_, T>
+where
+    T: SolValue + core::convert::From<<<T as SolValue>::SolType as SolType>::RustType>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.index >= self.len {
+            return None;
+        }
+        let value = read_packed(self.base, StorageVec::<T>::position(self.index));
+        self.index += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for StorageVecIter<'_, T> where
+    T: SolValue + core::convert::From<<<T as SolValue>::SolType as SolType>::RustType>
+{
+}
+
+#[cfg(all(test, feature = "test-env"))]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    /// Storage key of the `i`-th slot of data of a vector at slot `p`, `keccak256(p) + i`.
+    fn data_slot(p: u64, i: u64) -> U256 {
+        let hash = alloy_core::primitives::keccak256(U256::from(p).to_be_bytes::<32>());
+        U256::from_be_bytes(hash.0) + U256::from(i)
+    }
+
+    #[test]
+    fn test_push_pop_len_index() {
+        mock::reset();
+        let mut vec: StorageVec<U256> = StorageVec::allocate(0, 0, 0, 0);
+        assert!(vec.is_empty());
+        assert_eq!(vec.pop(), None);
+
+        vec.push(U256::from(10));
+        vec.push(U256::from(20));
+        vec.push(U256::from(30));
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec.get(1), Some(U256::from(20)));
+        assert_eq!(vec.get(3), None);
+
+        vec.set(1, U256::from(21));
+        assert_eq!(
+            vec.iter().collect::<Vec<_>>(),
+            [U256::from(10), U256::from(21), U256::from(30)]
+        );
+
+        assert_eq!(vec.pop(), Some(U256::from(30)));
+        assert_eq!(vec.len(), 2);
+        // Setting past the end reverts
+        assert!(mock::try_call(|| vec.set(2, U256::from(1))).is_err());
+    }
+
+    #[test]
+    fn test_slot_derivation() {
+        mock::reset();
+        let mut vec: StorageVec<Address> = StorageVec::allocate(3, 0, 0, 0);
+        vec.push(Address::repeat_byte(0xa1));
+        vec.push(Address::repeat_byte(0xb0));
+
+        // The length is at `p` and element `i` at `keccak256(p) + i`, one address per slot
+        assert_eq!(mock::storage(U256::from(3)), U256::from(2));
+        assert_eq!(
+            mock::storage(data_slot(3, 0)),
+            U256::from_be_slice(Address::repeat_byte(0xa1).as_slice())
+        );
+        assert_eq!(
+            mock::storage(data_slot(3, 1)),
+            U256::from_be_slice(Address::repeat_byte(0xb0).as_slice())
+        );
+
+        // Popping zeroes the freed slot
+        vec.pop();
+        assert_eq!(mock::storage(U256::from(3)), U256::from(1));
+        assert_eq!(mock::storage(data_slot(3, 1)), U256::ZERO);
+    }
+
+    #[test]
+    fn test_packed_elements() {
+        mock::reset();
+        let mut vec: StorageVec<u64> = StorageVec::allocate(5, 0, 0, 0);
+        for value in 1..=5u64 {
+            vec.push(value);
+        }
+
+        // Four `u64`s share a slot, filling it from the lower-order bytes upwards
+        let packed =
+            U256::from(1) | U256::from(2) << 64 | U256::from(3) << 128 | U256::from(4) << 192;
+        assert_eq!(mock::storage(data_slot(5, 0)), packed);
+        assert_eq!(mock::storage(data_slot(5, 1)), U256::from(5));
+
+        // Writing an element leaves its neighbours intact
+        vec.set(1, u64::MAX);
+        assert_eq!(
+            mock::storage(data_slot(5, 0)),
+            packed | U256::from(u64::MAX) << 64
+        );
+        assert_eq!(vec.get(0), Some(1));
+        assert_eq!(vec.get(2), Some(3));
+
+        // Popping only clears the bytes of the popped element
+        assert_eq!(vec.pop(), Some(5));
+        assert_eq!(vec.pop(), Some(4));
+        assert_eq!(
+            mock::storage(data_slot(5, 0)),
+            U256::from(1) | U256::from(u64::MAX) << 64 | U256::from(3) << 128
+        );
+    }
+
+    #[test]
+    fn test_bools_per_slot() {
+        mock::reset();
+        let mut vec: StorageVec<bool> = StorageVec::allocate(0, 0, 0, 0);
+        for i in 0..33 {
+            vec.push(i % 2 == 0);
+        }
+
+        // 32 `bool`s fill a slot, the 33rd starts the next one
+        assert_eq!(mock::storage(data_slot(0, 1)), U256::from(1));
+        assert_eq!(vec.get(31), Some(false));
+        assert_eq!(vec.get(32), Some(true));
+    }
+}
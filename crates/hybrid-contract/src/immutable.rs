@@ -0,0 +1,66 @@
+//! # Immutables
+//!
+//! Runtime support for the `#[immutable]` fields of `#[storage]` structs, the equivalent of
+//! Solidity's `immutable` state variables.
+//!
+//! An immutable is set by the constructor and embedded in the runtime code at deployment,
+//! right after the RISC-V binary, one word each in declaration order:
+//!
+//! ```text
+//! 0xff | runtime ELF | immutable 0 | immutable 1 | ...
+//! ```
+//!
+//! The ELF loader ignores the trailing words, and every call reads them back from the end of
+//! its own code with CODECOPY, so reading an immutable costs no SLOAD.
+//!
+//! ```rust,ignore
+//! #[storage]
+//! pub struct Token {
+//!     balance_of: Mapping<Address, Slot<U256>>,
+//!     #[immutable]
+//!     owner: Address,
+//! }
+//!
+//! #[contract]
+//! impl Token {
+//!     pub fn new(owner: Address) -> Self {
+//!         let mut token = Token::default();
+//!         token.owner = owner;
+//!         token
+//!     }
+//!
+//!     pub fn owner(&self) -> Address {
+//!         self.owner
+//!     }
+//! }
+//! ```
+//!
+//! Immutables must be one-word types: `Address`, `bool`, integers and fixed bytes.
+
+extern crate alloc;
+use crate::env::{code_copy, code_size};
+use alloc::{vec, vec::Vec};
+use alloy_sol_types::{abi::token::WordToken, SolType, SolValue};
+
+/// The size in bytes of an immutable in the runtime code.
+pub const IMMUTABLE_SIZE: usize = 32;
+
+/// Encodes an immutable as the word embedded in the runtime code.
+pub fn encode_immutable<T>(value: &T) -> [u8; IMMUTABLE_SIZE]
+where
+    T: SolValue,
+    for<'a> T::SolType: SolType<Token<'a> = WordToken>,
+{
+    value.tokenize().0.into()
+}
+
+/// Reads the last `count` immutables of the executing contract's code.
+///
+/// The immutables are decoded with [`decode_word`](crate::abi::decode_word), which reverts
+/// unless they are valid values of their types.
+pub fn load_immutables(count: usize) -> Vec<u8> {
+    let mut immutables = vec![0; count * IMMUTABLE_SIZE];
+    let offset = code_size().saturating_sub(immutables.len() as u64);
+    code_copy(offset, &mut immutables);
+    immutables
+}
@@ -18,6 +18,7 @@
 //! - **Environment Access**: Block and transaction information
 //! - **Memory Management**: Custom bump allocator for deterministic allocation
 //! - **Error Handling**: Contract reversion with structured error data
+//! - **Off-chain Testing**: In-memory syscall backend behind the `test-env` feature
 //!
 //! ## Architecture
 //!
@@ -27,6 +28,7 @@
 //! Provides persistent storage abstractions:
 //! - `Slot<T>`: Single storage slots for any ABI-encodable type
 //! - `Mapping<K, V>`: Key-value mappings with automatic key derivation
+//! - `#[immutable]` fields: values set by the constructor and read from the code (`immutable` module)
 //!
 //! ### Contract System
 //! - `Contract` trait: Entry point for contract execution
@@ -34,9 +36,12 @@
 //! - Type-safe interfaces with context checking (ReadOnly vs ReadWrite)
 //!
 //! ### Environment Access
-//! - Block information: timestamp, number, base fee, gas limit, chain ID
+//! - Block information: timestamp, number, base fee, gas limit, chain ID, coinbase,
+//!   prevrandao, blob base fee
+//! - Execution context: remaining gas, code size and code
 //! - Transaction information: gas price, origin address
 //! - Message context: sender, value, calldata
+//! - Ether balances: `balance`, `self_balance`, and `transfer` or `send` for sending ether
 //!
 //! ### System Integration
 //! - RISC-V system calls for EVM operations (SLOAD, SSTORE, CALL, etc.)
@@ -103,12 +108,16 @@
 //! - Standard blockchain tooling and interfaces
 
 #![no_std]
-#![no_main]
+#![cfg_attr(not(feature = "test-env"), no_main)]
 #![feature(alloc_error_handler, maybe_uninit_write_slice, round_char_boundary)]
 
 use alloy_core::primitives::{Address, U256};
-use core::{arch::asm, fmt::Write, panic::PanicInfo, slice};
+use core::slice;
+#[cfg(not(feature = "test-env"))]
+use core::{arch::asm, fmt::Write, panic::PanicInfo};
 extern crate alloc as ext_alloc;
+#[cfg(feature = "test-env")]
+extern crate std;
 
 mod allocator;
 pub mod env;
@@ -121,12 +130,25 @@ pub use create::Deployable;
 pub mod error;
 pub use error::{revert, revert_with_error, Error};
 
+pub mod abi;
+
+pub mod guard;
+pub use guard::Guard;
+
+pub mod immutable;
+
 pub mod log;
 pub use log::{emit_log, Event};
 
 pub mod call;
 pub use call::*;
 
+pub mod balance;
+pub use balance::{balance, self_balance, send, transfer};
+
+#[cfg(feature = "test-env")]
+pub mod mock;
+
 /// Memory address where calldata is mapped in the contract's address space.
 /// The first 8 bytes contain the calldata length, followed by the actual calldata.
 pub const CALLDATA_ADDRESS: usize = 0x8000_0000;
@@ -192,6 +214,7 @@ pub unsafe fn slice_from_raw_parts_mut(address: usize, length: usize) -> &'stati
 /// This function is marked unsafe because it accesses static mutable state
 /// (`IS_PANICKING`) without synchronization. This is acceptable in the
 /// single-threaded contract execution environment.
+#[cfg(not(feature = "test-env"))]
 #[panic_handler]
 unsafe fn panic(info: &PanicInfo<'_>) -> ! {
     static mut IS_PANICKING: bool = false;
@@ -240,10 +263,16 @@ use hybrid_syscalls::Syscall;
 /// # Note
 /// This function never returns - it uses the `!` return type to indicate divergence.
 pub fn return_riscv(addr: u64, offset: u64) -> ! {
-    unsafe {
-        asm!("ecall", in("a0") addr, in("a1") offset, in("t0") u8::from(Syscall::Return));
+    #[cfg(feature = "test-env")]
+    mock::return_riscv(addr, offset);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        unsafe {
+            asm!("ecall", in("a0") addr, in("a1") offset, in("t0") u8::from(Syscall::Return));
+        }
+        unreachable!()
     }
-    unreachable!()
 }
 
 /// Reads a 256-bit word from contract storage.
@@ -272,16 +301,22 @@ pub fn return_riscv(addr: u64, offset: u64) -> ! {
 /// SLOAD operations have variable gas costs depending on whether the storage
 /// slot has been accessed before in the current transaction (EIP-2929).
 pub fn sload(key: U256) -> U256 {
-    let key = key.as_limbs();
-    let (val0, val1, val2, val3): (u64, u64, u64, u64);
-    unsafe {
-        asm!(
-            "ecall",
-            lateout("a0") val0, lateout("a1") val1, lateout("a2") val2, lateout("a3") val3,
-            in("a0") key[0], in("a1") key[1], in("a2") key[2], in("a3") key[3],
-            in("t0") u8::from(Syscall::SLoad));
+    #[cfg(feature = "test-env")]
+    return mock::sload(key);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let key = key.as_limbs();
+        let (val0, val1, val2, val3): (u64, u64, u64, u64);
+        unsafe {
+            asm!(
+                "ecall",
+                lateout("a0") val0, lateout("a1") val1, lateout("a2") val2, lateout("a3") val3,
+                in("a0") key[0], in("a1") key[1], in("a2") key[2], in("a3") key[3],
+                in("t0") u8::from(Syscall::SLoad));
+        }
+        U256::from_limbs([val0, val1, val2, val3])
     }
-    U256::from_limbs([val0, val1, val2, val3])
 }
 
 /// Writes a 256-bit word to contract storage.
@@ -316,16 +351,22 @@ pub fn sload(key: U256) -> U256 {
 /// Storage modifications made by SSTORE are part of the transaction's state
 /// changes and will be reverted if the transaction fails or reverts.
 pub fn sstore(key: U256, value: U256) {
-    let key = key.as_limbs();
-    let value = value.as_limbs();
-
-    unsafe {
-        asm!(
-            "ecall",
-            in("a0") key[0], in("a1") key[1], in("a2") key[2], in("a3") key[3],
-            in("a4") value[0], in("a5") value[1], in("a6") value[2], in("a7") value[3],
-            in("t0") u8::from(Syscall::SStore)
-        );
+    #[cfg(feature = "test-env")]
+    return mock::sstore(key, value);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let key = key.as_limbs();
+        let value = value.as_limbs();
+
+        unsafe {
+            asm!(
+                "ecall",
+                in("a0") key[0], in("a1") key[1], in("a2") key[2], in("a3") key[3],
+                in("a4") value[0], in("a5") value[1], in("a6") value[2], in("a7") value[3],
+                in("t0") u8::from(Syscall::SStore)
+            );
+        }
     }
 }
 
@@ -364,20 +405,26 @@ pub fn sstore(key: U256, value: U256) {
 /// The gas cost is proportional to the amount of data being hashed, with
 /// a base cost plus additional cost per word (32 bytes) of data.
 pub fn keccak256(offset: u64, size: u64) -> U256 {
-    let (first, second, third, fourth): (u64, u64, u64, u64);
-    unsafe {
-        asm!(
-            "ecall",
-            in("a0") offset,
-            in("a1") size,
-            lateout("a0") first,
-            lateout("a1") second,
-            lateout("a2") third,
-            lateout("a3") fourth,
-            in("t0") u8::from(Syscall::Keccak256)
-        );
+    #[cfg(feature = "test-env")]
+    return mock::keccak256_memory(offset, size);
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let (first, second, third, fourth): (u64, u64, u64, u64);
+        unsafe {
+            asm!(
+                "ecall",
+                in("a0") offset,
+                in("a1") size,
+                lateout("a0") first,
+                lateout("a1") second,
+                lateout("a2") third,
+                lateout("a3") fourth,
+                in("t0") u8::from(Syscall::Keccak256)
+            );
+        }
+        U256::from_limbs([first, second, third, fourth])
     }
-    U256::from_limbs([first, second, third, fourth])
 }
 
 /// Returns the address of the immediate caller of the current contract.
@@ -416,15 +463,7 @@ pub fn keccak256(offset: u64, size: u64) -> U256 {
 /// - tx.origin = EOA
 /// ```
 pub fn msg_sender() -> Address {
-    let (first, second, third): (u64, u64, u64);
-    unsafe {
-        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, in("t0") u8::from(Syscall::Caller));
-    }
-    let mut bytes = [0u8; 20];
-    bytes[0..8].copy_from_slice(&first.to_be_bytes());
-    bytes[8..16].copy_from_slice(&second.to_be_bytes());
-    bytes[16..20].copy_from_slice(&third.to_be_bytes()[4..]);
-    Address::from_slice(&bytes)
+    env::read_address(Syscall::Caller)
 }
 
 /// Returns the amount of wei sent with the current message call.
@@ -461,11 +500,7 @@ pub fn msg_sender() -> Address {
 /// - Consider integer overflow when adding to balances
 /// - Implement proper refund mechanisms for overpayments
 pub fn msg_value() -> U256 {
-    let (first, second, third, fourth): (u64, u64, u64, u64);
-    unsafe {
-        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, lateout("a3") fourth, in("t0") u8::from(Syscall::CallValue));
-    }
-    U256::from_limbs([first, second, third, fourth])
+    env::read_word(Syscall::CallValue)
 }
 
 /// Returns the function selector (first 4 bytes) of the current call.
@@ -508,8 +543,14 @@ pub fn msg_value() -> U256 {
 /// ...
 /// ```
 pub fn msg_sig() -> [u8; 4] {
-    let sig = unsafe { slice_from_raw_parts(CALLDATA_ADDRESS + 8, 4) };
-    sig.try_into().unwrap()
+    #[cfg(feature = "test-env")]
+    return mock::calldata()[..4].try_into().unwrap();
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let sig = unsafe { slice_from_raw_parts(CALLDATA_ADDRESS + 8, 4) };
+        sig.try_into().unwrap()
+    }
 }
 
 /// Returns the complete calldata for the current message call.
@@ -534,7 +575,7 @@ pub fn msg_sig() -> [u8; 4] {
 /// let params = &calldata[4..];
 ///
 /// // Forward call to another contract
-/// let result = call_contract(target, 0, calldata, None);
+/// let result = call_contract(target, U256::ZERO, None, calldata, None);
 /// ```
 ///
 /// # Calldata Structure
@@ -558,11 +599,17 @@ pub fn msg_sig() -> [u8; 4] {
 /// - Dynamic types use offset-based encoding
 /// - All values are padded to 32-byte boundaries
 pub fn msg_data() -> &'static [u8] {
-    let length = unsafe { slice_from_raw_parts(CALLDATA_ADDRESS, 8) };
-    let length = u64::from_le_bytes([
-        length[0], length[1], length[2], length[3], length[4], length[5], length[6], length[7],
-    ]) as usize;
-    unsafe { slice_from_raw_parts(CALLDATA_ADDRESS + 8, length) }
+    #[cfg(feature = "test-env")]
+    return mock::calldata();
+
+    #[cfg(not(feature = "test-env"))]
+    {
+        let length = unsafe { slice_from_raw_parts(CALLDATA_ADDRESS, 8) };
+        let length = u64::from_le_bytes([
+            length[0], length[1], length[2], length[3], length[4], length[5], length[6], length[7],
+        ]) as usize;
+        unsafe { slice_from_raw_parts(CALLDATA_ADDRESS + 8, length) }
+    }
 }
 
 /// Default interrupt handler for unhandled RISC-V interrupts.
@@ -586,12 +633,14 @@ pub fn msg_data() -> &'static [u8] {
 /// - Incorrect interrupt vector configuration
 /// - Hardware or emulation issues
 /// - Runtime environment problems
+#[cfg(not(feature = "test-env"))]
 #[allow(non_snake_case)]
 #[no_mangle]
 fn DefaultHandler() {
     panic!("default handler");
 }
 
+#[cfg(not(feature = "test-env"))]
 #[allow(non_snake_case)]
 #[no_mangle]
 fn ExceptionHandler(_trap_frame: &riscv_rt::TrapFrame) -> ! {
@@ -14,7 +14,8 @@
 //! ## Features
 //!
 //! - Type-safe event emission with automatic ABI encoding
-//! - Support for up to 3 indexed topics (following EVM limitations)
+//! - Support for up to 3 indexed topics, or 4 for anonymous events (following EVM limitations)
+//! - Solidity-compatible hashing of dynamic indexed values
 //! - Integration with Solidity-compatible event definitions
 //! - Efficient topic and data encoding
 //!
@@ -47,9 +48,11 @@
 //! ```
 
 extern crate alloc;
+#[cfg(not(feature = "test-env"))]
 use crate::Syscall;
 use alloc::vec::Vec;
 use alloy_core::primitives::B256;
+#[cfg(not(feature = "test-env"))]
 use core::arch::asm;
 
 /// Trait for types that can be emitted as blockchain events.
@@ -61,7 +64,8 @@ use core::arch::asm;
 /// # Event Structure
 ///
 /// In the EVM event model:
-/// - **Topics**: Up to 3 indexed parameters (32 bytes each) used for efficient filtering
+/// - **Topics**: Up to 4 words (32 bytes each) used for efficient filtering: the event
+///   signature hash followed by up to 3 indexed parameters
 /// - **Data**: ABI-encoded non-indexed parameters containing additional event information
 ///
 /// # Implementation Guidelines
@@ -151,7 +155,7 @@ pub fn emit<T: Event>(event: T) {
 ///
 /// This is a mid-level function that directly emits log data and topics to the blockchain.
 /// It handles the formatting of topics into the expected array format and ensures
-/// compliance with EVM topic limits (maximum 3 topics beyond the event signature).
+/// compliance with EVM topic limits (maximum 4 topics, including the event signature).
 ///
 /// # Arguments
 /// * `data` - The event data as raw bytes (typically ABI-encoded)
@@ -162,7 +166,7 @@ pub fn emit<T: Event>(event: T) {
 /// - Topic 0: Usually the event signature hash (automatically added by most implementations)
 /// - Topics 1-3: Indexed event parameters
 ///
-/// This function enforces the 3-topic limit by truncating longer topic arrays.
+/// This function enforces the 4-topic limit by truncating longer topic arrays.
 ///
 /// # Examples
 /// ```rust,no_run
@@ -178,8 +182,8 @@ pub fn emit<T: Event>(event: T) {
 /// emit_log(&data, &topics);
 /// ```
 pub fn emit_log(data: &[u8], topics: &[B256]) {
-    let mut all_topics = [0u8; 96];
-    let topics = &topics[..topics.len().min(3)];
+    let mut all_topics = [0u8; 128];
+    let topics = &topics[..topics.len().min(4)];
     for (i, topic) in topics.iter().enumerate() {
         let start = i * 32;
         all_topics[start..start + 32].copy_from_slice(topic.as_ref());
@@ -196,13 +200,13 @@ pub fn emit_log(data: &[u8], topics: &[B256]) {
 /// Low-level function to emit a log entry via RISC-V system call.
 ///
 /// This function performs the actual EVM LOG operation through a system call to the Hybrid VM.
-/// It corresponds to the EVM LOG0, LOG1, LOG2, or LOG3 opcodes depending on the number of topics.
+/// It corresponds to the EVM LOG0 to LOG4 opcodes depending on the number of topics.
 ///
 /// # Arguments
 /// * `data_ptr` - Memory pointer to the event data
 /// * `data_size` - Size of the event data in bytes
-/// * `topics_ptr` - Memory pointer to the topics array (up to 3 topics, 32 bytes each)
-/// * `topics_size` - Number of topics (0-3)
+/// * `topics_ptr` - Memory pointer to the topics array (up to 4 topics, 32 bytes each)
+/// * `topics_size` - Number of topics (0-4)
 ///
 /// # Safety
 /// This function uses inline RISC-V assembly to make a system call. The caller must ensure:
@@ -229,6 +233,10 @@ pub fn emit_log(data: &[u8], topics: &[B256]) {
 /// );
 /// ```
 pub fn log(data_ptr: u64, data_size: u64, topics_ptr: u64, topics_size: u64) {
+    #[cfg(feature = "test-env")]
+    return crate::mock::log(data_ptr, data_size, topics_ptr, topics_size);
+
+    #[cfg(not(feature = "test-env"))]
     unsafe {
         asm!(
             "ecall",
@@ -0,0 +1,973 @@
+//! # Mock Environment
+//!
+//! This module replaces every Hybrid VM syscall with an in-memory implementation so
+//! contract logic can be unit tested on the host with `cargo test`, without compiling
+//! to RISC-V or running the emulator. It is only compiled with the `test-env` feature,
+//! which links `std`.
+//!
+//! The mock keeps two pieces of thread-local state:
+//! - [`MockEnv`]: block, transaction and message context (`caller`, `value`, ...)
+//! - The world state: contract storage, ether balances, emitted logs and external calls
+//!
+//! Each test thread starts from an empty world and [`MockEnv::default`], so tests running
+//! in parallel never observe each other's state.
+//!
+//! ## Behavior
+//! - `sload`/`sstore` read and write the storage of the contract at [`MockEnv::address`]
+//! - `balance`, `self_balance` and `transfer` use the balances set with [`set_balance`]
+//! - `emit!` and `emit_log` append to [`logs`]
+//! - Calls to other contracts are recorded in [`calls`], move their value, and return the
+//!   data registered with [`set_call_response`] (empty by default), or revert with the data
+//!   registered with [`set_call_revert`]
+//! - `revert` panics with the revert data; run code through [`try_call`] or [`dispatch`]
+//!   to get the data back as an `Err` with all state changes rolled back
+//!
+//! ## Usage
+//! ```rust,ignore
+//! use hybrid_contract::mock;
+//! use alloy_core::primitives::{address, U256};
+//!
+//! #[test]
+//! fn test_transfer() {
+//!     let alice = address!("00000000000000000000000000000000000a11ce");
+//!     let bob = address!("0000000000000000000000000000000000000b0b");
+//!
+//!     mock::set_caller(alice);
+//!     let mut token = ERC20::default();
+//!     token.mint(alice, U256::from(100)).unwrap();
+//!     token.transfer(bob, U256::from(40)).unwrap();
+//!
+//!     assert_eq!(token.balance_of(bob), U256::from(40));
+//!     mock::assert_emitted(&Transfer::new(alice, bob, U256::from(40)));
+//!
+//!     // Reverts surface as errors and leave storage untouched
+//!     assert!(mock::try_call(|| token.transfer(bob, U256::MAX)).is_err());
+//! }
+//! ```
+
+use crate::{call::Contract, log::Event};
+use alloy_core::primitives::{keccak256, Address, Bytes, B256, U256};
+use core::{cell::RefCell, slice};
+use hybrid_syscalls::Syscall;
+use std::{
+    boxed::Box,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    string::String,
+    vec::Vec,
+};
+
+/// Block, transaction and message values returned by the environment functions under test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockEnv {
+    /// Returned by `block::timestamp()`
+    pub timestamp: U256,
+    /// Returned by `block::number()`
+    pub number: U256,
+    /// Returned by `block::base_fee()`
+    pub base_fee: U256,
+    /// Returned by `block::blob_base_fee()`
+    pub blob_base_fee: U256,
+    /// Returned by `block::gas_limit()`
+    pub gas_limit: U256,
+    /// Returned by `block::prevrandao()`
+    pub prevrandao: U256,
+    /// Returned by `block::coinbase()`
+    pub coinbase: Address,
+    /// Returned by `block::chain_id()`
+    pub chain_id: u64,
+    /// Returned by `tx::gas_price()`
+    pub gas_price: U256,
+    /// Returned by `tx::origin()`
+    pub origin: Address,
+    /// Returned by `msg_sender()`
+    pub caller: Address,
+    /// Returned by `msg_value()`
+    pub value: U256,
+    /// Address of the contract under test, owner of its storage and `self_balance()`
+    pub address: Address,
+    /// Returned by `env::gas_left()`
+    pub gas_left: u64,
+    /// Returned by `env::code_size()`
+    pub code_size: u64,
+    /// Read by `env::code_copy()`, see [`set_code`]
+    pub code: Bytes,
+    /// Whether the contract under test runs in a static call, where storage writes, logs,
+    /// creations and calls with value revert as they do in the VM
+    pub is_static: bool,
+}
+
+impl Default for MockEnv {
+    /// Mainnet chain ID and a 30M gas block, with every other value zeroed.
+    fn default() -> Self {
+        Self {
+            timestamp: U256::ZERO,
+            number: U256::ZERO,
+            base_fee: U256::ZERO,
+            blob_base_fee: U256::ZERO,
+            gas_limit: U256::from(30_000_000u64),
+            prevrandao: U256::ZERO,
+            coinbase: Address::ZERO,
+            chain_id: 1,
+            gas_price: U256::ZERO,
+            origin: Address::ZERO,
+            caller: Address::ZERO,
+            value: U256::ZERO,
+            address: Address::ZERO,
+            gas_left: 30_000_000,
+            code_size: 0,
+            code: Bytes::new(),
+            is_static: false,
+        }
+    }
+}
+
+/// A log emitted by the contract under test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockLog {
+    /// Address of the emitting contract
+    pub address: Address,
+    /// Indexed topics, starting with the event signature hash
+    pub topics: Vec<B256>,
+    /// ABI-encoded non-indexed fields
+    pub data: Bytes,
+}
+
+/// A call made by the contract under test to another account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockCall {
+    /// The called account
+    pub to: Address,
+    /// Wei sent with the call
+    pub value: U256,
+    /// Calldata sent with the call
+    pub data: Bytes,
+    /// Gas limit requested for the callee, before the VM adds the stipend of value transfers
+    pub gas_limit: u64,
+    /// Whether the call was a STATICCALL
+    pub is_static: bool,
+}
+
+/// Accounts, logs and call bookkeeping, rolled back as a whole when a call reverts.
+#[derive(Clone, Default)]
+struct World {
+    storage: HashMap<(Address, U256), U256>,
+    balances: HashMap<Address, U256>,
+    logs: Vec<MockLog>,
+    calls: Vec<MockCall>,
+    call_responses: HashMap<Address, Result<Vec<u8>, Vec<u8>>>,
+    calldata: Vec<u8>,
+    return_data: Vec<u8>,
+    created: Option<Address>,
+    nonce: u64,
+    output: Option<Vec<u8>>,
+}
+
+/// Unwind payload used by `return_riscv` to leave a dispatched call early.
+struct Returned;
+
+std::thread_local! {
+    static ENV: RefCell<MockEnv> = RefCell::new(MockEnv::default());
+    static WORLD: RefCell<World> = RefCell::new(World::default());
+}
+
+/// Replaces the environment of the current thread.
+pub fn set_env(env: MockEnv) {
+    ENV.with(|cell| *cell.borrow_mut() = env);
+}
+
+/// Modifies the environment of the current thread in place.
+///
+/// # Examples
+/// ```rust,ignore
+/// mock::with_env(|env| env.timestamp += U256::from(3600)); // one hour later
+/// ```
+pub fn with_env<R>(f: impl FnOnce(&mut MockEnv) -> R) -> R {
+    ENV.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// Restores the environment to [`MockEnv::default`] and clears storage, balances, logs
+/// and recorded calls of the current thread.
+pub fn reset() {
+    set_env(MockEnv::default());
+    WORLD.with(|cell| *cell.borrow_mut() = World::default());
+}
+
+/// Sets the address returned by `msg_sender()`.
+pub fn set_caller(caller: Address) {
+    with_env(|env| env.caller = caller);
+}
+
+/// Sets the amount of wei returned by `msg_value()`.
+///
+/// Like on-chain, the value is expected to already be part of the contract's balance;
+/// use [`set_balance`] to fund the contract accordingly.
+pub fn set_value(value: U256) {
+    with_env(|env| env.value = value);
+}
+
+/// Sets the address of the contract under test.
+pub fn set_address(address: Address) {
+    with_env(|env| env.address = address);
+}
+
+/// Sets the code of the contract under test, and `env::code_size()` to its length.
+pub fn set_code(code: impl Into<Bytes>) {
+    let code = code.into();
+    with_env(|env| {
+        env.code_size = code.len() as u64;
+        env.code = code;
+    });
+}
+
+/// Returns the value stored at `key` in the storage of the contract under test.
+pub fn storage(key: U256) -> U256 {
+    let address = with_env(|env| env.address);
+    with_world(|world| {
+        world
+            .storage
+            .get(&(address, key))
+            .copied()
+            .unwrap_or_default()
+    })
+}
+
+/// Writes `value` at `key` in the storage of the contract under test.
+pub fn set_storage(key: U256, value: U256) {
+    let address = with_env(|env| env.address);
+    with_world(|world| world.storage.insert((address, key), value));
+}
+
+/// Sets the ether balance of `account`, in wei.
+pub fn set_balance(account: Address, amount: U256) {
+    with_world(|world| world.balances.insert(account, amount));
+}
+
+/// Registers the data returned by calls to `to`.
+pub fn set_call_response(to: Address, data: impl Into<Vec<u8>>) {
+    let data = data.into();
+    with_world(|world| world.call_responses.insert(to, Ok(data)));
+}
+
+/// Makes calls to `to` revert with `data`.
+pub fn set_call_revert(to: Address, data: impl Into<Vec<u8>>) {
+    let data = data.into();
+    with_world(|world| world.call_responses.insert(to, Err(data)));
+}
+
+/// Returns the logs emitted so far, oldest first.
+pub fn logs() -> Vec<MockLog> {
+    with_world(|world| world.logs.clone())
+}
+
+/// Returns the calls made to other accounts so far, oldest first.
+pub fn calls() -> Vec<MockCall> {
+    with_world(|world| world.calls.clone())
+}
+
+/// Returns whether `event` was emitted by the contract under test.
+pub fn emitted<E: Event>(event: &E) -> bool {
+    let address = with_env(|env| env.address);
+    let (data, topics) = event.encode_log();
+    let topics: Vec<B256> = topics.iter().map(B256::from).collect();
+
+    with_world(|world| {
+        world.logs.iter().any(|log| {
+            log.address == address && log.topics == topics && log.data.as_ref() == data.as_slice()
+        })
+    })
+}
+
+/// Asserts that `event` was emitted by the contract under test.
+///
+/// # Panics
+/// Panics listing the emitted logs if no log matches `event`
+pub fn assert_emitted<E: Event>(event: &E) {
+    if !emitted(event) {
+        panic!("expected event was not emitted, logs: {:#?}", logs());
+    }
+}
+
+/// Runs `f` as a top-level call of the contract under test.
+///
+/// # Returns
+/// The value returned by `f`, or the revert data if `f` reverted or panicked. Like on-chain,
+/// every storage, balance and log change made by a reverting call is rolled back.
+///
+/// # Examples
+/// ```rust,ignore
+/// mock::set_caller(stranger);
+/// assert!(mock::try_call(|| token.mint(stranger, U256::from(1))).is_err());
+/// ```
+pub fn try_call<R>(f: impl FnOnce() -> R) -> Result<R, Bytes> {
+    let snapshot = with_world(|world| world.clone());
+
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => Ok(result),
+        Err(payload) => {
+            if payload.is::<Returned>() {
+                panic::resume_unwind(payload);
+            }
+            let data = with_world(|world| world.output.take())
+                .unwrap_or_else(|| panic_message(payload.as_ref()).into_bytes());
+            with_world(|world| *world = snapshot);
+            Err(Bytes::from(data))
+        }
+    }
+}
+
+/// Sends `calldata` to `contract` through its generated selector dispatch.
+///
+/// `msg_data()` and `msg_sig()` return `calldata` for the duration of the call, which
+/// also checks ABI decoding and the payable guards generated by `#[contract]`.
+///
+/// # Returns
+/// The ABI-encoded return data, or the revert data if the call reverted
+///
+/// # Examples
+/// ```rust,ignore
+/// let calldata = [selector.as_slice(), &(owner,).abi_encode()].concat();
+/// let balance = mock::dispatch(&mut token, &calldata).unwrap();
+/// ```
+pub fn dispatch<C: Contract>(contract: &mut C, calldata: &[u8]) -> Result<Bytes, Bytes> {
+    with_world(|world| world.calldata = calldata.to_vec());
+
+    let result = try_call(|| {
+        panic::catch_unwind(AssertUnwindSafe(|| contract.call_with_data(calldata))).unwrap_or_else(
+            |payload| {
+                if !payload.is::<Returned>() {
+                    panic::resume_unwind(payload);
+                }
+            },
+        )
+    });
+
+    let output = with_world(|world| world.output.take()).unwrap_or_default();
+    result.map(|_| Bytes::from(output))
+}
+
+fn with_world<R>(f: impl FnOnce(&mut World) -> R) -> R {
+    WORLD.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+fn panic_message(payload: &(dyn core::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => String::from(*message),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default(),
+    }
+}
+
+/// Copies `size` bytes of contract memory starting at `offset`.
+fn read_memory(offset: u64, size: u64) -> Vec<u8> {
+    if size == 0 {
+        return Vec::new();
+    }
+    unsafe { slice::from_raw_parts(offset as *const u8, size as usize) }.to_vec()
+}
+
+/// Writes `data` to contract memory starting at `offset`.
+fn write_memory(offset: u64, data: &[u8]) {
+    if !data.is_empty() {
+        unsafe { slice::from_raw_parts_mut(offset as *mut u8, data.len()) }.copy_from_slice(data);
+    }
+}
+
+fn transfer_value(world: &mut World, from: Address, to: Address, value: U256) -> bool {
+    let from_balance = world.balances.get(&from).copied().unwrap_or_default();
+    if from_balance < value {
+        return false;
+    }
+    world.balances.insert(from, from_balance - value);
+    *world.balances.entry(to).or_default() += value;
+    true
+}
+
+pub(crate) fn read_word(syscall: Syscall) -> U256 {
+    with_env(|env| match syscall {
+        Syscall::Timestamp => env.timestamp,
+        Syscall::Number => env.number,
+        Syscall::BaseFee => env.base_fee,
+        Syscall::BlobBaseFee => env.blob_base_fee,
+        Syscall::GasLimit => env.gas_limit,
+        Syscall::Prevrandao => env.prevrandao,
+        Syscall::GasPrice => env.gas_price,
+        Syscall::CallValue => env.value,
+        _ => unmocked(syscall),
+    })
+}
+
+pub(crate) fn read_address(syscall: Syscall) -> Address {
+    with_env(|env| match syscall {
+        Syscall::Coinbase => env.coinbase,
+        Syscall::Origin => env.origin,
+        Syscall::Caller => env.caller,
+        _ => unmocked(syscall),
+    })
+}
+
+pub(crate) fn read_u64(syscall: Syscall) -> u64 {
+    with_env(|env| match syscall {
+        Syscall::ChainId => env.chain_id,
+        Syscall::Gas => env.gas_left,
+        Syscall::CodeSize => env.code_size,
+        _ => unmocked(syscall),
+    })
+}
+
+fn unmocked(syscall: Syscall) -> ! {
+    panic!("syscall {syscall} is not supported by the mock environment")
+}
+
+pub(crate) fn code_copy(offset: u64, dest: &mut [u8]) {
+    with_env(|env| {
+        // Bytes past the end of the code are zero, as on-chain
+        let start = (offset as usize).min(env.code.len());
+        let end = (start + dest.len()).min(env.code.len());
+        dest[..end - start].copy_from_slice(&env.code[start..end]);
+        dest[end - start..].fill(0);
+    });
+}
+
+pub(crate) fn sload(key: U256) -> U256 {
+    storage(key)
+}
+
+/// Reverts like the VM when the contract under test modifies state in a static call.
+fn require_non_static() {
+    if with_env(|env| env.is_static) {
+        panic!("state change during static call");
+    }
+}
+
+pub(crate) fn sstore(key: U256, value: U256) {
+    require_non_static();
+    set_storage(key, value)
+}
+
+pub(crate) fn keccak256_memory(offset: u64, size: u64) -> U256 {
+    keccak256(read_memory(offset, size)).into()
+}
+
+pub(crate) fn calldata() -> &'static [u8] {
+    // Contract code expects calldata to live for the whole call, as it does on-chain
+    Box::leak(with_world(|world| world.calldata.clone()).into_boxed_slice())
+}
+
+pub(crate) fn log(data_ptr: u64, data_size: u64, topics_ptr: u64, topics_size: u64) {
+    require_non_static();
+    let address = with_env(|env| env.address);
+    let topics = read_memory(topics_ptr, topics_size * 32)
+        .chunks_exact(32)
+        .map(B256::from_slice)
+        .collect();
+    let data = Bytes::from(read_memory(data_ptr, data_size));
+    with_world(|world| {
+        world.logs.push(MockLog {
+            address,
+            topics,
+            data,
+        })
+    });
+}
+
+pub(crate) fn balance(account: Address) -> U256 {
+    with_world(|world| world.balances.get(&account).copied().unwrap_or_default())
+}
+
+pub(crate) fn self_balance() -> U256 {
+    balance(with_env(|env| env.address))
+}
+
+pub(crate) fn call(
+    to: Address,
+    value: U256,
+    gas_limit: u64,
+    data_offset: u64,
+    data_size: u64,
+    is_static: bool,
+) -> bool {
+    if !value.is_zero() {
+        require_non_static();
+    }
+    let from = with_env(|env| env.address);
+    let data = Bytes::from(read_memory(data_offset, data_size));
+
+    with_world(|world| {
+        // A reverting callee keeps no value, and a call that cannot pay its value fails
+        // without touching the callee
+        let (success, return_data) = match world.call_responses.get(&to).cloned() {
+            Some(Err(revert_data)) => (false, revert_data),
+            response if is_static || value.is_zero() || transfer_value(world, from, to, value) => {
+                (true, response.and_then(Result::ok).unwrap_or_default())
+            }
+            _ => (false, Vec::new()),
+        };
+        world.return_data = return_data;
+        world.calls.push(MockCall {
+            to,
+            value,
+            data,
+            gas_limit,
+            is_static,
+        });
+        success
+    })
+}
+
+pub(crate) fn return_data_size() -> u64 {
+    with_world(|world| world.return_data.len() as u64)
+}
+
+pub(crate) fn return_data_copy(dest_offset: u64, res_offset: u64, res_size: u64) {
+    let data = with_world(|world| {
+        let start = (res_offset as usize).min(world.return_data.len());
+        let end = (start + res_size as usize).min(world.return_data.len());
+        world.return_data[start..end].to_vec()
+    });
+    write_memory(dest_offset, &data);
+}
+
+pub(crate) fn create(value: U256, _data_offset: u64, _data_size: u64) {
+    require_non_static();
+    let from = with_env(|env| env.address);
+
+    with_world(|world| {
+        // Deterministic per deployer and deployment count, not the real CREATE address
+        let salt = [from.as_slice(), &world.nonce.to_be_bytes()].concat();
+        let created = Address::from_word(keccak256(salt));
+        world.nonce += 1;
+        world.created = transfer_value(world, from, created, value).then_some(created);
+    });
+}
+
+pub(crate) fn return_create_address(data_offset: u64) {
+    let created = with_world(|world| world.created).unwrap_or_default();
+    write_memory(data_offset, created.as_slice());
+}
+
+pub(crate) fn return_riscv(offset: u64, size: u64) -> ! {
+    let data = read_memory(offset, size);
+    with_world(|world| world.output = Some(data));
+    panic::resume_unwind(Box::new(Returned))
+}
+
+pub(crate) fn revert(data: &[u8]) -> ! {
+    with_world(|world| world.output = Some(data.to_vec()));
+    panic!("execution reverted: {}", Bytes::copy_from_slice(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{env, env::block, tx, Error};
+    use alloy_sol_types::SolValue;
+
+    const ALICE: Address = Address::repeat_byte(0xa1);
+    const BOB: Address = Address::repeat_byte(0xb0);
+    const CONTRACT: Address = Address::repeat_byte(0xcc);
+
+    struct Counter;
+
+    impl Contract for Counter {
+        fn call(&mut self) {
+            self.call_with_data(crate::msg_data());
+        }
+
+        fn call_with_data(&mut self, calldata: &[u8]) {
+            let count = crate::sload(U256::ZERO) + U256::from(calldata[0]);
+            crate::sstore(U256::ZERO, count);
+            if count > U256::from(10) {
+                crate::revert_with_error(b"too high");
+            }
+            let result = count.abi_encode();
+            crate::return_riscv(result.as_ptr() as u64, result.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        reset();
+        assert_eq!(block::chain_id(), 1);
+        assert_eq!(block::gas_limit(), U256::from(30_000_000u64));
+        assert_eq!(env::gas_left(), 30_000_000);
+        assert_eq!(block::coinbase(), Address::ZERO);
+        assert_eq!(crate::msg_sender(), Address::ZERO);
+        assert_eq!(crate::msg_value(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_set_env() {
+        let coinbase = Address::repeat_byte(0xc0);
+        let origin = Address::repeat_byte(0x0e);
+        set_env(MockEnv {
+            timestamp: U256::from(1_700_000_000u64),
+            number: U256::from(19_000_000u64),
+            base_fee: U256::from(7),
+            blob_base_fee: U256::from(1),
+            prevrandao: U256::MAX,
+            coinbase,
+            chain_id: 31337,
+            gas_price: U256::from(10),
+            origin,
+            gas_left: 21_000,
+            code_size: 4096,
+            ..MockEnv::default()
+        });
+
+        assert_eq!(block::timestamp(), U256::from(1_700_000_000u64));
+        assert_eq!(block::number(), U256::from(19_000_000u64));
+        assert_eq!(block::base_fee(), U256::from(7));
+        assert_eq!(block::blob_base_fee(), U256::from(1));
+        assert_eq!(block::prevrandao(), U256::MAX);
+        assert_eq!(block::coinbase(), coinbase);
+        assert_eq!(block::chain_id(), 31337);
+        assert_eq!(tx::gas_price(), U256::from(10));
+        assert_eq!(tx::origin(), origin);
+        assert_eq!(env::gas_left(), 21_000);
+        assert_eq!(env::code_size(), 4096);
+    }
+
+    #[test]
+    fn test_code_copy() {
+        reset();
+        set_code(Bytes::from_static(&[0xff, 1, 2, 3]));
+        assert_eq!(env::code_size(), 4);
+
+        let mut code = [0xaa; 6];
+        env::code_copy(1, &mut code);
+        // Bytes past the end of the code are zero
+        assert_eq!(code, [1, 2, 3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_immutables() {
+        use crate::immutable::{encode_immutable, load_immutables};
+
+        reset();
+        let mut code = b"\xff\x7fELF".to_vec();
+        code.extend(encode_immutable(&ALICE));
+        code.extend(encode_immutable(&U256::from(7)));
+        set_code(code);
+
+        let immutables = load_immutables(2);
+        assert_eq!(crate::abi::decode_word::<Address>(&immutables, 0), ALICE);
+        assert_eq!(crate::abi::decode_word::<U256>(&immutables, 1), U256::from(7));
+        assert_eq!(load_immutables(0), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_with_env() {
+        reset();
+        with_env(|env| env.timestamp = U256::from(100));
+        with_env(|env| env.timestamp += U256::from(50));
+        assert_eq!(block::timestamp(), U256::from(150));
+
+        reset();
+        assert_eq!(block::timestamp(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_caller_and_value() {
+        reset();
+        set_caller(ALICE);
+        set_value(U256::from(5));
+        assert_eq!(crate::msg_sender(), ALICE);
+        assert_eq!(crate::msg_value(), U256::from(5));
+    }
+
+    #[test]
+    fn test_storage_is_per_contract() {
+        reset();
+        set_address(CONTRACT);
+        crate::sstore(U256::from(1), U256::from(42));
+        assert_eq!(crate::sload(U256::from(1)), U256::from(42));
+        assert_eq!(storage(U256::from(1)), U256::from(42));
+
+        set_address(BOB);
+        assert_eq!(crate::sload(U256::from(1)), U256::ZERO);
+    }
+
+    #[test]
+    fn test_keccak256() {
+        reset();
+        let data = b"Transfer(address,address,uint256)";
+        let hash = crate::keccak256(data.as_ptr() as u64, data.len() as u64);
+        assert_eq!(hash, U256::from_be_bytes(keccak256(data).0));
+    }
+
+    #[test]
+    fn test_transfer() {
+        reset();
+        set_address(CONTRACT);
+        set_balance(CONTRACT, U256::from(100));
+
+        crate::transfer(BOB, U256::from(30));
+
+        assert_eq!(crate::self_balance(), U256::from(70));
+        assert_eq!(crate::balance(BOB), U256::from(30));
+        assert_eq!(
+            calls(),
+            [MockCall {
+                to: BOB,
+                value: U256::from(30),
+                data: Bytes::new(),
+                gas_limit: 0,
+                is_static: false
+            }]
+        );
+    }
+
+    #[test]
+    fn test_transfer_reverts() {
+        reset();
+        set_address(CONTRACT);
+        set_balance(CONTRACT, U256::from(100));
+
+        // More than the contract holds
+        assert!(try_call(|| crate::transfer(BOB, U256::from(101))).is_err());
+        assert!(calls().is_empty());
+
+        // The recipient rejects the ether
+        set_call_revert(BOB, Vec::new());
+        assert!(try_call(|| crate::transfer(BOB, U256::from(30))).is_err());
+        assert_eq!(crate::self_balance(), U256::from(100));
+        assert_eq!(crate::balance(BOB), U256::ZERO);
+    }
+
+    #[test]
+    fn test_send() {
+        reset();
+        set_address(CONTRACT);
+        set_balance(CONTRACT, U256::from(100));
+
+        assert!(crate::send(BOB, U256::from(30)));
+        assert!(!crate::send(BOB, U256::from(71)));
+        set_call_revert(ALICE, Vec::new());
+        assert!(!crate::send(ALICE, U256::from(10)));
+
+        assert_eq!(crate::self_balance(), U256::from(70));
+        assert_eq!(crate::balance(BOB), U256::from(30));
+        assert_eq!(crate::balance(ALICE), U256::ZERO);
+        // Calls with value get the stipend from the VM, a call without value requests it
+        assert!(crate::send(BOB, U256::ZERO));
+        let gas_limits: Vec<_> = calls().iter().map(|call| call.gas_limit).collect();
+        assert_eq!(gas_limits, [0, 0, crate::balance::TRANSFER_STIPEND]);
+    }
+
+    #[test]
+    fn test_call_response() {
+        reset();
+        let response = U256::from(7).abi_encode();
+        set_call_response(BOB, response.clone());
+
+        let output = crate::call_contract(BOB, U256::ZERO, None, &[1, 2, 3, 4], None);
+
+        assert_eq!(output.as_ref(), response.as_slice());
+        assert_eq!(calls()[0].data.as_ref(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_call_revert() {
+        reset();
+        set_balance(CONTRACT, U256::from(100));
+        set_env(MockEnv {
+            address: CONTRACT,
+            ..MockEnv::default()
+        });
+        set_call_revert(BOB, b"nope".to_vec());
+        set_call_response(ALICE, U256::from(7).abi_encode());
+
+        let output = crate::try_call_contract(BOB, U256::from(30), None, &[], None);
+        assert_eq!(output, Err(Bytes::from_static(b"nope")));
+        // The value stays with the caller
+        assert_eq!(crate::self_balance(), U256::from(100));
+
+        let output = crate::try_staticcall_contract(ALICE, U256::ZERO, None, &[], None);
+        assert_eq!(output, Ok(Bytes::from(U256::from(7).abi_encode())));
+    }
+
+    /// `InsufficientBalance(uint256)`, as `#[derive(Error)]` implements it
+    #[derive(Debug, PartialEq, Eq)]
+    struct InsufficientBalance(U256);
+
+    impl Error for InsufficientBalance {
+        fn abi_encode(&self) -> Vec<u8> {
+            let selector = crate::abi::selector("InsufficientBalance", "(uint256)");
+            [&selector.to_be_bytes()[..], &self.0.abi_encode()].concat()
+        }
+
+        fn abi_decode(bytes: &[u8], validate: bool) -> Self {
+            Self::try_abi_decode(bytes, validate).expect("Unknown error")
+        }
+
+        fn try_abi_decode(bytes: &[u8], validate: bool) -> Option<Self> {
+            let selector = crate::abi::selector("InsufficientBalance", "(uint256)");
+            match bytes.get(..4) {
+                Some(head) if *head == selector.to_be_bytes() => {
+                    U256::abi_decode(&bytes[4..], validate).ok().map(Self)
+                }
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_call_result() {
+        type Outcome = crate::CallResult<U256, InsufficientBalance>;
+        let word = |value: u64| Bytes::from(U256::from(value).abi_encode());
+        let revert_data = |selector: [u8; 4], data: Vec<u8>| {
+            Err(Bytes::from([&selector[..], &data].concat()))
+        };
+
+        assert_eq!(Outcome::decode(Ok(word(7))), Outcome::Ok(U256::from(7)));
+        // Return data that does not decode as `T`
+        assert_eq!(
+            Outcome::decode(Ok(Bytes::from_static(b"short"))),
+            Outcome::Failed(Bytes::from_static(b"short"))
+        );
+        assert_eq!(
+            Outcome::decode(Err(InsufficientBalance(U256::from(5)).abi_encode().into())),
+            Outcome::Revert(InsufficientBalance(U256::from(5)))
+        );
+        assert_eq!(
+            Outcome::decode(revert_data(
+                crate::ERROR_STRING_SELECTOR,
+                String::from("too high").abi_encode()
+            )),
+            Outcome::Error(String::from("too high"))
+        );
+        // Arithmetic overflow
+        assert_eq!(
+            Outcome::decode(revert_data(crate::PANIC_SELECTOR, word(0x11).to_vec())),
+            Outcome::Panic(U256::from(0x11))
+        );
+        assert_eq!(Outcome::decode(Err(Bytes::new())), Outcome::Failed(Bytes::new()));
+        assert_eq!(
+            Outcome::decode(revert_data([1, 2, 3, 4], Vec::new())),
+            Outcome::Failed(Bytes::from_static(&[1, 2, 3, 4]))
+        );
+
+        assert!(Outcome::decode(Ok(word(7))).is_ok());
+        assert_eq!(Outcome::decode(Err(Bytes::new())).ok(), None);
+        assert_eq!(Outcome::decode(Ok(word(7))).or_revert(), U256::from(7));
+
+        // Failed calls bubble up the revert data of the callee
+        let revert = InsufficientBalance(U256::from(5)).abi_encode();
+        let output = try_call(|| Outcome::decode(Err(revert.clone().into())).or_revert());
+        assert_eq!(output, Err(Bytes::from(revert)));
+    }
+
+    #[test]
+    fn test_logs() {
+        reset();
+        set_address(CONTRACT);
+        let topic = B256::repeat_byte(0x11);
+        crate::emit_log(&[1, 2], &[topic]);
+
+        assert_eq!(
+            logs(),
+            [MockLog {
+                address: CONTRACT,
+                topics: std::vec![topic],
+                data: Bytes::from([1, 2])
+            }]
+        );
+    }
+
+    #[test]
+    fn test_static_call_rejects_state_changes() {
+        reset();
+        set_address(CONTRACT);
+        set_balance(CONTRACT, U256::from(10));
+        with_env(|env| env.is_static = true);
+
+        assert_eq!(crate::sload(U256::ZERO), U256::ZERO);
+        assert!(try_call(|| crate::sstore(U256::ZERO, U256::from(1))).is_err());
+        assert!(try_call(|| crate::emit_log(&[], &[])).is_err());
+        assert!(try_call(|| crate::transfer(BOB, U256::from(1))).is_err());
+        assert!(try_call(|| crate::call_contract(BOB, U256::ZERO, None, &[], None)).is_ok());
+        assert!(logs().is_empty());
+    }
+
+    #[test]
+    fn test_try_call_rolls_back() {
+        reset();
+        set_address(CONTRACT);
+        set_balance(CONTRACT, U256::from(10));
+
+        let result = try_call(|| {
+            crate::sstore(U256::ZERO, U256::from(1));
+            crate::transfer(BOB, U256::from(10));
+            crate::revert_with_error(b"nope");
+        });
+
+        assert_eq!(result, Err(Bytes::from_static(b"nope")));
+        assert_eq!(storage(U256::ZERO), U256::ZERO);
+        assert_eq!(crate::self_balance(), U256::from(10));
+        assert!(calls().is_empty());
+    }
+
+    #[test]
+    fn test_try_call_panic_message() {
+        reset();
+        let result = try_call(|| panic!("Non-payable function"));
+        assert_eq!(result, Err(Bytes::from_static(b"Non-payable function")));
+    }
+
+    #[test]
+    fn test_reentrancy_lock() {
+        use crate::guard::{nonreentrant_enter, nonreentrant_exit, REENTRANT_CALL};
+        reset();
+
+        nonreentrant_enter();
+        assert_eq!(
+            try_call(nonreentrant_enter),
+            Err(Bytes::copy_from_slice(&REENTRANT_CALL))
+        );
+        nonreentrant_exit();
+        assert!(try_call(nonreentrant_enter).is_ok());
+        assert_eq!(REENTRANT_CALL, [0x3e, 0xe5, 0xae, 0xb5]);
+    }
+
+    #[test]
+    fn test_dispatch() {
+        reset();
+        let mut counter = Counter;
+
+        assert_eq!(
+            dispatch(&mut counter, &[4]),
+            Ok(U256::from(4).abi_encode().into())
+        );
+        assert_eq!(
+            dispatch(&mut counter, &[5]),
+            Ok(U256::from(9).abi_encode().into())
+        );
+        assert_eq!(
+            dispatch(&mut counter, &[5]),
+            Err(Bytes::from_static(b"too high"))
+        );
+        assert_eq!(storage(U256::ZERO), U256::from(9));
+    }
+
+    #[test]
+    fn test_dispatch_sets_calldata() {
+        reset();
+        struct Echo;
+        impl Contract for Echo {
+            fn call(&mut self) {}
+            fn call_with_data(&mut self, _: &[u8]) {
+                let data = crate::msg_data();
+                crate::return_riscv(data.as_ptr() as u64, data.len() as u64);
+            }
+        }
+
+        assert_eq!(
+            dispatch(&mut Echo, &[9, 8, 7, 6, 5]),
+            Ok(Bytes::from([9, 8, 7, 6, 5]))
+        );
+        assert_eq!(crate::msg_sig(), [9, 8, 7, 6]);
+    }
+}
@@ -36,9 +36,10 @@
 //! to certain attack patterns. Prefer using `msg.sender` for most authorization logic.
 
 use alloy_core::primitives::{Address, U256};
-use core::arch::asm;
 use hybrid_syscalls::Syscall;
 
+use crate::env::{read_address, read_word};
+
 /// Returns the gas price of the current transaction.
 /// Returns the gas price of the current transaction.
 ///
@@ -67,14 +68,7 @@ use hybrid_syscalls::Syscall;
 /// - Base fee is burned, priority fee goes to miners
 /// - This provides the total amount paid per gas unit
 pub fn gas_price() -> U256 {
-    let first: u64;
-    let second: u64;
-    let third: u64;
-    let fourth: u64;
-    unsafe {
-        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, lateout("a3") fourth, in("t0") u8::from(Syscall::GasPrice))
-    }
-    U256::from_limbs([first, second, third, fourth])
+    read_word(Syscall::GasPrice)
 }
 
 /// Returns the original sender of the entire transaction chain.
@@ -134,15 +128,5 @@ pub fn gas_price() -> U256 {
 /// }
 /// ```
 pub fn origin() -> Address {
-    let first: u64;
-    let second: u64;
-    let third: u64;
-    unsafe {
-        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, in("t0") u8::from(Syscall::Origin));
-    }
-    let mut bytes = [0u8; 20];
-    bytes[0..8].copy_from_slice(&first.to_be_bytes());
-    bytes[8..16].copy_from_slice(&second.to_be_bytes());
-    bytes[16..20].copy_from_slice(&third.to_be_bytes()[4..]);
-    Address::from_slice(&bytes)
+    read_address(Syscall::Origin)
 }
@@ -0,0 +1,72 @@
+//! Selector dispatch generated by `#[contract]` for a contract with `#[receive]` and
+//! `#[fallback]` methods, driven through `mock::dispatch` (`cargo test --features test-env`).
+#![cfg(feature = "test-env")]
+// `#[contract]` checks the `deploy` and `interface-only` features of contract crates
+#![allow(unexpected_cfgs)]
+
+// The generated dispatch uses `alloc` paths, as contracts are `no_std`
+extern crate alloc;
+
+mod wallet {
+    use alloy_core::primitives::{Address, Bytes, U256};
+    use hybrid_derive::{contract, fallback, receive};
+
+    pub struct Wallet;
+
+    #[contract]
+    impl Wallet {
+        pub fn version(&self) -> U256 {
+            U256::from(2)
+        }
+
+        #[receive]
+        fn receive(&mut self) {
+            sstore(U256::ZERO, U256::from(1));
+        }
+
+        #[fallback]
+        fn fallback(&mut self, input: &[u8]) -> Bytes {
+            Bytes::from([b"fallback:".as_slice(), input].concat())
+        }
+    }
+}
+
+use alloy_core::primitives::{keccak256, U256};
+use alloy_sol_types::SolValue;
+use hybrid_contract::mock;
+use wallet::Wallet;
+
+fn version_selector() -> [u8; 4] {
+    keccak256("version()")[..4].try_into().unwrap()
+}
+
+#[test]
+fn test_selector_dispatch() {
+    mock::reset();
+    let output = mock::dispatch(&mut Wallet, &version_selector()).unwrap();
+    assert_eq!(output.as_ref(), U256::from(2).abi_encode());
+}
+
+#[test]
+fn test_empty_calldata_goes_to_receive() {
+    mock::reset();
+    let output = mock::dispatch(&mut Wallet, &[]).unwrap();
+    assert!(output.is_empty());
+    assert_eq!(mock::storage(U256::ZERO), U256::from(1));
+}
+
+#[test]
+fn test_short_calldata_goes_to_fallback() {
+    mock::reset();
+    let output = mock::dispatch(&mut Wallet, &[0xab, 0xcd]).unwrap();
+    assert_eq!(output.as_ref(), b"fallback:\xab\xcd");
+    assert_eq!(mock::storage(U256::ZERO), U256::ZERO);
+}
+
+#[test]
+fn test_unknown_selector_goes_to_fallback() {
+    mock::reset();
+    let calldata = [0xde, 0xad, 0xbe, 0xef, 0x01];
+    let output = mock::dispatch(&mut Wallet, &calldata).unwrap();
+    assert_eq!(output.as_ref(), b"fallback:\xde\xad\xbe\xef\x01");
+}
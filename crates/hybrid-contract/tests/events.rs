@@ -0,0 +1,109 @@
+//! Events derived with `#[derive(Event)]`, emitted against the mock environment
+//! (`cargo test --features test-env`).
+#![cfg(feature = "test-env")]
+
+// The derived encoders use `alloc` paths, as contracts are `no_std`
+extern crate alloc;
+
+use alloy_core::primitives::{keccak256, Address, B256, U256};
+use alloy_sol_types::SolValue;
+use hybrid_contract::{log::emit, mock};
+use hybrid_derive::Event;
+
+const ALICE: Address = Address::repeat_byte(0xa1);
+const BOB: Address = Address::repeat_byte(0xb0);
+
+#[derive(Event)]
+pub struct Transfer {
+    #[indexed]
+    pub from: Address,
+    #[indexed]
+    pub to: Address,
+    pub amount: U256,
+}
+
+#[derive(Event)]
+pub struct Registered {
+    #[indexed]
+    pub name: String,
+    pub owner: Address,
+}
+
+#[derive(Event)]
+#[event(anonymous)]
+pub struct Ping {
+    #[indexed]
+    pub from: Address,
+    #[indexed]
+    pub to: Address,
+    #[indexed]
+    pub id: U256,
+    #[indexed]
+    pub nonce: U256,
+}
+
+fn word(address: Address) -> B256 {
+    address.into_word()
+}
+
+#[test]
+fn test_signature_topic() {
+    mock::reset();
+    emit(Transfer {
+        from: ALICE,
+        to: BOB,
+        amount: U256::from(100),
+    });
+
+    let logs = mock::logs();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(
+        logs[0].topics,
+        [
+            keccak256("Transfer(address,address,uint256)"),
+            word(ALICE),
+            word(BOB)
+        ]
+    );
+    assert_eq!(logs[0].data.as_ref(), U256::from(100).abi_encode());
+}
+
+#[test]
+fn test_indexed_string_is_hashed() {
+    mock::reset();
+    emit(Registered {
+        name: "hybrid".to_string(),
+        owner: ALICE,
+    });
+
+    let logs = mock::logs();
+    assert_eq!(
+        logs[0].topics,
+        [keccak256("Registered(string,address)"), keccak256("hybrid")]
+    );
+    assert_eq!(logs[0].data.as_ref(), ALICE.abi_encode());
+}
+
+#[test]
+fn test_anonymous_event() {
+    mock::reset();
+    emit(Ping {
+        from: ALICE,
+        to: BOB,
+        id: U256::from(7),
+        nonce: U256::from(1),
+    });
+
+    // No signature topic, which leaves room for a fourth indexed field
+    let logs = mock::logs();
+    assert_eq!(
+        logs[0].topics,
+        [
+            word(ALICE),
+            word(BOB),
+            B256::from(U256::from(7)),
+            B256::from(U256::from(1))
+        ]
+    );
+    assert!(logs[0].data.is_empty());
+}
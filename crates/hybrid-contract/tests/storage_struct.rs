@@ -0,0 +1,98 @@
+//! Storage structs derived with `#[derive(StorageStruct)]`, run against the mock environment
+//! (`cargo test --features test-env`).
+#![cfg(feature = "test-env")]
+
+use alloy_core::primitives::{keccak256, Address, U256};
+use hybrid_contract::{
+    hstd::{DirectStorage, IndirectStorage, Mapping, Slot, StorageLayout, StorageValue},
+    mock,
+};
+use hybrid_derive::StorageStruct;
+
+const OWNER: Address = Address::repeat_byte(0xa1);
+
+#[derive(StorageStruct, Debug, PartialEq)]
+pub struct Position {
+    pub owner: Address, // slot 0
+    pub expiry: u64,    // slot 0
+    pub active: bool,   // slot 0
+    pub amount: U256,   // slot 1
+    pub fee: u128,      // slot 2
+}
+
+fn position() -> Position {
+    Position {
+        owner: OWNER,
+        expiry: 1_700_000_000,
+        active: true,
+        amount: U256::from(1000),
+        fee: 3,
+    }
+}
+
+/// Slot 0 of `position()`: `[active | expiry | owner]` from the lower-order bytes upwards.
+fn first_word() -> U256 {
+    U256::from_be_slice(OWNER.as_slice())
+        | U256::from(1_700_000_000u64) << 160
+        | U256::from(1) << 224
+}
+
+#[test]
+fn test_slot_round_trip() {
+    mock::reset();
+    assert_eq!(<Position as StorageValue>::SLOTS, 3);
+
+    let mut slot: Slot<Position> = Slot::allocate(4, 0, 0, 0);
+    slot.write(position());
+    assert_eq!(slot.read(), position());
+
+    assert_eq!(mock::storage(U256::from(4)), first_word());
+    assert_eq!(mock::storage(U256::from(5)), U256::from(1000));
+    assert_eq!(mock::storage(U256::from(6)), U256::from(3));
+}
+
+#[test]
+fn test_field_access() {
+    mock::reset();
+    let mut slot: Slot<Position> = Slot::allocate(4, 0, 0, 0);
+    slot.write(position());
+
+    // Writing a field only changes its own bytes
+    slot.expiry().write(42);
+    assert_eq!(slot.expiry().read(), 42);
+    assert_eq!(slot.owner().read(), OWNER);
+    assert!(slot.active().read());
+    assert_eq!(
+        mock::storage(U256::from(4)),
+        U256::from_be_slice(OWNER.as_slice()) | U256::from(42) << 160 | U256::from(1) << 224
+    );
+
+    slot.amount().write(U256::MAX);
+    assert_eq!(
+        slot.read(),
+        Position {
+            expiry: 42,
+            amount: U256::MAX,
+            ..position()
+        }
+    );
+}
+
+#[test]
+fn test_mapping_round_trip() {
+    mock::reset();
+    let mut positions: Mapping<Address, Slot<Position>> = Mapping::allocate(2, 0, 0, 0);
+    positions[OWNER].write(position());
+    assert_eq!(positions[OWNER].read(), position());
+    assert_eq!(positions[OWNER].fee().read(), 3);
+    assert_eq!(positions[Address::ZERO].read().owner, Address::ZERO);
+
+    // The struct starts at `keccak256(abi_encode(key) + abi_encode(p))`
+    let mut preimage = [0u8; 64];
+    preimage[12..32].copy_from_slice(OWNER.as_slice());
+    preimage[63] = 2;
+    let base = U256::from_be_bytes(keccak256(preimage).0);
+    assert_eq!(mock::storage(base), first_word());
+    assert_eq!(mock::storage(base + U256::from(1)), U256::from(1000));
+    assert_eq!(mock::storage(base + U256::from(2)), U256::from(3));
+}
@@ -0,0 +1,48 @@
+//! Selector dispatch generated by `#[contract]` for a contract without `#[receive]` or
+//! `#[fallback]` methods, driven through `mock::dispatch` (`cargo test --features test-env`).
+#![cfg(feature = "test-env")]
+// `#[contract]` checks the `deploy` and `interface-only` features of contract crates
+#![allow(unexpected_cfgs)]
+
+// The generated dispatch uses `alloc` paths, as contracts are `no_std`
+extern crate alloc;
+
+mod plain {
+    use alloy_core::primitives::{Address, U256};
+    use hybrid_derive::contract;
+
+    pub struct Plain;
+
+    #[contract]
+    impl Plain {
+        pub fn version(&self) -> U256 {
+            U256::from(1)
+        }
+    }
+}
+
+use alloy_core::primitives::{keccak256, Bytes, U256};
+use alloy_sol_types::SolValue;
+use hybrid_contract::mock;
+use plain::Plain;
+
+#[test]
+fn test_known_method() {
+    mock::reset();
+    let selector = &keccak256("version()")[..4];
+    let output = mock::dispatch(&mut Plain, selector).unwrap();
+    assert_eq!(output.as_ref(), U256::from(1).abi_encode());
+}
+
+#[test]
+fn test_unknown_method_reverts() {
+    mock::reset();
+    let unknown = Bytes::from_static(b"unknown method");
+    assert_eq!(
+        mock::dispatch(&mut Plain, &[0xde, 0xad, 0xbe, 0xef]),
+        Err(unknown.clone())
+    );
+    // Plain transfers and calldata too short for a selector revert the same way
+    assert_eq!(mock::dispatch(&mut Plain, &[]), Err(unknown.clone()));
+    assert_eq!(mock::dispatch(&mut Plain, &[0xab]), Err(unknown));
+}
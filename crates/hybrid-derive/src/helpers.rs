@@ -7,11 +7,14 @@
 
 use alloy_core::primitives::keccak256;
 use alloy_dyn_abi::DynSolType;
+use hybrid_abi::ValueType;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
     parse::{Parse, ParseStream},
-    FnArg, Ident, ImplItemMethod, LitStr, PathArguments, ReturnType, TraitItemMethod, Type,
+    punctuated::Punctuated,
+    Expr, FnArg, Ident, ImplItemMethod, LitStr, PathArguments, ReturnType, Token, TraitItemMethod,
+    Type,
 };
 
 /// Unified method information extracted from both `ImplItemMethod` and `TraitItemMethod`.
@@ -25,30 +28,38 @@ use syn::{
 /// * `name` - The method identifier
 /// * `args` - All function arguments including `self`
 /// * `return_type` - The method's return type specification
+/// * `selector` - The method's `#[selector(...)]` override, if any
 #[derive(Clone)]
 pub struct MethodInfo<'a> {
     name: &'a Ident,
     args: Vec<syn::FnArg>,
     return_type: &'a ReturnType,
+    selector: Option<SelectorOverride>,
 }
 
-impl<'a> From<&'a ImplItemMethod> for MethodInfo<'a> {
-    fn from(method: &'a ImplItemMethod) -> Self {
-        Self {
+impl<'a> TryFrom<&'a ImplItemMethod> for MethodInfo<'a> {
+    type Error = syn::Error;
+
+    fn try_from(method: &'a ImplItemMethod) -> Result<Self, Self::Error> {
+        Ok(Self {
             name: &method.sig.ident,
             args: method.sig.inputs.iter().cloned().collect(),
             return_type: &method.sig.output,
-        }
+            selector: selector_override(&method.attrs)?,
+        })
     }
 }
 
-impl<'a> From<&'a TraitItemMethod> for MethodInfo<'a> {
-    fn from(method: &'a TraitItemMethod) -> Self {
-        Self {
+impl<'a> TryFrom<&'a TraitItemMethod> for MethodInfo<'a> {
+    type Error = syn::Error;
+
+    fn try_from(method: &'a TraitItemMethod) -> Result<Self, Self::Error> {
+        Ok(Self {
             name: &method.sig.ident,
             args: method.sig.inputs.iter().cloned().collect(),
             return_type: &method.sig.output,
-        }
+            selector: selector_override(&method.attrs)?,
+        })
     }
 }
 
@@ -207,11 +218,213 @@ impl Parse for InterfaceArgs {
     }
 }
 
+/// Arguments parsed from the `#[storage]` attribute.
+pub struct StorageArgs {
+    /// ERC-7201 namespace id the storage fields are rooted at, if any.
+    pub namespace: Option<String>,
+}
+
+impl Parse for StorageArgs {
+    fn parse(input: ParseStream) -> Result<Self, syn::Error> {
+        if input.is_empty() {
+            return Ok(StorageArgs { namespace: None });
+        }
+
+        let key = input.parse::<Ident>()?;
+        if key != "namespace" {
+            return Err(syn::Error::new(
+                key.span(),
+                format!(
+                    "unsupported argument: {}. Only 'namespace' is supported",
+                    key
+                ),
+            ));
+        }
+        input.parse::<syn::Token![=]>()?;
+        let namespace = input.parse::<LitStr>()?;
+        if namespace.value().is_empty() {
+            return Err(syn::Error::new(
+                namespace.span(),
+                "storage namespace cannot be empty",
+            ));
+        }
+
+        Ok(StorageArgs {
+            namespace: Some(namespace.value()),
+        })
+    }
+}
+
+/// Checks a `#[storage]` field's attributes for `#[immutable]`.
+///
+/// Immutable fields hold their value instead of a storage type, and are embedded in the
+/// runtime code at deployment.
+pub fn is_immutable(field: &syn::Field) -> Result<bool, syn::Error> {
+    let mut immutable = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("immutable"))
+    {
+        if !attr.tokens.is_empty() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`#[immutable]` does not take arguments",
+            ));
+        }
+        if immutable {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "duplicate `#[immutable]` attribute",
+            ));
+        }
+        immutable = true;
+    }
+    Ok(immutable)
+}
+
+/// Checks an event's attributes for `#[event(anonymous)]`.
+///
+/// Anonymous events omit the signature hash from their topics, which frees a topic for a
+/// fourth indexed field.
+pub fn is_anonymous_event(attrs: &[syn::Attribute]) -> Result<bool, syn::Error> {
+    let mut anonymous = false;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("event")) {
+        let ident: Ident = attr.parse_args()?;
+        if ident != "anonymous" {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!(
+                    "unsupported event option: {}. Only 'anonymous' is supported",
+                    ident
+                ),
+            ));
+        }
+        anonymous = true;
+    }
+    Ok(anonymous)
+}
+
+/// A method's `#[selector(...)]` attribute, overriding the selector derived from its name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectorOverride {
+    /// `#[selector(name = "transfer")]`: the signature is hashed under this name
+    Name(String),
+    /// `#[selector(0xa9059cbb)]`: the selector itself
+    Raw(u32),
+}
+
+impl Parse for SelectorOverride {
+    fn parse(input: ParseStream) -> Result<Self, syn::Error> {
+        if input.peek(syn::LitInt) {
+            let selector = input.parse::<syn::LitInt>()?;
+            return selector
+                .base10_parse::<u32>()
+                .map(SelectorOverride::Raw)
+                .map_err(|_| syn::Error::new(selector.span(), "a selector is 4 bytes"));
+        }
+
+        let key = input.parse::<Ident>()?;
+        if key != "name" {
+            return Err(syn::Error::new(
+                key.span(),
+                format!(
+                    "unsupported argument: {}. Expected `name = \"...\"` or a selector such as `0xa9059cbb`",
+                    key
+                ),
+            ));
+        }
+        input.parse::<Token![=]>()?;
+        let name = input.parse::<LitStr>()?;
+        let value = name.value();
+        let is_identifier = value
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+        if !is_identifier {
+            return Err(syn::Error::new(
+                name.span(),
+                "selector name must be a Solidity identifier",
+            ));
+        }
+
+        Ok(SelectorOverride::Name(value))
+    }
+}
+
+/// Parses a method's `#[selector(...)]` attribute, if any.
+///
+/// A method takes at most one: either a name the signature is hashed under, which lets
+/// Rust methods implement overloaded Solidity functions, or the raw selector.
+pub fn selector_override(attrs: &[syn::Attribute]) -> Result<Option<SelectorOverride>, syn::Error> {
+    let mut selector = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("selector")) {
+        if selector.replace(attr.parse_args()?).is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "a method can only have one `#[selector]` attribute",
+            ));
+        }
+    }
+    Ok(selector)
+}
+
+/// Checks that no two dispatched methods share a selector.
+///
+/// Selectors hashed at expansion are compared here, and a collision is reported on the
+/// method declared last. Selectors only the compiler can compute (arguments of
+/// `#[derive(SolStruct)]` types) are compared by the returned `const` assertions instead.
+pub fn check_selector_collisions(methods: &[&ImplItemMethod]) -> Result<TokenStream, syn::Error> {
+    let mut known: Vec<(u32, &Ident)> = Vec::new();
+    let mut computed = Vec::new();
+    for method in methods {
+        let name = &method.sig.ident;
+        let info = MethodInfo::try_from(*method)?;
+        match generate_fn_selector(&info, None) {
+            Some(selector) => {
+                let selector = u32::from_be_bytes(selector);
+                if let Some((_, other)) = known.iter().find(|(known, _)| *known == selector) {
+                    return Err(syn::Error::new_spanned(
+                        name,
+                        format!(
+                            "`{}` and `{}` have the same selector 0x{:08x}; give one another name with `#[selector(name = \"...\")]`",
+                            other, name, selector
+                        ),
+                    ));
+                }
+                known.push((selector, name));
+            }
+            None => computed.push((generate_selector_expr(&info, None), name)),
+        }
+    }
+
+    let mut assertions = Vec::new();
+    for (i, (selector, name)) in computed.iter().enumerate() {
+        let others = known
+            .iter()
+            .map(|(selector, name)| (quote! { #selector }, *name))
+            .chain(computed[..i].iter().map(|(s, n)| (s.clone(), *n)));
+        for (other_selector, other) in others {
+            let message = format!("`{}` and `{}` have the same selector", other, name);
+            assertions.push(quote! { assert!(#selector != #other_selector, #message); });
+        }
+    }
+
+    Ok(match assertions.is_empty() {
+        true => quote! {},
+        false => quote! { const _: () = { #(#assertions)* }; },
+    })
+}
+
 /// Generates a complete interface implementation from a collection of methods.
 ///
 /// This function creates a type-safe contract interface that can be used to
 /// call external contracts. The generated interface includes:
 /// - Address management and context types
+/// - An optional gas limit applied to every call, set with `with_gas`
 /// - Separate method implementations for read-only and mutable operations
 /// - Automatic ABI encoding/decoding for all method calls
 /// - Builder pattern for interface instantiation
@@ -243,9 +456,14 @@ pub fn generate_interface<T>(
     interface_style: Option<InterfaceNamingStyle>,
 ) -> quote::__private::TokenStream
 where
-    for<'a> MethodInfo<'a>: From<&'a T>,
+    for<'a> MethodInfo<'a>: TryFrom<&'a T, Error = syn::Error>,
 {
-    let methods: Vec<MethodInfo> = methods.iter().map(|&m| MethodInfo::from(m)).collect();
+    // A malformed `#[selector]` is reported where the interface is declared
+    let methods = methods.iter().map(|&m| MethodInfo::try_from(m));
+    let methods: Vec<MethodInfo> = match methods.collect() {
+        Ok(methods) => methods,
+        Err(e) => return e.to_compile_error(),
+    };
     let (mut_methods, immut_methods): (Vec<MethodInfo>, Vec<MethodInfo>) =
         methods.into_iter().partition(|m| m.is_mutable());
 
@@ -261,6 +479,7 @@ where
         use core::marker::PhantomData;
         pub struct #interface_name<C: CallCtx> {
             address: Address,
+            gas_limit: Option<u64>,
             _ctx: PhantomData<C>
         }
 
@@ -278,6 +497,7 @@ where
             fn into_interface(self) -> #interface_name<C> {
                 #interface_name {
                     address: self.address,
+                    gas_limit: self.gas_limit,
                     _ctx: PhantomData
                 }
             }
@@ -289,6 +509,7 @@ where
             fn from_builder(builder: InterfaceBuilder<Self>) -> Self {
                 Self {
                     address: builder.address,
+                    gas_limit: None,
                     _ctx: PhantomData
                 }
             }
@@ -298,6 +519,14 @@ where
             pub fn address(&self) -> Address {
                 self.address
             }
+
+            /// Limits the gas forwarded to each call made through this interface.
+            ///
+            /// Without a limit, calls forward all but one 64th of the remaining gas.
+            pub fn with_gas(mut self, gas_limit: u64) -> Self {
+                self.gas_limit = Some(gas_limit);
+                self
+            }
         }
 
         impl<C: StaticCtx> #interface_name<C> {
@@ -317,6 +546,7 @@ where
 /// - Encodes method parameters into calldata
 /// - Makes the appropriate contract call (call vs staticcall)
 /// - Decodes and returns the result
+/// - Documents whether the method is `view`
 ///
 /// # Parameters
 ///
@@ -343,9 +573,7 @@ fn generate_method_impl(
 ) -> TokenStream {
     let name = method.name;
     let return_type = method.return_type;
-    let method_selector = u32::from_be_bytes(
-        generate_fn_selector(method, interface_style).expect("Unable to generate fn selector"),
-    );
+    let method_selector = generate_selector_expr(method, interface_style);
 
     let (arg_names, arg_types) = get_arg_props_skip_first(method);
 
@@ -361,7 +589,7 @@ fn generate_method_impl(
         }
     } else {
         quote! {
-            let mut args_calldata = (#(#arg_names),*).abi_encode();
+            let mut args_calldata = (#(#arg_names,)*).abi_encode_params();
             let mut complete_calldata = Vec::with_capacity(4 + args_calldata.len());
             complete_calldata.extend_from_slice(&[
                 #method_selector.to_be_bytes()[0],
@@ -373,22 +601,29 @@ fn generate_method_impl(
         }
     };
 
-    let (call_fn, self_param) = if is_mutable {
+    // `&self` methods are `view`: STATICCALL makes the VM revert any state change they attempt
+    let (call_fn, self_param, mutability_doc) = if is_mutable {
         (
             quote! { hybrid_contract::call_contract },
             quote! { &mut self },
+            format!("Calls `{}` with CALL.", name),
         )
     } else {
         (
             quote! { hybrid_contract::staticcall_contract },
             quote! { &self},
+            format!(
+                "`view`: calls `{}` with STATICCALL, so it cannot modify state.",
+                name
+            ),
         )
     };
 
     // Generate different implementations based on return type
-    match extract_wrapper_types(&method.return_type) {
+    match extract_wrapper_types(method.return_type) {
         // If `Result<T, E>` handle each individual type
         WrapperType::Result(ok_type, err_type) => quote! {
+            #[doc = #mutability_doc]
             pub fn #name(#self_param, #(#arg_names: #arg_types),*) -> Result<#ok_type, #err_type>  {
                 use alloy_sol_types::SolValue;
                 use alloc::vec::Vec;
@@ -397,7 +632,8 @@ fn generate_method_impl(
 
                 let result = #call_fn(
                     self.address,
-                    0_u64,
+                    alloy_core::primitives::U256::ZERO,
+                    self.gas_limit,
                     &complete_calldata,
                     None
                 );
@@ -408,9 +644,37 @@ fn generate_method_impl(
                 }
             }
         },
+        // If `CallResult<T, E>` tell return data from revert data, and decode either
+        WrapperType::CallResult(ok_type, err_type) => {
+            let try_call_fn = if is_mutable {
+                quote! { hybrid_contract::try_call_contract }
+            } else {
+                quote! { hybrid_contract::try_staticcall_contract }
+            };
+            quote! {
+                #[doc = #mutability_doc]
+                pub fn #name(#self_param, #(#arg_names: #arg_types),*) -> hybrid_contract::CallResult<#ok_type, #err_type> {
+                    use alloy_sol_types::SolValue;
+                    use alloc::vec::Vec;
+
+                    #calldata
+
+                    let output = #try_call_fn(
+                        self.address,
+                        alloy_core::primitives::U256::ZERO,
+                        self.gas_limit,
+                        &complete_calldata,
+                        None
+                    );
+
+                    hybrid_contract::CallResult::decode(output)
+                }
+            }
+        }
         // If `Option<T>` unwrap the type to decode, and wrap it back
         WrapperType::Option(return_ty) => {
             quote! {
+                #[doc = #mutability_doc]
                 pub fn #name(#self_param, #(#arg_names: #arg_types),*) -> Option<#return_ty> {
                     use alloy_sol_types::SolValue;
                     use alloc::vec::Vec;
@@ -419,7 +683,8 @@ fn generate_method_impl(
 
                     let result = #call_fn(
                         self.address,
-                        0_u64,
+                        alloy_core::primitives::U256::ZERO,
+                        self.gas_limit,
                         &complete_calldata,
                         None
                     );
@@ -438,6 +703,7 @@ fn generate_method_impl(
                 ReturnType::Type(_, ty) => quote! { #ty },
             };
             quote! {
+                #[doc = #mutability_doc]
                 pub fn #name(#self_param, #(#arg_names: #arg_types),*) -> Option<#return_ty> {
                     use alloy_sol_types::SolValue;
                     use alloc::vec::Vec;
@@ -446,7 +712,8 @@ fn generate_method_impl(
 
                     let result = #call_fn(
                         self.address,
-                        0_u64,
+                        alloy_core::primitives::U256::ZERO,
+                        self.gas_limit,
                         &complete_calldata,
                         None
                     );
@@ -468,6 +735,8 @@ fn generate_method_impl(
 pub enum WrapperType {
     /// `Result<T, E>` return type with success and error types as TokenStreams.
     Result(TokenStream, TokenStream),
+    /// `CallResult<T, E>` return type with success and error types as TokenStreams.
+    CallResult(TokenStream, TokenStream),
     /// `Option<T>` return type with inner type as TokenStream.
     Option(TokenStream),
     /// Direct return type with no wrapper.
@@ -481,6 +750,7 @@ pub enum WrapperType {
 /// require different error handling strategies:
 ///
 /// - `Result<T, E>` - Returns `Ok(value)` on success, `Err(error)` on failure
+/// - `CallResult<T, E>` - Returns `Ok(value)` on success, the decoded revert data on failure
 /// - `Option<T>` - Returns `Some(value)` on success, `None` on failure
 /// - `T` - Direct return, wrapped in `Option` automatically
 ///
@@ -506,7 +776,7 @@ pub fn extract_wrapper_types(return_type: &ReturnType) -> WrapperType {
     };
 
     match last_segment.ident.to_string().as_str() {
-        "Result" => {
+        wrapper @ ("Result" | "CallResult") => {
             let PathArguments::AngleBracketed(args) = &last_segment.arguments else {
                 return WrapperType::None;
             };
@@ -527,7 +797,10 @@ pub fn extract_wrapper_types(return_type: &ReturnType) -> WrapperType {
                 _ => return WrapperType::None,
             };
 
-            WrapperType::Result(ok_type, err_type)
+            match wrapper {
+                "Result" => WrapperType::Result(ok_type, err_type),
+                _ => WrapperType::CallResult(ok_type, err_type),
+            }
         }
         "Option" => {
             let PathArguments::AngleBracketed(args) = &last_segment.arguments else {
@@ -554,12 +827,15 @@ pub fn extract_wrapper_types(return_type: &ReturnType) -> WrapperType {
 /// Generates a 4-byte function selector for a method following Ethereum ABI standards.
 ///
 /// This function computes the function selector by:
-/// 1. Converting the method name according to the specified style
+/// 1. Converting the method name according to the specified style, or taking the name
+///    of its `#[selector(name = "...")]`
 /// 2. Converting Rust parameter types to Solidity ABI type names
 /// 3. Creating the canonical function signature string
 /// 4. Computing the Keccak-256 hash of the signature
 /// 5. Returning the first 4 bytes as the selector
 ///
+/// A raw `#[selector(0x...)]` is returned as is.
+///
 /// # Parameters
 ///
 /// * `method` - The method to generate a selector for
@@ -585,12 +861,10 @@ pub fn generate_fn_selector(
     method: &MethodInfo,
     style: Option<InterfaceNamingStyle>,
 ) -> Option<[u8; 4]> {
-    let name = match style {
-        None => method.name.to_string(),
-        Some(style) => match style {
-            InterfaceNamingStyle::CamelCase => to_camel_case(method.name.to_string()),
-        },
-    };
+    if let Some(SelectorOverride::Raw(selector)) = method.selector {
+        return Some(selector.to_be_bytes());
+    }
+    let name = signature_name(method, style);
 
     let (_, arg_types) = get_arg_props_skip_first(method);
     let args = arg_types
@@ -609,6 +883,229 @@ pub fn generate_fn_selector(
     Some(selector_bytes)
 }
 
+/// Generates an expression evaluating to a method's selector, as a `u32`.
+///
+/// Selectors of methods taking only primitive types are hashed here, as by
+/// [`generate_fn_selector`]. Types the macro cannot resolve, such as `#[derive(SolStruct)]`
+/// structs, are only known to the compiler: for those the expression is a `const` block
+/// hashing the `SolType::SOL_NAME` of the argument tuple with `hybrid_contract::abi::selector`.
+pub fn generate_selector_expr(
+    method: &MethodInfo,
+    style: Option<InterfaceNamingStyle>,
+) -> TokenStream {
+    if let Some(selector) = generate_fn_selector(method, style) {
+        let selector = u32::from_be_bytes(selector);
+        return quote! { #selector };
+    }
+
+    let name = signature_name(method, style);
+    let (_, arg_types) = get_arg_props_skip_first(method);
+    quote! {
+        {
+            const SELECTOR: u32 = hybrid_contract::abi::selector(
+                #name,
+                <(#(<#arg_types as alloy_sol_types::SolValue>::SolType,)*) as alloy_sol_types::SolType>::SOL_NAME,
+            );
+            SELECTOR
+        }
+    }
+}
+
+/// Generates the decoding of a dispatched method's arguments from `calldata`, binding each
+/// to its name.
+///
+/// When every argument is a single word (`Address`, `bool`, integers, fixed bytes), each one
+/// is read at its fixed offset with `hybrid_contract::abi::decode_word`. Other arguments go
+/// through `abi_decode_params`, which follows the offsets of dynamic types.
+pub fn generate_args_decode(method: &MethodInfo) -> TokenStream {
+    let (arg_names, arg_types) = get_arg_props_skip_first(method);
+    let is_word = |ty: &&Type| {
+        matches!(
+            rust_type_to_sol_type(ty),
+            Ok(DynSolType::Address
+                | DynSolType::Bool
+                | DynSolType::Uint(_)
+                | DynSolType::Int(_)
+                | DynSolType::FixedBytes(_))
+        )
+    };
+
+    if arg_types.iter().all(is_word) {
+        let indices = 0..arg_names.len();
+        quote! {
+            #( let #arg_names = hybrid_contract::abi::decode_word::<#arg_types>(calldata, #indices); )*
+        }
+    } else {
+        quote! {
+            let (#( #arg_names, )*) = <(#( #arg_types, )*)>::abi_decode_params(calldata, true).expect("abi decode failed");
+        }
+    }
+}
+
+/// The name a method's signature is hashed under: its `#[selector(name = "...")]`, else its
+/// Rust name in the given style.
+fn signature_name(method: &MethodInfo, style: Option<InterfaceNamingStyle>) -> String {
+    match (&method.selector, style) {
+        (Some(SelectorOverride::Name(name)), _) => name.clone(),
+        (_, None) => method.name.to_string(),
+        (_, Some(InterfaceNamingStyle::CamelCase)) => to_camel_case(method.name.to_string()),
+    }
+}
+
+/// Checks whether a type is `&[u8]`.
+fn is_byte_slice(ty: &Type) -> bool {
+    let Type::Reference(reference) = ty else {
+        return false;
+    };
+    let Type::Slice(slice) = reference.elem.as_ref() else {
+        return false;
+    };
+    reference.mutability.is_none()
+        && matches!(slice.elem.as_ref(), Type::Path(path) if path.path.is_ident("u8"))
+}
+
+/// Generates the call to a contract's `#[fallback]` method, run for calldata that matches
+/// no selector.
+///
+/// As in Solidity, the fallback either takes no arguments or receives the full calldata
+/// as `&[u8]`, and may return raw bytes (any `AsRef<[u8]>`) which are returned without
+/// ABI encoding. It rejects value unless also marked `#[payable]`.
+///
+/// The generated code reads the calldata from a variable named `input` and never returns.
+pub fn generate_fallback_call(
+    method: &ImplItemMethod,
+    payable: bool,
+) -> Result<TokenStream, syn::Error> {
+    let name = &method.sig.ident;
+    if method.sig.receiver().is_none() {
+        return Err(syn::Error::new_spanned(
+            name,
+            "`#[fallback]` method must take `&self` or `&mut self`",
+        ));
+    }
+
+    // Point at the first argument that is not `&[u8]`, or at the second one if both are
+    let extra_args: Vec<_> = method.sig.inputs.iter().skip(1).collect();
+    let invalid = extra_args
+        .iter()
+        .find(|arg| !matches!(arg, FnArg::Typed(arg) if is_byte_slice(&arg.ty)))
+        .or(extra_args.get(1));
+    if let Some(arg) = invalid {
+        return Err(syn::Error::new_spanned(
+            arg,
+            "`#[fallback]` method takes no arguments or the calldata as `&[u8]`",
+        ));
+    }
+    let args = match extra_args.is_empty() {
+        true => quote! {},
+        false => quote! { input },
+    };
+
+    let checks = if payable {
+        quote! {}
+    } else {
+        quote! {
+            if hybrid_contract::msg_value() > U256::from(0) {
+                panic!("Non-payable function");
+            }
+        }
+    };
+
+    let return_handling = match &method.sig.output {
+        ReturnType::Default => quote! {
+            self.#name(#args);
+            hybrid_contract::return_riscv(0, 0)
+        },
+        ReturnType::Type(_, _) => quote! {
+            let output = self.#name(#args);
+            let output: &[u8] = output.as_ref();
+            hybrid_contract::return_riscv(output.as_ptr() as u64, output.len() as u64)
+        },
+    };
+
+    Ok(quote! {
+        {
+            #checks
+            #return_handling
+        }
+    })
+}
+
+/// Generates the call to a contract's `#[receive]` method, run for calls with empty
+/// calldata such as plain ether transfers.
+///
+/// As in Solidity, the receive method is always payable, takes no arguments and returns
+/// nothing. The generated code never returns.
+pub fn generate_receive_call(method: &ImplItemMethod) -> Result<TokenStream, syn::Error> {
+    let name = &method.sig.ident;
+    if method.sig.receiver().is_none() || method.sig.inputs.len() != 1 {
+        return Err(syn::Error::new_spanned(
+            &method.sig.inputs,
+            "`#[receive]` method must take only `&self` or `&mut self`",
+        ));
+    }
+    if let ReturnType::Type(_, ty) = &method.sig.output {
+        return Err(syn::Error::new_spanned(
+            ty,
+            "`#[receive]` method cannot return a value",
+        ));
+    }
+
+    Ok(quote! {
+        self.#name();
+        hybrid_contract::return_riscv(0, 0)
+    })
+}
+
+/// Generates the checks of a method's `#[guard(...)]` attributes, run in its dispatch arm
+/// after the arguments are decoded and before the method is called.
+///
+/// Each guard is a contract method returning a `hybrid_contract::Guard`, named either alone
+/// (`#[guard(only_owner)]`) or as a call whose arguments may use those of the guarded method
+/// (`#[guard(only_holder(token_id))]`). Guards run in the order they are written.
+pub fn generate_guards(method: &ImplItemMethod) -> Result<TokenStream, syn::Error> {
+    let mut checks = Vec::new();
+    for attr in method
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("guard"))
+    {
+        let guards = attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+        if guards.is_empty() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "expected a guard method, as in `#[guard(only_owner)]`",
+            ));
+        }
+
+        for guard in guards {
+            let call = match &guard {
+                Expr::Path(path) if path.path.get_ident().is_some() => {
+                    let name = path.path.get_ident();
+                    quote! { self.#name() }
+                }
+                Expr::Call(call) => match &*call.func {
+                    Expr::Path(path) if path.path.get_ident().is_some() => {
+                        let name = path.path.get_ident();
+                        let args = &call.args;
+                        quote! { self.#name(#args) }
+                    }
+                    _ => return Err(syn::Error::new_spanned(&call.func, "expected a method name")),
+                },
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        guard,
+                        "expected a guard method, as in `#[guard(only_owner)]` or `#[guard(only_holder(id))]`",
+                    ))
+                }
+            };
+            checks.push(quote! { hybrid_contract::Guard::check(#call); });
+        }
+    }
+
+    Ok(quote! { #(#checks)* })
+}
+
 /// Converts Rust types to their Solidity ABI equivalent types.
 ///
 /// This function provides the core type mapping between Rust's type system
@@ -624,9 +1121,9 @@ pub fn generate_fn_selector(
 /// | `bool`, `Bool` | `bool` | Boolean value |
 /// | `String`, `str` | `string` | UTF-8 string |
 /// | `Bytes` | `bytes` | Dynamic byte array |
-/// | `B1`-`B32` | `bytes1`-`bytes32` | Fixed-size byte arrays |
-/// | `U8`-`U256` | `uint8`-`uint256` | Unsigned integers |
-/// | `I8`-`I256` | `int8`-`int256` | Signed integers |
+/// | `B8`-`B256`, `FixedBytes<N>` | `bytes1`-`bytes32` | Fixed-size byte arrays, `B<N>` sized in bits |
+/// | `u8`-`u128`, `U8`-`U256` | `uint8`-`uint256` | Unsigned integers |
+/// | `i8`-`i128`, `I8`-`I256` | `int8`-`int256` | Signed integers |
 /// | `Vec<T>` | `T[]` | Dynamic array |
 /// | `[T; N]` | `T[N]` | Fixed-size array |
 /// | `(T1, T2, ...)` | `(T1, T2, ...)` | Tuple |
@@ -653,8 +1150,9 @@ pub fn generate_fn_selector(
 ///
 /// # Implementation Notes
 ///
-/// - Integer types must be multiples of 8 bits and ≤ 256 bits
-/// - Fixed bytes must be between 1 and 32 bytes
+/// - Value types are mapped by [`hybrid_abi::ValueType`], shared with the JSON ABI and storage
+///   layout written by `hybrid-compile`
+/// - Integer and fixed bytes types must be multiples of 8 bits and ≤ 256 bits
 /// - Nested types (arrays of arrays, etc.) are fully supported
 /// - Custom struct types are not yet supported (planned enhancement)
 pub fn rust_type_to_sol_type(ty: &Type) -> Result<DynSolType, &'static str> {
@@ -665,68 +1163,35 @@ pub fn rust_type_to_sol_type(ty: &Type) -> Result<DynSolType, &'static str> {
             let ident = &segment.ident;
             let type_name = ident.to_string();
 
-            match type_name.as_str() {
-                // Fixed-size types
-                "Address" => Ok(DynSolType::Address),
-                "Function" => Ok(DynSolType::Function),
-                "bool" | "Bool" => Ok(DynSolType::Bool),
-                "String" | "str" => Ok(DynSolType::String),
-                "Bytes" => Ok(DynSolType::Bytes),
-                // Fixed-size bytes
-                b if b.starts_with('B') => {
-                    let size: usize = b
-                        .trim_start_matches('B')
-                        .parse()
-                        .map_err(|_| "Invalid fixed bytes size")?;
-                    if size > 0 && size <= 32 {
-                        Ok(DynSolType::FixedBytes(size))
-                    } else {
-                        Err("Invalid fixed bytes size (between 1-32)")
-                    }
-                }
-                // Fixed-size unsigned integers
-                u if u.starts_with('U') => {
-                    let size: usize = u
-                        .trim_start_matches('U')
-                        .parse()
-                        .map_err(|_| "Invalid uint size")?;
-                    if size > 0 && size <= 256 && size % 8 == 0 {
-                        Ok(DynSolType::Uint(size))
-                    } else {
-                        Err("Invalid uint size (multiple of 8 + leq 256)")
-                    }
-                }
-                // Fixed-size signed integers
-                i if i.starts_with('I') => {
-                    let size: usize = i
-                        .trim_start_matches('I')
-                        .parse()
-                        .map_err(|_| "Invalid int size")?;
-                    if size > 0 && size <= 256 && size % 8 == 0 {
-                        Ok(DynSolType::Int(size))
-                    } else {
-                        Err("Invalid int size (must be multiple of 8, max 256)")
-                    }
-                }
-                // Handle vecs
-                _ => {
-                    if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                        match type_name.as_str() {
-                            "Vec" => {
-                                let inner = args.args.first().ok_or("Empty Vec type argument")?;
-                                if let syn::GenericArgument::Type(inner_ty) = inner {
-                                    let inner_sol_type = rust_type_to_sol_type(inner_ty)?;
-                                    Ok(DynSolType::Array(Box::new(inner_sol_type)))
-                                } else {
-                                    Err("Invalid Vec type argument")
-                                }
-                            }
-                            _ => Err("Unsupported generic type"),
+            if let Some(value_type) = ValueType::from_type(ty) {
+                return Ok(match value_type {
+                    ValueType::Address => DynSolType::Address,
+                    ValueType::Bool => DynSolType::Bool,
+                    ValueType::String => DynSolType::String,
+                    ValueType::Bytes => DynSolType::Bytes,
+                    ValueType::Function => DynSolType::Function,
+                    ValueType::FixedBytes(size) => DynSolType::FixedBytes(size),
+                    ValueType::Uint(bits) => DynSolType::Uint(bits),
+                    ValueType::Int(bits) => DynSolType::Int(bits),
+                });
+            }
+
+            // Handle vecs
+            if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                match type_name.as_str() {
+                    "Vec" => {
+                        let inner = args.args.first().ok_or("Empty Vec type argument")?;
+                        if let syn::GenericArgument::Type(inner_ty) = inner {
+                            let inner_sol_type = rust_type_to_sol_type(inner_ty)?;
+                            Ok(DynSolType::Array(Box::new(inner_sol_type)))
+                        } else {
+                            Err("Invalid Vec type argument")
                         }
-                    } else {
-                        Err("Unsupported type")
                     }
+                    _ => Err("Unsupported generic type"),
                 }
+            } else {
+                Err("Unsupported type")
             }
         }
         Type::Array(array) => {
@@ -834,7 +1299,8 @@ fn to_camel_case(s: String) -> String {
 ///
 /// 1. **Constructor Arguments**: Decode ABI-encoded constructor parameters
 /// 2. **Contract Initialization**: Call constructor or use default initialization
-/// 3. **Runtime Code Return**: Load and return the runtime bytecode
+/// 3. **Runtime Code Return**: Load and return the runtime bytecode, followed by the
+///    `#[immutable]` fields set by the constructor
 /// 4. **Exit**: Terminate deployment with the returned runtime code
 ///
 /// # Constructor Requirements
@@ -850,7 +1316,8 @@ fn to_camel_case(s: String) -> String {
 /// The deployment code expects the runtime bytecode to be available at:
 /// `../target/riscv64imac-unknown-none-elf/release/runtime`
 ///
-/// This file is included at compile time and returned during deployment.
+/// This file is included at compile time and returned during deployment, with the immutables
+/// appended (see `hybrid_contract::immutable`).
 pub fn generate_deployment_code(
     struct_name: &Ident,
     constructor: Option<&ImplItemMethod>,
@@ -858,7 +1325,10 @@ pub fn generate_deployment_code(
     // Decode constructor args + trigger constructor logic
     let constructor_code = match constructor {
         Some(method) => {
-            let method_info = MethodInfo::from(method);
+            let method_info = match MethodInfo::try_from(method) {
+                Ok(info) => info,
+                Err(e) => return e.to_compile_error(),
+            };
             let (arg_names, arg_types) = get_arg_props_all(&method_info);
             quote! {
                 impl #struct_name { #method }
@@ -868,11 +1338,11 @@ pub fn generate_deployment_code(
 
                 let (#(#arg_names),*) = <(#(#arg_types),*)>::abi_decode(&calldata, true)
                     .expect("Failed to decode constructor args");
-                #struct_name::new(#(#arg_names),*);
+                let contract = #struct_name::new(#(#arg_names),*);
             }
         }
         None => quote! {
-            #struct_name::default();
+            let contract = #struct_name::default();
         },
     };
 
@@ -884,11 +1354,13 @@ pub fn generate_deployment_code(
         pub extern "C" fn main() -> ! {
             #constructor_code
 
-            // Return runtime code
+            // Return runtime code, followed by the immutables set by the constructor
             let runtime: &[u8] = include_bytes!("../target/riscv64imac-unknown-none-elf/release/runtime");
-            let mut prepended_runtime = Vec::with_capacity(1 + runtime.len());
+            let immutables = contract.__immutables();
+            let mut prepended_runtime = Vec::with_capacity(1 + runtime.len() + immutables.len());
             prepended_runtime.push(0xff);
             prepended_runtime.extend_from_slice(runtime);
+            prepended_runtime.extend_from_slice(&immutables);
 
             let prepended_runtime_slice: &[u8] = &prepended_runtime;
             let result_ptr = prepended_runtime_slice.as_ptr() as u64;
@@ -940,7 +1412,7 @@ mod tests {
 
     impl<'a> From<&'a MockMethod> for MethodInfo<'a> {
         fn from(test_method: &'a MockMethod) -> Self {
-            MethodInfo::from(&test_method.method)
+            MethodInfo::try_from(&test_method.method).unwrap()
         }
     }
 
@@ -985,9 +1457,10 @@ mod tests {
     #[test]
     fn test_rust_to_sol_fixed_bytes() {
         let test_cases = vec![
-            (parse_quote!(B1), DynSolType::FixedBytes(1)),
-            (parse_quote!(B16), DynSolType::FixedBytes(16)),
-            (parse_quote!(B32), DynSolType::FixedBytes(32)),
+            (parse_quote!(B8), DynSolType::FixedBytes(1)),
+            (parse_quote!(B32), DynSolType::FixedBytes(4)),
+            (parse_quote!(B256), DynSolType::FixedBytes(32)),
+            (parse_quote!(FixedBytes<20>), DynSolType::FixedBytes(20)),
         ];
 
         for (rust_type, expected_sol_type) in test_cases {
@@ -999,7 +1472,9 @@ mod tests {
 
         // Invalid cases
         assert!(rust_type_to_sol_type(&parse_quote!(B0)).is_err());
-        assert!(rust_type_to_sol_type(&parse_quote!(B33)).is_err());
+        assert!(rust_type_to_sol_type(&parse_quote!(B7)).is_err()); // Not multiple of 8
+        assert!(rust_type_to_sol_type(&parse_quote!(B264)).is_err());
+        assert!(rust_type_to_sol_type(&parse_quote!(FixedBytes<33>)).is_err());
     }
 
     #[test]
@@ -1060,7 +1535,7 @@ mod tests {
         );
 
         assert_eq!(
-            rust_type_to_sol_type(&parse_quote!((Address, B32, I128))).unwrap(),
+            rust_type_to_sol_type(&parse_quote!((Address, B256, I128))).unwrap(),
             DynSolType::Tuple(vec![
                 DynSolType::Address,
                 DynSolType::FixedBytes(32),
@@ -1157,6 +1632,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fn_selector_fixed_bytes() {
+        // `B<N>` is sized in bits, like `U<N>`, so `B32` is a `bytes4`
+        let method = MockMethod::new(
+            "register",
+            vec!["id: B32", "root: B256", "owner: FixedBytes<20>"],
+        );
+        assert_eq!(
+            generate_fn_selector(&method.info(), None).unwrap(),
+            get_selector_from_sig("register(bytes4,bytes32,bytes20)")
+        );
+    }
+
     #[test]
     fn test_fn_selector_rename_camel_case() {
         let method = MockMethod::new("get_balance", vec![]);
@@ -1172,6 +1660,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_selector_expr_for_user_types() {
+        // Primitive signatures are hashed by the macro
+        let method = MockMethod::new("transfer", vec!["to: Address", "amount: U256"]);
+        let selector = u32::from_be_bytes(get_selector_from_sig("transfer(address,uint256)"));
+        assert_eq!(
+            generate_selector_expr(&method.info(), None).to_string(),
+            quote!(#selector).to_string()
+        );
+
+        // `SolStruct` signatures are left to the compiler
+        let method = MockMethod::new("open", vec!["orders: Vec<Order>", "amount: U256"]);
+        assert!(generate_fn_selector(&method.info(), None).is_none());
+        let expr = generate_selector_expr(&method.info(), None).to_string();
+        assert!(expr.contains("hybrid_contract :: abi :: selector"));
+        assert!(expr.contains("\"open\""));
+    }
+
     #[test]
     fn test_fn_selector_erc20() {
         let cases = vec![
@@ -1248,4 +1754,218 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_anonymous_event() {
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(Event)])];
+        assert!(!is_anonymous_event(&attrs).unwrap());
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[event(anonymous)])];
+        assert!(is_anonymous_event(&attrs).unwrap());
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[event(indexed)])];
+        assert!(is_anonymous_event(&attrs).is_err());
+    }
+
+    #[test]
+    fn test_immutable_fields() {
+        use syn::parse::Parser;
+        let field = |tokens| syn::Field::parse_named.parse2(tokens).unwrap();
+
+        assert!(!is_immutable(&field(quote!(balance: Slot<U256>))).unwrap());
+        assert!(is_immutable(&field(quote!(#[immutable] owner: Address))).unwrap());
+        assert!(is_immutable(&field(quote!(#[immutable(owner)] owner: Address))).is_err());
+        assert!(is_immutable(&field(quote!(#[immutable] #[immutable] owner: Address))).is_err());
+    }
+
+    #[test]
+    fn test_fallback_and_receive_signatures() {
+        let fallback: ImplItemMethod = parse_quote! { fn fallback(&mut self) {} };
+        assert!(generate_fallback_call(&fallback, false).is_ok());
+        let fallback: ImplItemMethod =
+            parse_quote! { fn fallback(&mut self, input: &[u8]) -> Bytes { todo!() } };
+        assert!(generate_fallback_call(&fallback, true).is_ok());
+        let fallback: ImplItemMethod = parse_quote! { fn fallback(&mut self, a: U256, b: U256) {} };
+        assert!(generate_fallback_call(&fallback, false).is_err());
+        for fallback in [
+            parse_quote! { fn fallback(&mut self, input: U256) {} },
+            parse_quote! { fn fallback(&mut self, input: &mut [u8]) {} },
+            parse_quote! { fn fallback(&mut self, input: Vec<u8>) {} },
+            parse_quote! { fn fallback(&mut self, input: &[u8], extra: &[u8]) {} },
+        ] {
+            let fallback: ImplItemMethod = fallback;
+            assert!(generate_fallback_call(&fallback, false).is_err());
+        }
+
+        let receive: ImplItemMethod = parse_quote! { fn receive(&mut self) {} };
+        assert!(generate_receive_call(&receive).is_ok());
+        let receive: ImplItemMethod = parse_quote! { fn receive(&mut self, amount: U256) {} };
+        assert!(generate_receive_call(&receive).is_err());
+        let receive: ImplItemMethod = parse_quote! { fn receive(&mut self) -> bool { true } };
+        assert!(generate_receive_call(&receive).is_err());
+    }
+
+    #[test]
+    fn test_guards() {
+        let method: ImplItemMethod = parse_quote! {
+            #[guard(only_owner, only_holder(id))]
+            #[guard(not_paused)]
+            pub fn renew(&mut self, id: U256) {}
+        };
+        assert_eq!(
+            generate_guards(&method).unwrap().to_string(),
+            quote! {
+                hybrid_contract::Guard::check(self.only_owner());
+                hybrid_contract::Guard::check(self.only_holder(id));
+                hybrid_contract::Guard::check(self.not_paused());
+            }
+            .to_string()
+        );
+
+        let method: ImplItemMethod = parse_quote! { pub fn renew(&mut self) {} };
+        assert!(generate_guards(&method).unwrap().is_empty());
+        let method: ImplItemMethod = parse_quote! { #[guard()] pub fn renew(&mut self) {} };
+        assert!(generate_guards(&method).is_err());
+        let method: ImplItemMethod = parse_quote! { #[guard(self.x)] pub fn renew(&mut self) {} };
+        assert!(generate_guards(&method).is_err());
+    }
+
+    #[test]
+    fn test_selector_override() {
+        let method: ImplItemMethod = parse_quote! {
+            #[selector(name = "transfer")]
+            pub fn transfer_with_data(&mut self, to: Address, amount: U256, data: Bytes) {}
+        };
+        assert_eq!(
+            generate_fn_selector(&MethodInfo::try_from(&method).unwrap(), None).unwrap(),
+            get_selector_from_sig("transfer(address,uint256,bytes)")
+        );
+        // The name is used as written, whatever the interface's naming style
+        assert_eq!(
+            generate_fn_selector(
+                &MethodInfo::try_from(&method).unwrap(),
+                Some(InterfaceNamingStyle::CamelCase)
+            )
+            .unwrap(),
+            get_selector_from_sig("transfer(address,uint256,bytes)")
+        );
+
+        let method: ImplItemMethod = parse_quote! {
+            #[selector(0x23b872dd)]
+            pub fn move_from(&mut self, from: Address, to: Address, amount: U256) {}
+        };
+        assert_eq!(
+            generate_fn_selector(&MethodInfo::try_from(&method).unwrap(), None).unwrap(),
+            [0x23, 0xb8, 0x72, 0xdd]
+        );
+
+        let method: ImplItemMethod = parse_quote! {
+            #[selector(name = "open")]
+            pub fn open_position(&mut self, position: Position) {}
+        };
+        let expr =
+            generate_selector_expr(&MethodInfo::try_from(&method).unwrap(), None).to_string();
+        assert!(expr.contains("\"open\""));
+
+        for attr in [
+            quote!(#[selector(0x1234567890)]),
+            quote!(#[selector(label = "transfer")]),
+            quote!(#[selector(name = "transfer(address)")]),
+            quote!(#[selector(name = "a")] #[selector(name = "b")]),
+        ] {
+            let method: ImplItemMethod = parse_quote! { #attr pub fn m(&mut self) {} };
+            assert!(selector_override(&method.attrs).is_err());
+            assert!(MethodInfo::try_from(&method).is_err());
+        }
+    }
+
+    #[test]
+    fn test_interface_reports_bad_selector() {
+        let method: TraitItemMethod = parse_quote! {
+            #[selector(label = "transfer")]
+            fn transfer(&mut self, to: Address, amount: U256);
+        };
+        let interface = generate_interface(&[&method], &format_ident!("IToken"), None);
+        assert!(interface.to_string().contains("compile_error"));
+    }
+
+    #[test]
+    fn test_args_decode() {
+        let method = MockMethod::new("transfer", vec!["to: Address", "amount: U256"]);
+        assert_eq!(
+            generate_args_decode(&method.info()).to_string(),
+            quote! {
+                let arg0 = hybrid_contract::abi::decode_word::<Address>(calldata, 0usize);
+                let arg1 = hybrid_contract::abi::decode_word::<U256>(calldata, 1usize);
+            }
+            .to_string()
+        );
+
+        let method = MockMethod::new("total_supply", vec![]);
+        assert!(generate_args_decode(&method.info()).is_empty());
+
+        // Dynamic and user-defined types are not at a fixed offset
+        for args in [vec!["to: Address", "data: Bytes"], vec!["order: Order"]] {
+            let method = MockMethod::new("call", args);
+            let decode = generate_args_decode(&method.info()).to_string();
+            assert!(decode.contains("abi_decode_params"));
+        }
+    }
+
+    #[test]
+    fn test_selector_collisions() {
+        let transfer: ImplItemMethod =
+            parse_quote! { pub fn transfer(&mut self, to: Address, amount: U256) {} };
+        let send: ImplItemMethod = parse_quote! {
+            #[selector(name = "transfer")]
+            pub fn send(&mut self, to: Address, amount: U256) {}
+        };
+        let raw: ImplItemMethod = parse_quote! {
+            #[selector(0xa9059cbb)]
+            pub fn pay(&mut self, to: Address) {}
+        };
+        let overload: ImplItemMethod = parse_quote! {
+            #[selector(name = "transfer")]
+            pub fn transfer_all(&mut self, to: Address) {}
+        };
+
+        assert!(check_selector_collisions(&[&transfer, &overload])
+            .unwrap()
+            .is_empty());
+        let err = check_selector_collisions(&[&transfer, &overload, &send]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`transfer` and `send` have the same selector 0xa9059cbb; give one another name with `#[selector(name = \"...\")]`"
+        );
+        assert!(check_selector_collisions(&[&transfer, &raw]).is_err());
+
+        // Selectors of user-defined types are compared by the compiler
+        let open: ImplItemMethod = parse_quote! { pub fn open(&mut self, position: Position) {} };
+        let checks = check_selector_collisions(&[&transfer, &open])
+            .unwrap()
+            .to_string();
+        assert!(checks.contains("\"`transfer` and `open` have the same selector\""));
+    }
+
+    #[test]
+    fn test_wrapper_types() {
+        let wrapper = |ty: ReturnType| match extract_wrapper_types(&ty) {
+            WrapperType::Result(ok, err) => format!("Result {} {}", ok, err),
+            WrapperType::CallResult(ok, err) => format!("CallResult {} {}", ok, err),
+            WrapperType::Option(inner) => format!("Option {}", inner),
+            WrapperType::None => String::from("None"),
+        };
+
+        assert_eq!(
+            wrapper(parse_quote! { -> Result<bool, TokenError> }),
+            "Result bool TokenError"
+        );
+        assert_eq!(
+            wrapper(parse_quote! { -> hybrid_contract::CallResult<U256, TokenError> }),
+            "CallResult U256 TokenError"
+        );
+        assert_eq!(wrapper(parse_quote! { -> Option<U256> }), "Option U256");
+        assert_eq!(wrapper(parse_quote! { -> U256 }), "None");
+        assert_eq!(wrapper(ReturnType::Default), "None");
+    }
 }
@@ -15,7 +15,13 @@
 //! - **Event Emission**: `#[derive(Event)]` for blockchain event logging
 //! - **Contract Interfaces**: `#[interface]` for generating type-safe contract interfaces
 //! - **Storage Management**: `#[storage]` for defining persistent contract storage
+//! - **Storage Structs**: `#[derive(StorageStruct)]` for structs packed with Solidity's layout
+//! - **Solidity Types**: `#[derive(SolStruct)]` and `#[derive(SolEnum)]` for structs and enums
+//!   in method signatures
 //! - **Payment Handling**: `#[payable]` attribute for functions that can receive payments
+//! - **Fallback and Receive**: `#[fallback]` and `#[receive]` for unmatched calls and plain transfers
+//! - **Guards**: `#[guard]` and `#[nonreentrant]` for access control and reentrancy protection
+//! - **Selectors**: `#[selector]` to dispatch a method on another name or selector
 //!
 //! ## Basic Usage
 //!
@@ -54,7 +60,6 @@
 //! ```
 
 extern crate proc_macro;
-use alloy_core::primitives::U256;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
@@ -63,7 +68,11 @@ use syn::{
 };
 
 mod helpers;
-use crate::helpers::{InterfaceArgs, MethodInfo};
+use crate::helpers::{InterfaceArgs, MethodInfo, StorageArgs};
+
+/// Number of dispatched methods from which `#[contract]` looks selectors up by binary search
+/// instead of a `match`, whose arms for `SolStruct` methods are compared one by one.
+const DISPATCH_TABLE_MIN_METHODS: usize = 8;
 
 /// Derives an `Error` trait implementation for enums that can be ABI-encoded and used
 /// as smart contract error types.
@@ -71,6 +80,7 @@ use crate::helpers::{InterfaceArgs, MethodInfo};
 /// This derive macro generates implementations for:
 /// - `Error::abi_encode()` - Encodes the error into ABI format with 4-byte selector
 /// - `Error::abi_decode()` - Decodes ABI bytes back into the error type
+/// - `Error::try_abi_decode()` - Same, returning `None` for data that is not one of the variants
 /// - `Debug` trait for error display
 ///
 /// The error selector is computed as the first 4 bytes of the Keccak-256 hash of the
@@ -104,6 +114,9 @@ use crate::helpers::{InterfaceArgs, MethodInfo};
 /// let error = TokenError::InsufficientBalance;
 /// let encoded = error.abi_encode(); // 4-byte selector
 /// let decoded = TokenError::abi_decode(&encoded, true); // Reconstructs error
+///
+/// // Revert data of a failed call may hold any error
+/// let decoded = TokenError::try_abi_decode(&revert_data, true); // `None` if not a `TokenError`
 /// ```
 ///
 /// # Error Signature Generation
@@ -111,7 +124,7 @@ use crate::helpers::{InterfaceArgs, MethodInfo};
 /// Error signatures follow the format: `ErrorName(type1,type2,...)` where types
 /// are converted to their Solidity ABI type names. For example:
 /// - `TransferFailed(Address, U256)` → `"TransferFailed(address,uint256)"`
-/// - `InsufficientBalance` → `"InsufficientBalance"`
+/// - `InsufficientBalance` → `"InsufficientBalance()"`
 ///
 /// # ABI Compatibility
 ///
@@ -135,7 +148,7 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
 
         let signature = match &variant.fields {
             Fields::Unit => {
-                format!("{}::{}", name, variant_name)
+                format!("{}()", variant_name)
             }
             Fields::Unnamed(fields) => {
                 let type_names: Vec<_> = fields
@@ -149,7 +162,7 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
                     })
                     .collect();
 
-                format!("{}::{}({})", name, variant_name, type_names.join(","))
+                format!("{}({})", variant_name, type_names.join(","))
             }
             Fields::Named(_) => panic!("Named fields are not supported"),
         };
@@ -192,7 +205,7 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
 
         let signature = match &variant.fields {
             Fields::Unit => {
-                format!("{}::{}", name, variant_name)
+                format!("{}()", variant_name)
             },
             Fields::Unnamed(fields) => {
                 let type_names: Vec<_> = fields.unnamed.iter()
@@ -202,8 +215,7 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
                         .into_owned()
                     ).collect();
 
-                format!("{}::{}({})",
-                    name,
+                format!("{}({})",
                     variant_name,
                     type_names.join(",")
                 )
@@ -214,14 +226,15 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
         let selector_bytes = quote!{ &keccak256(#signature.as_bytes())[..4].to_vec() };
 
         match &variant.fields {
-            Fields::Unit => quote! { selector if selector == #selector_bytes => #name::#variant_name },
+            Fields::Unit => quote! { selector if selector == #selector_bytes => Some(#name::#variant_name) },
             Fields::Unnamed(fields) => {
                 let field_types: Vec<_> = fields.unnamed.iter().map(|f| &f.ty).collect();
-                let indices: Vec<_> = (0..fields.unnamed.len()).collect();
+                let vars: Vec<_> = (0..fields.unnamed.len())
+                    .map(|i| format_ident!("_{}", i))
+                    .collect();
                 quote!{ selector if selector == #selector_bytes => {
-                    let mut values = Vec::new();
-                    #( values.push(<#field_types>::abi_decode(data.unwrap(), true).expect("Unable to decode")); )*
-                    #name::#variant_name(#(values[#indices]),*)
+                    let (#(#vars,)*) = <(#(#field_types,)*)>::abi_decode_params(data, validate).ok()?;
+                    Some(#name::#variant_name(#(#vars),*))
                 }}
             },
             Fields::Named(_) => panic!("Named fields are not supported"),
@@ -262,17 +275,25 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
             }
 
             fn abi_decode(bytes: &[u8], validate: bool) -> Self {
+                if bytes.len() < 4 { panic!("Invalid error length") };
+
+                match Self::try_abi_decode(bytes, validate) {
+                    Some(err) => err,
+                    None => panic!("Unknown error")
+                }
+            }
+
+            fn try_abi_decode(bytes: &[u8], validate: bool) -> Option<Self> {
                 use alloy_core::primitives::keccak256;
                 use alloy_sol_types::SolValue;
-                use alloc::vec::Vec;
 
-                if bytes.len() < 4 { panic!("Invalid error length") };
-                let selector = &bytes[..4];
-                let data = if bytes.len() > 4 { Some(&bytes[4..]) } else { None };
+                if bytes.len() < 4 { return None };
+                #[allow(unused_variables)]
+                let (selector, data) = bytes.split_at(4);
 
                 match selector {
                     #(#decode_arms),*,
-                    _ => panic!("Unknown error")
+                    _ => None
                 }
             }
         }
@@ -292,7 +313,7 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
 ///
 /// This derive macro generates implementations for:
 /// - `Event::encode_log()` - Encodes the event into log format with topics and data
-/// - Event signature computation and topic generation
+/// - `SIGNATURE` and `SIGNATURE_HASH` constants, computed during macro expansion
 /// - Support for indexed fields (up to 3 indexed fields allowed)
 ///
 /// Events follow the Ethereum event ABI specification, where the first topic (topic0)
@@ -302,6 +323,7 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
 /// # Attributes
 ///
 /// - `#[indexed]` - Marks a field as indexed (becomes a topic rather than log data)
+/// - `#[event(anonymous)]` - Omits topic0, allowing a fourth indexed field
 ///
 /// # Examples
 ///
@@ -338,6 +360,21 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
 /// are converted to their Solidity ABI type names:
 /// - `Transfer(Address, Address, U256)` → `"Transfer(address,address,uint256)"`
 ///
+/// # Indexed Fields
+///
+/// Value types (`Address`, `U256`, `bool`, `B32`, ...) are stored in their topic as is.
+/// Following Solidity, dynamic types (`String`, `Bytes`, `Vec<T>`) and arrays do not fit
+/// in a topic, so their topic is the Keccak-256 hash of their encoding:
+///
+/// ```rust,ignore
+/// #[derive(Event)]
+/// pub struct Registered {
+///     #[indexed]
+///     pub name: String,     // topic1 = keccak256(name)
+///     pub owner: Address,
+/// }
+/// ```
+///
 /// # Usage with `emit!` Macro
 ///
 /// Events are typically emitted using the generated `emit!` macro:
@@ -348,10 +385,11 @@ pub fn error_derive(input: TokenStream) -> TokenStream {
 ///
 /// # Limitations
 ///
-/// - Maximum of 3 indexed fields (Ethereum limitation)
+/// - Maximum of 3 indexed fields, or 4 for anonymous events (Ethereum limitation);
+///   more is a compile error
 /// - Only named struct fields supported (not tuple structs)
 /// - Field types must implement Solidity ABI encoding
-#[proc_macro_derive(Event, attributes(indexed))]
+#[proc_macro_derive(Event, attributes(event, indexed))]
 pub fn event_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
@@ -366,14 +404,63 @@ pub fn event_derive(input: TokenStream) -> TokenStream {
         panic!("Event must be a struct");
     };
 
+    let anonymous = match helpers::is_anonymous_event(&input.attrs) {
+        Ok(anonymous) => anonymous,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
     // Collect iterators into vectors
     let field_names: Vec<_> = fields.iter().map(|f| &f.ident).collect();
     let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
-    let indexed_fields: Vec<_> = fields
+    let (indexed, non_indexed): (Vec<_>, Vec<_>) = fields
         .iter()
-        .filter(|f| f.attrs.iter().any(|attr| attr.path.is_ident("indexed")))
-        .map(|f| &f.ident)
-        .collect();
+        .partition(|f| f.attrs.iter().any(|attr| attr.path.is_ident("indexed")));
+    let indexed_fields: Vec<_> = indexed.iter().map(|f| &f.ident).collect();
+    let indexed_types: Vec<_> = indexed.iter().map(|f| &f.ty).collect();
+    let data_fields: Vec<_> = non_indexed.iter().map(|f| &f.ident).collect();
+
+    // The EVM caps logs at 4 topics, one of which holds the signature unless anonymous
+    let max_indexed = if anonymous { 4 } else { 3 };
+    if indexed.len() > max_indexed {
+        return syn::Error::new_spanned(
+            &indexed[max_indexed].ident,
+            format!(
+                "{} events can have at most {} indexed fields",
+                if anonymous {
+                    "anonymous"
+                } else {
+                    "non-anonymous"
+                },
+                max_indexed
+            ),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // The signature and its hash are fixed, so compute them once during expansion
+    let type_names = match field_types
+        .iter()
+        .map(|ty| {
+            helpers::rust_type_to_sol_type(ty)
+                .map(|ty| ty.sol_type_name().into_owned())
+                .map_err(|e| syn::Error::new_spanned(ty, e))
+        })
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(type_names) => type_names,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let signature = format!("{}({})", name, type_names.join(","));
+    let signature_hash = alloy_core::primitives::keccak256(signature.as_bytes()).0;
+
+    let topic0 = (!anonymous).then(|| quote! { Self::SIGNATURE_HASH, });
+
+    let data = if data_fields.is_empty() {
+        quote! { Vec::new() }
+    } else {
+        quote! { (#(&self.#data_fields,)*).abi_encode_params() }
+    };
 
     let expanded = quote! {
         impl #name {
@@ -382,6 +469,11 @@ pub fn event_derive(input: TokenStream) -> TokenStream {
                 #(stringify!(#indexed_fields)),*
             ];
 
+            /// The event signature, e.g. `Transfer(address,address,uint256)`
+            pub const SIGNATURE: &'static str = #signature;
+            /// The Keccak-256 hash of the signature, topic0 of non-anonymous events
+            pub const SIGNATURE_HASH: [u8; 32] = [#(#signature_hash),*];
+
             pub fn new(#(#field_names: #field_types),*) -> Self {
                 Self {
                     #(#field_names),*
@@ -391,37 +483,23 @@ pub fn event_derive(input: TokenStream) -> TokenStream {
 
         impl hybrid_contract::log::Event for #name {
             fn encode_log(&self) -> (alloc::vec::Vec<u8>, alloc::vec::Vec<[u8; 32]>) {
-                use alloy_sol_types::SolValue;
-                use alloy_core::primitives::{keccak256, B256};
+                use alloy_sol_types::{EventTopic, SolValue};
                 use alloc::vec::Vec;
 
-                let mut signature = Vec::new();
-                signature.extend_from_slice(Self::NAME.as_bytes());
-                signature.extend_from_slice(b"(");
-
-                let mut first = true;
-                let mut topics = alloc::vec![B256::default()];
-                let mut data = Vec::new();
-
-                #(
-                    if !first { signature.extend_from_slice(b","); }
-                    first = false;
-
-                    signature.extend_from_slice(self.#field_names.sol_type_name().as_bytes());
-                    let encoded = self.#field_names.abi_encode();
-
-                    let field_name = stringify!(#field_names);
-                    if Self::INDEXED_FIELDS.contains(&field_name) && topics.len() < 4 {
-                        topics.push(B256::from_slice(&encoded));
-                    } else {
-                        data.extend_from_slice(&encoded);
-                    }
-                )*
-
-                signature.extend_from_slice(b")");
-                topics[0] = B256::from(keccak256(&signature));
-
-                (data, topics.iter().map(|t| t.0).collect())
+                // Value types are topics as is, dynamic types and arrays are hashed
+                let topics = alloc::vec![
+                    #topic0
+                    #(
+                        <<#indexed_types as SolValue>::SolType as EventTopic>::encode_topic(
+                            &self.#indexed_fields,
+                        )
+                        .0
+                        .0,
+                    )*
+                ];
+                let data = #data;
+
+                (data, topics)
             }
         }
     };
@@ -455,8 +533,10 @@ pub fn show_streams(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// This attribute performs several critical transformations:
 /// 1. **Function Selector Generation**: Computes 4-byte selectors for all public methods
-/// 2. **Dispatch Logic**: Creates a routing mechanism to call methods based on selectors
-/// 3. **ABI Handling**: Automatically encodes/decodes function parameters and return values
+/// 2. **Dispatch Logic**: Creates a routing mechanism to call methods based on selectors,
+///    a binary search of the sorted selectors for contracts with many methods
+/// 3. **ABI Handling**: Automatically encodes/decodes function parameters and return values,
+///    reading arguments that are each one word long in place
 /// 4. **Interface Generation**: Creates type-safe interfaces for external contract calls
 /// 5. **Deployment Code**: Generates initialization code for contract deployment
 ///
@@ -525,6 +605,8 @@ pub fn show_streams(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// ## Public Methods
 /// - Must have `pub` visibility to be callable externally
 /// - Parameters must be ABI-encodable types
+/// - `&self` methods are `view`: interfaces call them with STATICCALL, and the VM reverts
+///   storage writes, logs, creations and calls with value made during a static call
 /// - Return types can be:
 ///   - Direct types (wrapped in `Option` automatically)
 ///   - `Option<T>` for explicit success/failure handling
@@ -545,6 +627,10 @@ pub fn show_streams(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// pub fn transfer(&mut self, to: Address, amount: U256) -> Result<(), TransferError> { ... }
 /// ```
 ///
+/// Arguments and return values may also be `#[derive(SolStruct)]` structs and
+/// `#[derive(SolEnum)]` enums, encoded as tuples and `uint8` as in Solidity. Arguments are
+/// decoded as function parameters, so calldata built by Solidity or `alloy` is accepted as is.
+///
 /// # Payment Handling
 ///
 /// Methods can receive payments by using the `#[payable]` attribute:
@@ -560,6 +646,49 @@ pub fn show_streams(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// pub fn withdraw(&mut self) { ... }
 /// ```
 ///
+/// # Fallback and Receive
+///
+/// With Solidity semantics, calls with empty calldata go to the `#[receive]` method and
+/// calls matching no selector go to the `#[fallback]` method, and revert when the contract
+/// has none:
+///
+/// ```rust,ignore
+/// #[receive]
+/// fn receive(&mut self) {
+///     // Accept plain transfers
+/// }
+///
+/// #[fallback]
+/// fn fallback(&mut self, input: &[u8]) {
+///     // Handle unknown calls
+/// }
+/// ```
+///
+/// # Guards
+///
+/// `#[guard(...)]` checks preconditions before a method runs and `#[nonreentrant]` rejects
+/// reentrant calls:
+///
+/// ```rust,ignore
+/// #[guard(only_owner)]
+/// #[nonreentrant]
+/// pub fn sweep(&mut self, to: Address) { ... }
+/// ```
+///
+/// # Selectors
+///
+/// Selectors are hashed from the Rust method name, as in Solidity. `#[selector(...)]` hashes
+/// the signature under another name, or sets the selector itself, and is reflected in the
+/// contract's interface. Two methods with the same selector are a compile error:
+///
+/// ```rust,ignore
+/// #[selector(name = "transfer")]
+/// pub fn transfer_with_memo(&mut self, to: Address, amount: U256, memo: String) { ... }
+///
+/// #[selector(0x23b872dd)]
+/// pub fn move_from(&mut self, from: Address, to: Address, amount: U256) { ... }
+/// ```
+///
 /// # Build Features
 ///
 /// The macro generates different code based on Cargo features:
@@ -567,6 +696,31 @@ pub fn show_streams(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// - **`interface-only`**: Only generates interface definitions
 /// - **`deploy`**: Only generates deployment/initialization code
 ///
+/// # Unit Testing
+///
+/// Under `cfg(test)` the RISC-V entry point is omitted and the constructor is kept on the
+/// contract, so with `hybrid-contract`'s `test-env` feature its methods can be called
+/// directly from host tests:
+///
+/// ```rust,ignore
+/// #![cfg_attr(not(test), no_std, no_main)]
+///
+/// #[cfg(test)]
+/// mod tests {
+///     use super::*;
+///     use hybrid_contract::mock;
+///
+///     #[test]
+///     fn test_deposit() {
+///         mock::set_caller(alice);
+///         mock::set_value(U256::from(10));
+///         let mut vault = Vault::new(alice);
+///         vault.deposit();
+///         assert_eq!(vault.balance_of(alice), U256::from(10));
+///     }
+/// }
+/// ```
+///
 /// # ABI Compatibility
 ///
 /// Generated contracts are fully ABI-compatible with Solidity contracts:
@@ -584,31 +738,120 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
     };
 
     let mut constructor = None;
+    let mut fallback = None;
+    let mut receive = None;
     let mut public_methods: Vec<&ImplItemMethod> = Vec::new();
+    let mut private_methods: Vec<&ImplItemMethod> = Vec::new();
 
-    // Iterate over the items in the impl block to find pub methods + constructor
+    // Iterate over the items in the impl block to find pub methods + constructor,
+    // fallback and receive. Private methods (such as guards) are kept but not dispatched
     for item in input.items.iter() {
         if let ImplItem::Method(method) = item {
-            if method.sig.ident == "new" {
+            if has_attribute(method, "fallback") {
+                if fallback.replace(method).is_some() {
+                    return syn::Error::new_spanned(
+                        &method.sig.ident,
+                        "a contract can only have one `#[fallback]` method",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+            } else if has_attribute(method, "receive") {
+                if receive.replace(method).is_some() {
+                    return syn::Error::new_spanned(
+                        &method.sig.ident,
+                        "a contract can only have one `#[receive]` method",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+            } else if method.sig.ident == "new" {
                 constructor = Some(method);
             } else if let syn::Visibility::Public(_) = method.vis {
                 public_methods.push(method);
+            } else {
+                private_methods.push(method);
             }
         }
     }
 
+    // Only dispatched methods have a selector to override
+    let undispatched = constructor.into_iter().chain(fallback).chain(receive);
+    for method in undispatched.chain(private_methods.iter().copied()) {
+        if let Some(attr) = method
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("selector"))
+        {
+            return syn::Error::new_spanned(attr, "`#[selector]` only applies to `pub` methods")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    // A malformed `#[selector]` is reported on the method carrying it
+    let method_infos: Vec<MethodInfo> = match public_methods
+        .iter()
+        .map(|&method| MethodInfo::try_from(method))
+        .collect()
+    {
+        Ok(infos) => infos,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    // Colliding selectors would make one method unreachable
+    let selector_checks = match helpers::check_selector_collisions(&public_methods) {
+        Ok(checks) => checks,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    // Calls that match no selector go to the fallback, plain transfers to receive
+    let fallback_dispatch = match fallback {
+        Some(method) => match helpers::generate_fallback_call(method, is_payable(method)) {
+            Ok(call) => call,
+            Err(e) => return e.to_compile_error().into(),
+        },
+        None => quote! { panic!("unknown method") },
+    };
+    let receive_dispatch = match receive {
+        Some(method) => match helpers::generate_receive_call(method) {
+            Ok(call) => quote! {
+                if calldata.is_empty() {
+                    #call
+                }
+            },
+            Err(e) => return e.to_compile_error().into(),
+        },
+        None => quote! {},
+    };
+    let fallback_input = fallback.map(|_| quote! { let input = calldata; });
+    let special_methods: Vec<_> = fallback.into_iter().chain(receive).collect();
+
     let input_methods: Vec<_> = public_methods
         .iter()
         .map(|method| quote! { #method })
         .collect();
-    let match_arms: Vec<_> = public_methods.iter().map(|method| {
+    // Large contracts find the called method by binary search over a sorted selector table
+    let use_dispatch_table = public_methods.len() >= DISPATCH_TABLE_MIN_METHODS;
+    let match_arms: Vec<_> = public_methods.iter().enumerate().map(|(index, method)| {
         let method_name = &method.sig.ident;
-        let method_info = MethodInfo::from(*method);
-        let method_selector = u32::from_be_bytes(
-            helpers::generate_fn_selector(&method_info, None)
-                .expect("Unable to generate fn selector")
-        );
-        let (arg_names, arg_types) = helpers::get_arg_props_skip_first(&method_info);
+        let method_info = method_infos[index].clone();
+        // Selectors of methods taking `SolStruct`s are only known once the types are resolved
+        let method_selector = match helpers::generate_fn_selector(&method_info, None) {
+            _ if use_dispatch_table => {
+                let index = index as u16;
+                quote! { Some(#index) }
+            }
+            Some(selector) => {
+                let selector = u32::from_be_bytes(selector);
+                quote! { #selector }
+            }
+            None => {
+                let selector = helpers::generate_selector_expr(&method_info, None);
+                quote! { selector if selector == #selector }
+            }
+        };
+        let args_decode = helpers::generate_args_decode(&method_info);
+        let (arg_names, _) = helpers::get_arg_props_skip_first(&method_info);
 
         // Check if there are payable methods
         let checks = if !is_payable(&method) {
@@ -621,16 +864,40 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
             quote! {}
         };
 
+        // Guards run before the call, the reentrancy lock is held for its duration
+        let guards = match helpers::generate_guards(method) {
+            Ok(guards) => guards,
+            Err(e) => return e.to_compile_error(),
+        };
+        let (lock, unlock) = match (has_attribute(method, "nonreentrant"), method_info.is_mutable()) {
+            (false, _) => (quote! {}, quote! {}),
+            (true, true) => (
+                quote! { hybrid_contract::guard::nonreentrant_enter(); },
+                quote! { hybrid_contract::guard::nonreentrant_exit(); },
+            ),
+            // View methods cannot take the lock, but must not observe a call in progress
+            (true, false) => (quote! { hybrid_contract::guard::nonreentrant_check(); }, quote! {}),
+        };
+        let call = quote! {
+            {
+                #guards
+                #lock
+                let res = self.#method_name(#( #arg_names ),*);
+                #unlock
+                res
+            }
+        };
+
         // Check if the method has a return type
         let return_handling = match &method.sig.output {
             ReturnType::Default => {
                 // No return value
-                quote! { self.#method_name(#( #arg_names ),*); }
+                quote! { #call; }
             }
            ReturnType::Type(_,_) => {
                 match helpers::extract_wrapper_types(&method.sig.output) {
                     helpers::WrapperType::Result(_,_) => quote! {
-                        let res = self.#method_name(#( #arg_names ),*);
+                        let res = #call;
                         match res {
                             Ok(success) => {
                                 let result_bytes = success.abi_encode();
@@ -643,8 +910,16 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
                             }
                         }
                     },
+                    // Failed calls to other contracts bubble up, as in Solidity
+                    helpers::WrapperType::CallResult(_,_) => quote! {
+                        let result = #call.or_revert();
+                        let result_bytes = result.abi_encode();
+                        let result_size = result_bytes.len() as u64;
+                        let result_ptr = result_bytes.as_ptr() as u64;
+                        hybrid_contract::return_riscv(result_ptr, result_size);
+                    },
                     helpers::WrapperType::Option(_) => quote! {
-                        match self.#method_name(#( #arg_names ),*) {
+                        match #call {
                             Some(success) => {
                                 let result_bytes = success.abi_encode();
                                 let result_size = result_bytes.len() as u64;
@@ -655,7 +930,7 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
                         }
                     },
                     helpers::WrapperType::None => quote! {
-                        let result = self.#method_name(#( #arg_names ),*);
+                        let result = #call;
                         let result_bytes = result.abi_encode();
                         let result_size = result_bytes.len() as u64;
                         let result_ptr = result_bytes.as_ptr() as u64;
@@ -667,60 +942,39 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
         quote! {
             #method_selector => {
-                let (#( #arg_names ),*) = <(#( #arg_types ),*)>::abi_decode(calldata, true).expect("abi decode failed");
+                #args_decode
                 #checks
                 #return_handling
             }
         }
     }).collect();
 
-    let emit_helper = quote! {
-        #[macro_export]
-        macro_rules! get_type_signature {
-            ($arg:expr) => {
-                $arg.sol_type_name().as_bytes()
-            };
+    let dispatch = if use_dispatch_table {
+        let selectors = method_infos
+            .iter()
+            .map(|info| helpers::generate_selector_expr(info, None));
+        let len = public_methods.len();
+        quote! {
+            const DISPATCH: [(u32, u16); #len] = hybrid_contract::abi::dispatch_table([#( #selectors ),*]);
+            match hybrid_contract::abi::dispatch_index(&DISPATCH, selector) {
+                #( #match_arms )*
+                _ => #fallback_dispatch,
+            }
         }
+    } else {
+        quote! {
+            match selector {
+                #( #match_arms )*
+                _ => #fallback_dispatch,
+            }
+        }
+    };
 
+    let emit_helper = quote! {
         #[macro_export]
         macro_rules! emit {
             ($event:ident, $($field:expr),*) => {{
-                use alloy_sol_types::SolValue;
-                use alloy_core::primitives::{keccak256, B256, U256, I256};
-                use alloc::vec::Vec;
-
-                let mut signature = alloc::vec![];
-                signature.extend_from_slice($event::NAME.as_bytes());
-                signature.extend_from_slice(b"(");
-
-                let mut first = true;
-                let mut topics = alloc::vec![B256::default()];
-                let mut data = Vec::new();
-
-                $(
-                    if !first { signature.extend_from_slice(b","); }
-                    first = false;
-
-                    signature.extend_from_slice(get_type_signature!($field));
-                    let encoded = $field.abi_encode();
-
-                    let field_ident = stringify!($field);
-                    if $event::INDEXED_FIELDS.contains(&field_ident) && topics.len() < 4 {
-                        topics.push(B256::from_slice(&encoded));
-                    } else {
-                        data.extend_from_slice(&encoded);
-                    }
-                )*
-
-                signature.extend_from_slice(b")");
-                topics[0] = B256::from(keccak256(&signature));
-
-                if !data.is_empty() {
-                    hybrid_contract::emit_log(&data, &topics);
-                } else if topics.len() > 1 {
-                    let data = topics.pop().unwrap();
-                    hybrid_contract::emit_log(data.as_ref(), &topics);
-                }
+                hybrid_contract::log::emit($event::new($($field),*));
             }};
         }
     };
@@ -732,6 +986,14 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Generate initcode for deployments
     let deployment_code = helpers::generate_deployment_code(struct_name, constructor);
 
+    // Host unit tests deploy by calling the constructor directly
+    let test_constructor = constructor.map(|method| {
+        quote! {
+            #[cfg(test)]
+            impl #struct_name { #method }
+        }
+    });
+
     // Generate the complete output with module structure
     let output = quote! {
         use hybrid_contract::*;
@@ -767,26 +1029,34 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
             use hybrid_contract::*;
 
             #emit_helper
+            #selector_checks
 
-            impl #struct_name { #(#input_methods)* }
+            impl #struct_name { #(#input_methods)* #(#private_methods)* #(#special_methods)* }
+            #test_constructor
             impl Contract for #struct_name {
                 fn call(&mut self) {
                     self.call_with_data(&msg_data());
                 }
 
                 fn call_with_data(&mut self, calldata: &[u8]) {
+                    #receive_dispatch
+
+                    #fallback_input
+                    if calldata.len() < 4 {
+                        #fallback_dispatch
+                    }
+
                     let selector = u32::from_be_bytes([calldata[0], calldata[1], calldata[2], calldata[3]]);
                     let calldata = &calldata[4..];
 
-                    match selector {
-                        #( #match_arms )*
-                        _ => panic!("unknown method"),
-                    }
+                    #dispatch
 
                     return_riscv(0, 0);
                 }
             }
 
+            // Host unit tests provide their own entry point
+            #[cfg(not(test))]
             #[hybrid_contract::entry]
             fn main() -> ! {
                 let mut contract = #struct_name::default();
@@ -866,6 +1136,174 @@ pub fn payable(_attr: TokenStream, item: TokenStream) -> TokenStream {
     item
 }
 
+/// Attribute to mark the contract method run when no other method matches the calldata.
+///
+/// The fallback is called when the calldata's selector matches no public method, or when
+/// the calldata is shorter than a selector (including empty calldata, unless the contract
+/// has a `#[receive]` method). This lets contracts act as proxies or routers.
+///
+/// # Usage
+///
+/// ```rust,ignore
+/// #[contract]
+/// impl Proxy {
+///     #[fallback]
+///     #[payable]
+///     fn forward(&mut self, input: &[u8]) -> Bytes {
+///         // Forward the call to the implementation
+///     }
+/// }
+/// ```
+///
+/// # Behavior
+///
+/// - Takes no arguments, or the full calldata (selector included) as `&[u8]`
+/// - May return raw bytes (any `AsRef<[u8]>`), returned as is rather than ABI-encoded
+/// - Rejects value unless also marked `#[payable]`
+/// - Is not part of the contract's interface, and at most one method can be marked
+#[proc_macro_attribute]
+pub fn fallback(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+
+/// Attribute to mark the contract method run for calls with empty calldata, such as plain
+/// native token transfers.
+///
+/// # Usage
+///
+/// ```rust,ignore
+/// #[contract]
+/// impl Vault {
+///     #[receive]
+///     fn receive(&mut self) {
+///         let deposit = hybrid_contract::msg_value();
+///         // Credit the sender
+///     }
+/// }
+/// ```
+///
+/// # Behavior
+///
+/// - Always accepts value, like Solidity's `receive() external payable`
+/// - Takes no arguments and returns nothing
+/// - Without a receive method, empty calldata goes to the `#[fallback]` method, and the
+///   call reverts if there is none
+/// - Is not part of the contract's interface, and at most one method can be marked
+#[proc_macro_attribute]
+pub fn receive(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+
+/// Attribute to check one or more preconditions before a contract method runs, like a
+/// Solidity modifier.
+///
+/// Each guard is a method of the contract returning a `hybrid_contract::Guard`: a `bool`,
+/// reverting without data when `false`, or a `Result<(), E>` with a `#[derive(Error)]` type,
+/// reverting with the encoded error when `Err`. Guards may be written as a call taking the
+/// guarded method's arguments.
+///
+/// # Usage
+///
+/// ```rust,ignore
+/// #[contract]
+/// impl Registry {
+///     #[guard(only_owner)]
+///     pub fn set_fee(&mut self, fee: U256) -> Result<(), RegistryError> {
+///         self.fee.write(fee);
+///         Ok(())
+///     }
+///
+///     #[guard(not_paused, only_holder(id))]
+///     pub fn renew(&mut self, id: U256) { ... }
+///
+///     fn only_owner(&self) -> Result<(), RegistryError> {
+///         match msg_sender() == self.owner.read() {
+///             true => Ok(()),
+///             false => Err(RegistryError::OnlyOwner),
+///         }
+///     }
+///
+///     fn not_paused(&self) -> bool {
+///         !self.paused.read()
+///     }
+///
+///     fn only_holder(&self, id: U256) -> bool {
+///         self.holders[id].read() == msg_sender()
+///     }
+/// }
+/// ```
+///
+/// # Behavior
+///
+/// - Guards run in order, after the arguments are decoded and before the method is called
+/// - Guards are part of the method's dispatch: calling the method from Rust skips them
+#[proc_macro_attribute]
+pub fn guard(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+
+/// Attribute to reject reentrant calls to a contract method.
+///
+/// The method holds a lock in storage while it runs (see `hybrid_contract::guard`), so a call
+/// reentering any `#[nonreentrant]` method of the contract reverts with
+/// `ReentrancyGuardReentrantCall()` until it returns.
+///
+/// # Usage
+///
+/// ```rust,ignore
+/// #[contract]
+/// impl Vault {
+///     #[nonreentrant]
+///     pub fn withdraw(&mut self, amount: U256) {
+///         self.balances[msg_sender()] -= amount;
+///         transfer(msg_sender(), amount);
+///     }
+/// }
+/// ```
+///
+/// # Behavior
+///
+/// - The lock is taken after `#[guard]`s pass and released once the method returns
+/// - `&self` methods only check the lock, as they may be run by STATICCALL
+#[proc_macro_attribute]
+pub fn nonreentrant(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+
+/// Attribute to override the selector a contract or interface method is dispatched on.
+///
+/// Selectors are otherwise hashed from the method's Rust name, so Solidity overloads, which
+/// share a name, cannot be written as distinct Rust methods without it.
+///
+/// # Usage
+///
+/// ```rust,ignore
+/// #[contract]
+/// impl Token {
+///     // transfer(address,uint256)
+///     pub fn transfer(&mut self, to: Address, amount: U256) -> bool { ... }
+///
+///     // transfer(address,uint256,bytes)
+///     #[selector(name = "transfer")]
+///     pub fn transfer_with_data(&mut self, to: Address, amount: U256, data: Bytes) -> bool { ... }
+///
+///     // transferFrom(address,address,uint256)
+///     #[selector(0x23b872dd)]
+///     pub fn transfer_from(&mut self, from: Address, to: Address, amount: U256) -> bool { ... }
+/// }
+/// ```
+///
+/// # Behavior
+///
+/// - `name = "..."` replaces the method name in the signature, the argument types are kept
+/// - A raw selector is used as is; the method is left out of the contract's JSON ABI, which
+///   describes functions by signature
+/// - Methods sharing a selector are rejected at compile time, naming both methods
+#[proc_macro_attribute]
+pub fn selector(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    item
+}
+
 /// Checks if a method is marked with the `#[payable]` attribute.
 ///
 /// This helper function examines the attributes of a method to determine if it
@@ -879,10 +1317,15 @@ pub fn payable(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// `true` if the method has the `#[payable]` attribute, `false` otherwise.
 fn is_payable(method: &syn::ImplItemMethod) -> bool {
+    has_attribute(method, "payable")
+}
+
+/// Checks if a method is marked with the marker attribute `#[name]`.
+fn has_attribute(method: &syn::ImplItemMethod, name: &str) -> bool {
     method.attrs.iter().any(|attr| {
         if let Ok(syn::Meta::Path(path)) = attr.parse_meta() {
             if let Some(segment) = path.segments.first() {
-                return segment.ident == "payable";
+                return segment.ident == name;
             }
         }
         false
@@ -937,6 +1380,19 @@ fn is_payable(method: &syn::ImplItemMethod) -> bool {
 /// Supported styles:
 /// - `"camelCase"` - Converts snake_case to camelCase for Solidity compatibility
 ///
+/// Methods can also take a `#[selector(...)]`, which is how overloaded Solidity functions
+/// are called from Rust:
+///
+/// ```rust,ignore
+/// #[interface]
+/// trait IERC721 {
+///     #[selector(name = "safeTransferFrom")]
+///     fn safe_transfer_from(&mut self, from: Address, to: Address, id: U256);
+///     #[selector(name = "safeTransferFrom")]
+///     fn safe_transfer_from_with_data(&mut self, from: Address, to: Address, id: U256, data: Bytes);
+/// }
+/// ```
+///
 /// # Generated Interface Structure
 ///
 /// The macro generates:
@@ -975,6 +1431,28 @@ fn is_payable(method: &syn::ImplItemMethod) -> bool {
 ///     .ok_or(TokenError::TransferFailed)?;
 /// ```
 ///
+/// Methods declared to return `CallResult<T, E>`, with `E` a `#[derive(Error)]` type of the
+/// callee, tell a revert apart from the return data and decode it, like Solidity's
+/// `try`/`catch`:
+///
+/// ```rust,ignore
+/// #[interface]
+/// trait IVault {
+///     fn withdraw(&mut self, amount: U256) -> CallResult<U256, VaultError>;
+/// }
+///
+/// match vault.withdraw(amount) {
+///     CallResult::Ok(withdrawn) => { /* the call succeeded */ }
+///     CallResult::Revert(VaultError::Paused) => { /* handle a custom error */ }
+///     CallResult::Error(reason) => { /* `Error(string)`, a failed `require` */ }
+///     CallResult::Panic(code) => { /* `Panic(uint256)` */ }
+///     CallResult::Failed(data) => { /* any other failure */ }
+/// }
+/// ```
+///
+/// `vault.withdraw(amount).or_revert()` reverts with the error of the callee instead, which is
+/// also what a `#[contract]` method returning a `CallResult` does.
+///
 /// # ABI Compatibility
 ///
 /// Generated interfaces are fully compatible with:
@@ -1039,13 +1517,32 @@ pub fn interface(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// # Storage Slot Allocation
 ///
-/// Each field is assigned a sequential storage slot:
-/// - Field 0 → Storage slot 0
-/// - Field 1 → Storage slot 1
-/// - And so on...
+/// Fields are laid out in declaration order starting from slot 0, each one starting where
+/// the previous one ends:
+/// - Most fields (`Slot<T>`, `Mapping<K, V>`, `StorageVec<T>`, `StorageString`) occupy one slot
+/// - `Slot<S>` with `S: StorageStruct` occupies as many slots as the struct's packed fields
+///
+/// The number of slots of each field is read from `StorageLayout::SLOTS` at compile time.
+///
+/// # Namespaced Storage
 ///
-/// The allocation uses a simple strategy suitable for basic types. For complex nested
-/// structures, the allocation may need manual optimization in future versions.
+/// `#[storage(namespace = "example.main")]` roots the fields at the [ERC-7201] location of the
+/// namespace instead of slot 0, so storage structs sharing a contract's storage (e.g. a proxy
+/// and its implementation behind `delegatecall`) cannot collide:
+///
+/// ```rust,ignore
+/// #[storage(namespace = "example.main")]
+/// pub struct Main {
+///     pub owner: Slot<Address>,                // root + 0
+///     pub balances: Mapping<Address, Slot<U256>>, // root + 1
+/// }
+/// ```
+///
+/// The root is `keccak256(abi.encode(uint256(keccak256("example.main")) - 1)) & ~0xff`, the
+/// location Solidity contracts annotated with `@custom:storage-location erc7201:example.main`
+/// use, so the layout stays compatible with them.
+///
+/// [ERC-7201]: https://eips.ethereum.org/EIPS/eip-7201
 ///
 /// # Supported Storage Types
 ///
@@ -1098,6 +1595,27 @@ pub fn interface(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// - Storage mappings are properly initialized
 /// - No storage conflicts occur between fields
 ///
+/// # Immutables
+///
+/// Fields marked `#[immutable]` take no storage slot. The constructor sets them like any
+/// other field, and the deployment code appends them to the runtime code, where every call
+/// reads them back without an SLOAD, like Solidity's `immutable` variables:
+///
+/// ```rust,ignore
+/// #[storage]
+/// pub struct Token {
+///     balance_of: Mapping<Address, Slot<U256>>, // Slot 0
+///     #[immutable]
+///     owner: Address,
+///     #[immutable]
+///     decimals: u64,
+/// }
+/// ```
+///
+/// Immutables must be one-word types (`Address`, `bool`, integers and fixed bytes), and are
+/// only set by the constructor: changes made by other methods are not persisted. In unit
+/// tests they start zeroed, like the deployment's.
+///
 /// # Unit Structs
 ///
 /// For contracts without storage fields, you can use an empty struct:
@@ -1114,14 +1632,11 @@ pub fn interface(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// # Future Enhancements
 ///
-/// The current implementation uses a naive storage allocation strategy.
 /// Future versions may include:
-/// - Storage layout optimization
-/// - Custom storage slot assignment
-/// - Support for storage structs and complex nested types
 /// - Storage migration utilities
 #[proc_macro_attribute]
-pub fn storage(_attr: TokenStream, input: TokenStream) -> TokenStream {
+pub fn storage(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as StorageArgs);
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
     let vis = &input.vis;
@@ -1132,7 +1647,12 @@ pub fn storage(_attr: TokenStream, input: TokenStream) -> TokenStream {
             _ => {
                 let output = quote! {
                     #vis struct #name;
-                    impl #name { pub fn new() -> Self { Self {} } }
+                    impl #name {
+                        pub fn new() -> Self { Self {} }
+
+                        #[doc(hidden)]
+                        pub fn __immutables(&self) -> alloc::vec::Vec<u8> { alloc::vec::Vec::new() }
+                    }
                 };
                 return TokenStream::from(output);
             }
@@ -1147,24 +1667,515 @@ pub fn storage(_attr: TokenStream, input: TokenStream) -> TokenStream {
         quote! { pub #name: #ty }
     });
 
-    // Generate initialization code for each field
-    // TODO: PoC uses a naive strategy. Enhance to support complex types like tuples or custom structs.
-    let init_fields = fields.iter().enumerate().map(|(i, f)| {
-        let name = &f.ident;
-        let slot = U256::from(i);
-        let [limb0, limb1, limb2, limb3] = slot.as_limbs();
-        quote! { #name: StorageLayout::allocate(#limb0, #limb1, #limb2, #limb3) }
+    // `#[immutable]` fields are embedded in the runtime code instead of taking storage slots
+    let mut immutables = Vec::new();
+    let mut slot_fields = Vec::new();
+    for field in fields {
+        match helpers::is_immutable(field) {
+            Ok(true) => immutables.push(field),
+            Ok(false) => slot_fields.push(field),
+            Err(err) => return err.to_compile_error().into(),
+        }
+    }
+
+    // Generate initialization code for each field, placing it after the slots of the previous ones
+    // and relative to the namespace root, if any
+    let root = args.namespace.as_deref().map(|namespace| {
+        alloy_core::primitives::U256::from_be_bytes(hybrid_abi::erc7201_root(namespace))
     });
+    let field_types: Vec<_> = slot_fields.iter().map(|f| &f.ty).collect();
+    let init_fields: Vec<_> = slot_fields.iter().enumerate().map(|(i, f)| {
+        let name = &f.ident;
+        let previous = &field_types[..i];
+        let allocate = match root {
+            Some(root) => {
+                let [limb0, limb1, limb2, limb3] = root.as_limbs();
+                quote! {
+                    let slot = alloy_core::primitives::U256::from_limbs([#limb0, #limb1, #limb2, #limb3])
+                        .wrapping_add(alloy_core::primitives::U256::from(SLOT));
+                    let [limb0, limb1, limb2, limb3] = *slot.as_limbs();
+                    StorageLayout::allocate(limb0, limb1, limb2, limb3)
+                }
+            }
+            None => quote! { StorageLayout::allocate(SLOT, 0, 0, 0) },
+        };
+        quote! {
+            #name: {
+                const SLOT: u64 = 0 #(+ <#previous as StorageLayout>::SLOTS as u64)*;
+                #allocate
+            }
+        }
+    }).collect();
+
+    let immutable_names: Vec<_> = immutables.iter().map(|f| &f.ident).collect();
+    let immutable_types: Vec<_> = immutables.iter().map(|f| &f.ty).collect();
+    let immutable_count = immutables.len();
+    let immutable_indices = 0..immutable_count;
+
+    // Calls read the immutables from the runtime code, while the constructor (and host unit
+    // tests) start from zeroed values and set them
+    let default = if immutables.is_empty() {
+        quote! {
+            pub fn default() -> Self {
+                Self { #(#init_fields,)* }
+            }
+        }
+    } else {
+        quote! {
+            #[cfg(not(any(feature = "deploy", test)))]
+            pub fn default() -> Self {
+                let immutables = hybrid_contract::immutable::load_immutables(#immutable_count);
+                Self {
+                    #(#init_fields,)*
+                    #(#immutable_names: hybrid_contract::abi::decode_word::<#immutable_types>(&immutables, #immutable_indices),)*
+                }
+            }
+
+            #[cfg(any(feature = "deploy", test))]
+            pub fn default() -> Self {
+                Self {
+                    #(#init_fields,)*
+                    #(#immutable_names: Default::default(),)*
+                }
+            }
+        }
+    };
+
+    let encode_immutables = if immutables.is_empty() {
+        quote! { alloc::vec::Vec::new() }
+    } else {
+        quote! {
+            let mut immutables = alloc::vec::Vec::with_capacity(#immutable_count * hybrid_contract::immutable::IMMUTABLE_SIZE);
+            #(immutables.extend_from_slice(&hybrid_contract::immutable::encode_immutable(&self.#immutable_names));)*
+            immutables
+        }
+    };
 
     let expanded = quote! {
         #vis struct #name { #(#struct_fields,)* }
 
         impl #name {
-            pub fn default() -> Self {
-                Self { #(#init_fields,)* }
+            #default
+
+            /// The immutables to embed after the runtime code, as set by the constructor.
+            #[doc(hidden)]
+            pub fn __immutables(&self) -> alloc::vec::Vec<u8> {
+                #encode_immutables
             }
         }
     };
 
     TokenStream::from(expanded)
 }
+
+/// Derives `StorageValue` and `StorageStruct` for structs stored with Solidity's storage layout.
+///
+/// The struct can then be held by a `Slot<T>`, on its own or as a `Mapping` value, and its
+/// fields read and written individually without loading the whole struct.
+///
+/// # Storage Layout
+///
+/// Fields are packed into consecutive slots following Solidity's rules: in declaration order,
+/// each field starts at the lower-order end of the space left in the current slot, moving to
+/// the next slot when it does not fit. An `Address`, a `u64` and a `bool` therefore share a
+/// single slot, exactly as the equivalent Solidity struct would.
+///
+/// Fields must be static value types encoded in a single ABI word (integers, `bool`,
+/// `Address`, fixed bytes); other types fail to compile.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use hybrid_derive::{storage, StorageStruct};
+///
+/// #[derive(StorageStruct)]
+/// pub struct Position {
+///     pub owner: Address, // slot 0, bytes 12..32
+///     pub expiry: u64,    // slot 0, bytes 4..12
+///     pub active: bool,   // slot 0, byte 3
+///     pub amount: U256,   // slot 1
+/// }
+///
+/// #[storage]
+/// pub struct Vault {
+///     pub config: Slot<Position>,                      // Slots 0 and 1
+///     pub positions: Mapping<Address, Slot<Position>>, // Slot 2
+/// }
+///
+/// #[contract]
+/// impl Vault {
+///     pub fn extend(&mut self, expiry: u64) {
+///         // Only reads and writes the slot holding `expiry`
+///         self.positions[msg_sender()].expiry().write(expiry);
+///     }
+///
+///     pub fn owner(&self) -> Address {
+///         self.config.read().owner
+///     }
+/// }
+/// ```
+///
+/// # Generated Components
+///
+/// - `StorageValue` and `StorageStruct` implementations reading and writing the whole struct
+/// - A `{Name}Fields` struct with one lazy accessor per field, returning a `StorageField<T>`
+///   (accessors of tuple structs are named `_0`, `_1`, ...)
+///
+/// # Limitations
+///
+/// - Generic structs are not supported
+/// - Nested structs, arrays, strings and bytes cannot be used as fields
+#[proc_macro_derive(StorageStruct)]
+pub fn storage_struct_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let vis = &input.vis;
+
+    if !input.generics.params.is_empty() {
+        panic!("`StorageStruct` does not support generic structs");
+    }
+    let fields = match &input.data {
+        Data::Struct(data) if !data.fields.is_empty() => &data.fields,
+        Data::Struct(_) => panic!("`StorageStruct` must have at least one field"),
+        _ => panic!("`StorageStruct` must be a struct"),
+    };
+
+    let field_count = fields.len();
+    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let field_vis: Vec<_> = fields.iter().map(|f| &f.vis).collect();
+    let indices: Vec<_> = (0..field_count).map(syn::Index::from).collect();
+    let members: Vec<syn::Member> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(syn::Index::from(i)),
+        })
+        .collect();
+    let accessors: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("_{}", i),
+        })
+        .collect();
+
+    let fields_name = format_ident!("{}Fields", name);
+    let fields_doc = format!(
+        "Lazy accessors for the fields of a [`{}`] in storage.",
+        name
+    );
+
+    let expanded = quote! {
+        impl #name {
+            #[doc(hidden)]
+            const __STORAGE_LAYOUT: ([hybrid_contract::hstd::PackedPosition; #field_count], usize) =
+                hybrid_contract::hstd::pack([
+                    #(hybrid_contract::hstd::packed_size::<#field_types>()),*
+                ]);
+        }
+
+        impl hybrid_contract::hstd::StorageValue for #name {
+            const SLOTS: usize = #name::__STORAGE_LAYOUT.1;
+
+            fn __read(key: alloy_core::primitives::U256) -> Self {
+                let layout = &#name::__STORAGE_LAYOUT.0;
+                let mut words = [[0u8; 32]; #name::__STORAGE_LAYOUT.1];
+                hybrid_contract::hstd::load_words(key, &mut words);
+
+                Self {
+                    #(#members: hybrid_contract::hstd::decode_field(&words, layout[#indices]),)*
+                }
+            }
+
+            fn __write(key: alloy_core::primitives::U256, value: Self) {
+                let layout = &#name::__STORAGE_LAYOUT.0;
+                let mut words = [[0u8; 32]; #name::__STORAGE_LAYOUT.1];
+                #(hybrid_contract::hstd::encode_field(&mut words, layout[#indices], &value.#members);)*
+
+                hybrid_contract::hstd::store_words(key, &words);
+            }
+        }
+
+        #[doc = #fields_doc]
+        #vis struct #fields_name {
+            key: alloy_core::primitives::U256,
+        }
+
+        impl #fields_name {
+            #(
+                #field_vis fn #accessors(&self) -> hybrid_contract::hstd::StorageField<#field_types> {
+                    hybrid_contract::hstd::StorageField::new(self.key, #name::__STORAGE_LAYOUT.0[#indices])
+                }
+            )*
+        }
+
+        impl hybrid_contract::hstd::StorageStruct for #name {
+            type Fields = #fields_name;
+
+            fn __fields(key: alloy_core::primitives::U256) -> Self::Fields {
+                #fields_name { key }
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Derives the ABI encoding of a Solidity struct, so the struct can be taken and returned by
+/// `#[contract]` methods and `#[interface]` bindings.
+///
+/// A struct is encoded as the tuple of its fields, in declaration order, and its Solidity
+/// name is that tuple's signature: the selector of `fn open(&mut self, position: Position)`
+/// below is `open((address,uint64,bool))`, as for the equivalent Solidity function. Since
+/// the macros expanding the contract cannot see the struct's fields, the selectors of
+/// methods using it are hashed at compile time by `hybrid_contract::abi::selector`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use hybrid_derive::{contract, SolEnum, SolStruct};
+///
+/// #[derive(SolEnum, Clone, Copy, PartialEq)]
+/// pub enum Side {
+///     Long,
+///     Short,
+/// }
+///
+/// #[derive(SolStruct)]
+/// pub struct Position {
+///     pub owner: Address,
+///     pub expiry: u64,
+///     pub side: Side,
+/// }
+///
+/// #[contract]
+/// impl Exchange {
+///     pub fn open(&mut self, position: Position) -> Result<U256, ExchangeError> {
+///         // ...
+///     }
+///
+///     pub fn positions(&self, owner: Address) -> Vec<Position> {
+///         // ...
+///     }
+/// }
+/// ```
+///
+/// # Generated Components
+///
+/// - `SolValue` and `SolType` implementations (from `alloy_sol_types`), with `SOL_NAME` set to
+///   the tuple signature
+///
+/// # Limitations
+///
+/// - Generic structs are not supported
+/// - Every field type must be its own `SolType::RustType`: primitives, `String`, `Bytes`,
+///   `Vec<T>`, arrays and other `SolStruct`/`SolEnum` types
+#[proc_macro_derive(SolStruct)]
+pub fn sol_struct_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return syn::Error::new_spanned(
+            &input.generics,
+            "`SolStruct` does not support generic structs",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let fields = match &input.data {
+        Data::Struct(data) if !data.fields.is_empty() => &data.fields,
+        Data::Struct(_) => {
+            return syn::Error::new_spanned(name, "`SolStruct` must have at least one field")
+                .to_compile_error()
+                .into()
+        }
+        _ => {
+            return syn::Error::new_spanned(name, "`SolStruct` must be a struct")
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let members: Vec<syn::Member> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(syn::Index::from(i)),
+        })
+        .collect();
+    let bindings: Vec<_> = (0..fields.len())
+        .map(|i| format_ident!("field_{}", i))
+        .collect();
+
+    let expanded = quote! {
+        const _: () = {
+            use alloy_sol_types::{private::SolTypeValue, SolType, SolValue};
+
+            type UnderlyingSolTuple = (#(<#field_types as SolValue>::SolType,)*);
+
+            impl SolValue for #name {
+                type SolType = Self;
+            }
+
+            impl SolType for #name {
+                type RustType = Self;
+                type Token<'a> = <UnderlyingSolTuple as SolType>::Token<'a>;
+
+                const SOL_NAME: &'static str = <UnderlyingSolTuple as SolType>::SOL_NAME;
+                const ENCODED_SIZE: Option<usize> = <UnderlyingSolTuple as SolType>::ENCODED_SIZE;
+                const PACKED_ENCODED_SIZE: Option<usize> =
+                    <UnderlyingSolTuple as SolType>::PACKED_ENCODED_SIZE;
+
+                fn valid_token(token: &Self::Token<'_>) -> bool {
+                    <UnderlyingSolTuple as SolType>::valid_token(token)
+                }
+
+                fn detokenize(token: Self::Token<'_>) -> Self::RustType {
+                    let (#(#bindings,)*) = <UnderlyingSolTuple as SolType>::detokenize(token);
+                    Self { #(#members: #bindings),* }
+                }
+            }
+
+            impl SolTypeValue<Self> for #name {
+                fn stv_to_tokens(&self) -> <Self as SolType>::Token<'_> {
+                    (#(<<#field_types as SolValue>::SolType as SolType>::tokenize(&self.#members),)*)
+                }
+
+                fn stv_abi_encoded_size(&self) -> usize {
+                    if let Some(size) = <Self as SolType>::ENCODED_SIZE {
+                        return size;
+                    }
+                    // Dynamic tuples are encoded behind an offset
+                    32 #(+ <<#field_types as SolValue>::SolType as SolType>::abi_encoded_size(&self.#members))*
+                }
+
+                fn stv_eip712_data_word(&self) -> alloy_sol_types::Word {
+                    let mut words = alloy_sol_types::private::Vec::new();
+                    #(
+                        words.extend_from_slice(
+                            <<#field_types as SolValue>::SolType as SolType>::eip712_data_word(&self.#members).as_slice()
+                        );
+                    )*
+                    alloy_sol_types::private::keccak256(words)
+                }
+
+                fn stv_abi_encode_packed_to(&self, out: &mut alloy_sol_types::private::Vec<u8>) {
+                    #(<<#field_types as SolValue>::SolType as SolType>::abi_encode_packed_to(&self.#members, out);)*
+                }
+
+                fn stv_abi_packed_encoded_size(&self) -> usize {
+                    0 #(+ <<#field_types as SolValue>::SolType as SolType>::abi_packed_encoded_size(&self.#members))*
+                }
+            }
+        };
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Derives the ABI encoding of a Solidity enum: a `uint8` holding the variant's index.
+///
+/// Only enums whose variants have no fields are supported, with at most 256 variants. As in
+/// Solidity, decoding an out of range value fails, which makes `#[contract]` dispatch revert.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use hybrid_derive::SolEnum;
+///
+/// #[derive(SolEnum, Clone, Copy, PartialEq)]
+/// pub enum Status {
+///     Pending,  // 0
+///     Active,   // 1
+///     Closed,   // 2
+/// }
+/// ```
+#[proc_macro_derive(SolEnum)]
+pub fn sol_enum_derive(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => {
+            return syn::Error::new_spanned(name, "`SolEnum` must be an enum")
+                .to_compile_error()
+                .into()
+        }
+    };
+    if variants.is_empty() || variants.len() > 256 {
+        return syn::Error::new_spanned(name, "`SolEnum` must have between 1 and 256 variants")
+            .to_compile_error()
+            .into();
+    }
+    if let Some(variant) = variants.iter().find(|v| !matches!(v.fields, Fields::Unit)) {
+        return syn::Error::new_spanned(variant, "`SolEnum` variants cannot have fields")
+            .to_compile_error()
+            .into();
+    }
+
+    let variant_names: Vec<_> = variants.iter().map(|v| &v.ident).collect();
+    let indices: Vec<_> = (0..variants.len()).map(|i| i as u8).collect();
+    let count = variants.len();
+
+    let expanded = quote! {
+        const _: () = {
+            use alloy_sol_types::{abi::token::WordToken, private::SolTypeValue, sol_data, SolType, SolValue};
+
+            impl #name {
+                #[doc(hidden)]
+                fn __index(&self) -> u8 {
+                    match self {
+                        #(#name::#variant_names => #indices,)*
+                    }
+                }
+            }
+
+            impl SolValue for #name {
+                type SolType = Self;
+            }
+
+            impl SolType for #name {
+                type RustType = Self;
+                type Token<'a> = WordToken;
+
+                const SOL_NAME: &'static str = "uint8";
+                const ENCODED_SIZE: Option<usize> = Some(32);
+                const PACKED_ENCODED_SIZE: Option<usize> = Some(1);
+
+                fn valid_token(token: &Self::Token<'_>) -> bool {
+                    <sol_data::Uint<8> as SolType>::valid_token(token)
+                        && (token.0 .0[31] as usize) < #count
+                }
+
+                fn detokenize(token: Self::Token<'_>) -> Self::RustType {
+                    match token.0 .0[31] {
+                        #(#indices => #name::#variant_names,)*
+                        _ => panic!("invalid enum value"),
+                    }
+                }
+            }
+
+            impl SolTypeValue<Self> for #name {
+                fn stv_to_tokens(&self) -> WordToken {
+                    <sol_data::Uint<8> as SolType>::tokenize(&self.__index())
+                }
+
+                fn stv_eip712_data_word(&self) -> alloy_sol_types::Word {
+                    SolTypeValue::<Self>::stv_to_tokens(self).0
+                }
+
+                fn stv_abi_encode_packed_to(&self, out: &mut alloy_sol_types::private::Vec<u8>) {
+                    out.push(self.__index());
+                }
+            }
+        };
+    };
+
+    TokenStream::from(expanded)
+}
@@ -3,6 +3,7 @@ pub mod constants;
 pub mod executor;
 pub mod factory;
 pub mod payload_builder;
+pub mod tracer;
 
 use constants::obtain_specs;
 use executor::HybridExecutorBuilder;
@@ -14,7 +15,10 @@ use reth::{
 use reth_ethereum::node::{node::EthereumAddOns, EthereumNode};
 use reth_tracing::{RethTracer, Tracer};
 
-use crate::payload_builder::HybridPayloadBuilder;
+use crate::{
+    payload_builder::HybridPayloadBuilder,
+    tracer::{HybridDebug, HybridDebugApiServer},
+};
 
 pub async fn run_node(is_dev: bool) -> Result<(), eyre::Error> {
     let _guard = RethTracer::new().init().map_err(|e| anyhow::anyhow!(e));
@@ -46,6 +50,13 @@ pub async fn run_node(is_dev: bool) -> Result<(), eyre::Error> {
                 )),
         )
         .with_add_ons(EthereumAddOns::default())
+        // serve the RISC-V aware `hybridTracer` from the debug tracing endpoints
+        .extend_rpc_modules(|ctx| {
+            let hybrid_debug =
+                HybridDebug::new(ctx.registry.eth_api().clone(), ctx.registry.debug_api());
+            ctx.modules.replace_configured(hybrid_debug.into_rpc())?;
+            Ok(())
+        })
         .launch()
         .await
         .unwrap();
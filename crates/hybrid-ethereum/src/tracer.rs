@@ -0,0 +1,645 @@
+//! RISC-V aware tracing exposed through `debug_traceTransaction` and `debug_traceCall`.
+//!
+//! The stock geth tracers only understand EVM opcodes, so a RISC-V frame shows up to them as an
+//! opaque call that burns gas. Selecting `{"tracer": "hybridTracer"}` instead returns one entry
+//! per call frame with the VM that executed it, the syscalls a RISC-V frame issued, how many
+//! instructions ran and the gas derived from them. Any other tracer is forwarded untouched to
+//! reth's debug API.
+//!
+//! With `{"tracerConfig": {"profile": true}}` RISC-V frames are also profiled, and report the
+//! instructions and gas spent per call stack of the contract's functions (folded stacks, as
+//! consumed by flamegraph tools).
+use alloy_primitives::{Address, B256};
+use alloy_rpc_types_eth::{BlockId, TransactionRequest};
+use alloy_rpc_types_trace::geth::{
+    GethDebugTracerType, GethDebugTracingCallOptions, GethDebugTracingOptions, GethTrace,
+};
+use hybrid_vm::trace::{self as riscv_trace, RiscvSegment};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::ErrorObjectOwned};
+use reth::{
+    revm::{
+        context::ContextTr,
+        interpreter::{
+            interpreter::EthInterpreter, CallInputs, CallOutcome, CreateInputs, CreateOutcome,
+            Interpreter,
+        },
+        Inspector,
+    },
+    rpc::{
+        api::{
+            eth::helpers::{Call, Trace},
+            DebugApiServer,
+        },
+        server_types::eth::{EthApiError, EvmOverrides},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Name that selects the hybrid tracer in the geth tracing options.
+pub const HYBRID_TRACER_NAME: &str = "hybridTracer";
+
+/// The virtual machine that executed a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VmKind {
+    Evm,
+    Riscv,
+}
+
+/// Options of the hybrid tracer, passed as `tracerConfig`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HybridTracerConfig {
+    /// Profile RISC-V frames per function.
+    #[serde(default)]
+    pub profile: bool,
+}
+
+/// Instructions and gas spent in a single call stack of a RISC-V frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackCost {
+    pub instructions: u64,
+    pub gas: u64,
+}
+
+impl StackCost {
+    pub fn add(&mut self, other: StackCost) {
+        self.instructions += other.instructions;
+        self.gas += other.gas;
+    }
+}
+
+/// Execution summary of a single call or create frame.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HybridFrameTrace {
+    /// Call depth, `0` being the transaction's top-level frame.
+    pub depth: usize,
+    /// Address of the executed contract (the created address for creates).
+    pub address: Address,
+    /// Whether the frame ran as EVM bytecode or as a RISC-V program.
+    pub kind: VmKind,
+    /// Gas forwarded to the frame.
+    pub gas_limit: u64,
+    /// Gas charged to the frame by revm, including gas forwarded to and spent by sub-calls.
+    pub gas_used: u64,
+    /// EVM opcodes or RISC-V instructions executed by this frame alone.
+    pub instructions: u64,
+    /// Gas derived from the RISC-V instruction mix. Always `0` for EVM frames.
+    pub instruction_gas: u64,
+    /// Syscalls issued by a RISC-V frame, in order.
+    pub syscalls: Vec<String>,
+    /// Whether the frame returned successfully.
+    pub success: bool,
+    /// Cost per call stack of a profiled RISC-V frame, keyed by `;`-separated function names.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub folded_stacks: BTreeMap<String, StackCost>,
+}
+
+/// Trace produced by the hybrid tracer, frames listed in the order they were entered.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HybridTrace {
+    pub frames: Vec<HybridFrameTrace>,
+}
+
+/// Inspector that builds a [`HybridTrace`].
+///
+/// EVM frames are observed through the regular opcode hooks. RISC-V frames are observed through
+/// [`hybrid_vm::trace`], which the RISC-V interpreter fills in on the same thread.
+#[derive(Debug, Default)]
+pub struct HybridTracerInspector {
+    config: HybridTracerConfig,
+    frames: Vec<HybridFrameTrace>,
+    // Indices into `frames` of the frames currently executing.
+    stack: Vec<usize>,
+}
+
+impl HybridTracerInspector {
+    pub fn new(config: HybridTracerConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Consumes the inspector and returns the collected trace.
+    pub fn into_trace(mut self) -> HybridTrace {
+        // Nothing should be left behind once the top-level frame ended, but never leak the
+        // recorder into the next transaction executed on this thread.
+        riscv_trace::stop_recording();
+        self.stack.clear();
+        HybridTrace {
+            frames: self.frames,
+        }
+    }
+
+    fn enter_frame(&mut self, address: Address, gas_limit: u64) {
+        if self.stack.is_empty() {
+            riscv_trace::start_recording();
+            if self.config.profile {
+                riscv_trace::enable_profiling();
+            }
+        }
+        // Whatever the caller ran before yielding belongs to the caller.
+        self.attach_segments();
+
+        self.stack.push(self.frames.len());
+        self.frames.push(HybridFrameTrace {
+            depth: self.stack.len() - 1,
+            address,
+            kind: VmKind::Evm,
+            gas_limit,
+            gas_used: 0,
+            instructions: 0,
+            instruction_gas: 0,
+            syscalls: Vec::new(),
+            success: false,
+            folded_stacks: BTreeMap::new(),
+        });
+    }
+
+    fn exit_frame(&mut self, address: Option<Address>, gas_used: u64, success: bool) {
+        self.attach_segments();
+
+        if let Some(index) = self.stack.pop() {
+            let frame = &mut self.frames[index];
+            if let Some(address) = address {
+                frame.address = address;
+            }
+            frame.gas_used = gas_used;
+            frame.success = success;
+        }
+
+        if self.stack.is_empty() {
+            riscv_trace::stop_recording();
+        }
+    }
+
+    fn attach_segments(&mut self) {
+        let segments = riscv_trace::take_segments();
+        let Some(&index) = self.stack.last() else {
+            return;
+        };
+
+        let frame = &mut self.frames[index];
+        for RiscvSegment {
+            syscalls,
+            instructions,
+            gas_used,
+            folded_stacks,
+            ..
+        } in segments
+        {
+            frame.kind = VmKind::Riscv;
+            frame.instructions += instructions;
+            frame.instruction_gas += gas_used;
+            frame
+                .syscalls
+                .extend(syscalls.iter().map(|syscall| syscall.to_string()));
+            for (stack, cost) in folded_stacks {
+                frame
+                    .folded_stacks
+                    .entry(stack)
+                    .or_default()
+                    .add(StackCost {
+                        instructions: cost.instructions,
+                        gas: cost.gas,
+                    });
+            }
+        }
+    }
+}
+
+impl<CTX: ContextTr> Inspector<CTX, EthInterpreter> for HybridTracerInspector {
+    fn step(&mut self, _interp: &mut Interpreter<EthInterpreter>, _context: &mut CTX) {
+        if let Some(&index) = self.stack.last() {
+            self.frames[index].instructions += 1;
+        }
+    }
+
+    fn call(&mut self, _context: &mut CTX, inputs: &mut CallInputs) -> Option<CallOutcome> {
+        self.enter_frame(inputs.target_address, inputs.gas_limit);
+        None
+    }
+
+    fn call_end(&mut self, _context: &mut CTX, _inputs: &CallInputs, outcome: &mut CallOutcome) {
+        self.exit_frame(
+            None,
+            outcome.result.gas.spent(),
+            outcome.result.result.is_ok(),
+        );
+    }
+
+    fn create(&mut self, _context: &mut CTX, inputs: &mut CreateInputs) -> Option<CreateOutcome> {
+        self.enter_frame(Address::ZERO, inputs.gas_limit);
+        None
+    }
+
+    fn create_end(
+        &mut self,
+        _context: &mut CTX,
+        _inputs: &CreateInputs,
+        outcome: &mut CreateOutcome,
+    ) {
+        self.exit_frame(
+            outcome.address,
+            outcome.result.gas.spent(),
+            outcome.result.result.is_ok(),
+        );
+    }
+}
+
+/// Returns `true` if the tracing options select the hybrid tracer.
+fn is_hybrid_tracer(opts: &GethDebugTracingOptions) -> bool {
+    matches!(&opts.tracer, Some(GethDebugTracerType::JsTracer(name)) if name == HYBRID_TRACER_NAME)
+}
+
+/// Parses the `tracerConfig` of the hybrid tracer.
+fn tracer_config(opts: &GethDebugTracingOptions) -> RpcResult<HybridTracerConfig> {
+    let config = &opts.tracer_config.0;
+    if config.is_null() {
+        return Ok(HybridTracerConfig::default());
+    }
+    serde_json::from_value(config.clone())
+        .map_err(|err| EthApiError::InvalidParams(err.to_string()).into())
+}
+
+fn into_geth_trace(trace: HybridTrace) -> RpcResult<GethTrace> {
+    let value =
+        serde_json::to_value(trace).map_err(|err| EthApiError::InvalidParams(err.to_string()))?;
+    Ok(GethTrace::JS(value))
+}
+
+/// Overrides of the `debug` tracing endpoints that understand the hybrid tracer.
+#[rpc(server, namespace = "debug")]
+pub trait HybridDebugApi {
+    /// `debug_traceTransaction` with support for the hybrid tracer.
+    #[method(name = "traceTransaction")]
+    async fn trace_transaction(
+        &self,
+        tx_hash: B256,
+        opts: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<GethTrace>;
+
+    /// `debug_traceCall` with support for the hybrid tracer.
+    #[method(name = "traceCall")]
+    async fn trace_call(
+        &self,
+        request: TransactionRequest,
+        block_id: Option<BlockId>,
+        opts: Option<GethDebugTracingCallOptions>,
+    ) -> RpcResult<GethTrace>;
+}
+
+/// Handler for [`HybridDebugApiServer`], forwarding every non-hybrid tracer to reth's debug API.
+pub struct HybridDebug<Eth, Debug> {
+    eth_api: Eth,
+    debug_api: Debug,
+}
+
+impl<Eth, Debug> HybridDebug<Eth, Debug> {
+    pub fn new(eth_api: Eth, debug_api: Debug) -> Self {
+        Self { eth_api, debug_api }
+    }
+}
+
+#[jsonrpsee::core::async_trait]
+impl<Eth, Debug> HybridDebugApiServer for HybridDebug<Eth, Debug>
+where
+    Eth: Trace + Call + Clone + Send + Sync + 'static,
+    Debug: DebugApiServer + Send + Sync + 'static,
+{
+    async fn trace_transaction(
+        &self,
+        tx_hash: B256,
+        opts: Option<GethDebugTracingOptions>,
+    ) -> RpcResult<GethTrace> {
+        let opts = opts.unwrap_or_default();
+        if !is_hybrid_tracer(&opts) {
+            return self
+                .debug_api
+                .debug_trace_transaction(tx_hash, Some(opts))
+                .await;
+        }
+
+        let config = tracer_config(&opts)?;
+        let trace = self
+            .eth_api
+            .spawn_trace_transaction_in_block_with_inspector(
+                tx_hash,
+                HybridTracerInspector::new(config),
+                |_tx_info, inspector, _result, _db| Ok(inspector.into_trace()),
+            )
+            .await
+            .map_err(Into::<ErrorObjectOwned>::into)?
+            .ok_or(EthApiError::TransactionNotFound)?;
+
+        into_geth_trace(trace)
+    }
+
+    async fn trace_call(
+        &self,
+        request: TransactionRequest,
+        block_id: Option<BlockId>,
+        opts: Option<GethDebugTracingCallOptions>,
+    ) -> RpcResult<GethTrace> {
+        let opts = opts.unwrap_or_default();
+        if !is_hybrid_tracer(&opts.tracing_options) {
+            return self
+                .debug_api
+                .debug_trace_call(request, block_id, Some(opts))
+                .await;
+        }
+
+        let config = tracer_config(&opts.tracing_options)?;
+        let eth_api = self.eth_api.clone();
+        let overrides = EvmOverrides::new(opts.state_overrides, opts.block_overrides.map(Box::new));
+        let trace = self
+            .eth_api
+            .spawn_with_call_at(
+                request,
+                block_id.unwrap_or_default(),
+                overrides,
+                move |db, evm_env, tx_env| {
+                    let mut inspector = HybridTracerInspector::new(config);
+                    eth_api.inspect(db, evm_env, tx_env, &mut inspector)?;
+                    Ok(inspector.into_trace())
+                },
+            )
+            .await
+            .map_err(Into::<ErrorObjectOwned>::into)?;
+
+        into_geth_trace(trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::obtain_specs, executor::HybridExecutorBuilder,
+        payload_builder::HybridPayloadBuilder,
+    };
+    use alloy_primitives::{Bytes, U256};
+    use hybrid_syscalls::Syscall;
+    use hybrid_vm::{
+        evm::HybridEvm,
+        execution::helper::hybrid_gas_used,
+        revm::{
+            db::{CacheDB, EmptyDB},
+            handler::EthPrecompiles,
+            inspector::InspectEvm,
+            primitives::TxKind,
+            state::{AccountInfo, Bytecode},
+            Context, MainBuilder, MainContext,
+        },
+    };
+    use jsonrpsee::{core::client::ClientT, rpc_params};
+    use reth::{
+        args::RpcServerArgs,
+        builder::{components::BasicPayloadServiceBuilder, NodeBuilder, NodeConfig},
+        tasks::TaskManager,
+    };
+    use reth_ethereum::node::{node::EthereumAddOns, EthereumNode};
+    use rvemu::{bus::DRAM_BASE, emulator::Emulator};
+    use serde_json::{json, Value};
+
+    /// Runs a RISC-V segment of `address` that retires `addi` instructions and issues `syscalls`.
+    fn run_segment(address: Address, addi: u64, syscalls: &[Syscall]) -> u64 {
+        for &syscall in syscalls {
+            riscv_trace::record_syscall(syscall);
+        }
+        let mut emu = Emulator::new();
+        emu.cpu.inst_counter.insert("addi".to_string(), addi);
+        let gas = hybrid_gas_used(&emu.cpu.inst_counter);
+        riscv_trace::record_segment(address, &mut emu, &Default::default());
+        gas
+    }
+
+    /// A RISC-V contract, prefixed with `0xFF`, that returns nothing: `return(0, 0)`.
+    fn riscv_return_contract() -> Bytes {
+        const CODE_BASE: u64 = DRAM_BASE + 0x10_0000;
+        const EHSIZE: u64 = 64;
+        const PHENTSIZE: u64 = 56;
+
+        // addi a0, zero, 0; addi a1, zero, 0; addi t0, zero, Return; ecall
+        let code = [
+            0x0000_0513,
+            0x0000_0593,
+            (u32::from(u8::from(Syscall::Return)) << 20) | 0x293,
+            0x0000_0073,
+        ];
+        let segment: Vec<u8> = code
+            .iter()
+            .flat_map(|inst: &u32| inst.to_le_bytes())
+            .collect();
+
+        let mut elf = vec![0xFF];
+        // e_ident: 64-bit, little endian, version 1
+        elf.extend([0x7f, b'E', b'L', b'F', 2, 1, 1]);
+        elf.resize(17, 0);
+        elf.extend(2u16.to_le_bytes()); // e_type: executable
+        elf.extend(243u16.to_le_bytes()); // e_machine: RISC-V
+        elf.extend(1u32.to_le_bytes()); // e_version
+        elf.extend(CODE_BASE.to_le_bytes()); // e_entry
+        elf.extend(EHSIZE.to_le_bytes()); // e_phoff
+        elf.extend(0u64.to_le_bytes()); // e_shoff
+        elf.extend(0u32.to_le_bytes()); // e_flags
+        elf.extend((EHSIZE as u16).to_le_bytes()); // e_ehsize
+        elf.extend((PHENTSIZE as u16).to_le_bytes()); // e_phentsize
+        elf.extend(1u16.to_le_bytes()); // e_phnum
+        elf.extend(64u16.to_le_bytes()); // e_shentsize
+        elf.extend(0u16.to_le_bytes()); // e_shnum
+        elf.extend(0u16.to_le_bytes()); // e_shstrndx
+
+        // A single PT_LOAD segment, right after the headers
+        elf.extend(1u32.to_le_bytes()); // p_type
+        elf.extend(7u32.to_le_bytes()); // p_flags: rwx
+        elf.extend((EHSIZE + PHENTSIZE).to_le_bytes()); // p_offset
+        elf.extend(CODE_BASE.to_le_bytes()); // p_vaddr
+        elf.extend(CODE_BASE.to_le_bytes()); // p_paddr
+        elf.extend((segment.len() as u64).to_le_bytes()); // p_filesz
+        elf.extend((segment.len() as u64).to_le_bytes()); // p_memsz
+        elf.extend(8u64.to_le_bytes()); // p_align
+
+        elf.extend(segment);
+        elf.into()
+    }
+
+    #[test]
+    fn test_riscv_segments_attach_to_their_frame() {
+        let caller = Address::repeat_byte(0x11);
+        let callee = Address::repeat_byte(0x22);
+        let mut inspector = HybridTracerInspector::new(HybridTracerConfig::default());
+
+        inspector.enter_frame(caller, 1_000_000);
+        assert!(riscv_trace::is_recording());
+        // The RISC-V caller yields to revm to call an EVM contract...
+        let before = run_segment(caller, 40, &[Syscall::Caller, Syscall::Call]);
+        inspector.enter_frame(callee, 500_000);
+        inspector.exit_frame(None, 2_100, false);
+        // ...and resumes once the call returned.
+        let after = run_segment(caller, 2, &[Syscall::ReturnDataSize, Syscall::Return]);
+        inspector.exit_frame(None, 30_000, true);
+        assert!(!riscv_trace::is_recording());
+
+        let frames = inspector.into_trace().frames;
+        assert_eq!(frames.len(), 2);
+
+        let outer = &frames[0];
+        assert_eq!(
+            (outer.depth, outer.address, outer.kind),
+            (0, caller, VmKind::Riscv)
+        );
+        assert_eq!((outer.gas_limit, outer.gas_used), (1_000_000, 30_000));
+        assert_eq!(outer.instructions, 42);
+        assert_eq!(outer.instruction_gas, before + after);
+        assert_eq!(
+            outer.syscalls,
+            ["caller", "call", "returndatasize", "return"]
+        );
+        assert!(outer.success);
+
+        let inner = &frames[1];
+        assert_eq!(
+            (inner.depth, inner.address, inner.kind),
+            (1, callee, VmKind::Evm)
+        );
+        assert_eq!((inner.gas_limit, inner.gas_used), (500_000, 2_100));
+        assert_eq!((inner.instructions, inner.instruction_gas), (0, 0));
+        assert!(inner.syscalls.is_empty());
+        assert!(!inner.success);
+    }
+
+    #[test]
+    fn test_segments_outside_a_trace_are_dropped() {
+        let address = Address::repeat_byte(0x33);
+        run_segment(address, 10, &[Syscall::Return]);
+
+        let mut inspector = HybridTracerInspector::new(HybridTracerConfig::default());
+        inspector.enter_frame(address, 100_000);
+        inspector.exit_frame(None, 21_000, true);
+
+        let frames = inspector.into_trace().frames;
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].kind, VmKind::Evm);
+        assert!(frames[0].syscalls.is_empty());
+    }
+
+    #[test]
+    fn test_inspected_evm_traces_riscv_frame() {
+        let contract = Address::repeat_byte(0x44);
+        let code = Bytecode::new_raw(riscv_return_contract());
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            contract,
+            AccountInfo {
+                balance: U256::ZERO,
+                nonce: 1,
+                code_hash: code.hash_slow(),
+                code: Some(code),
+            },
+        );
+
+        let mut evm = HybridEvm(
+            Context::mainnet()
+                .with_db(db)
+                .modify_tx_chained(|tx| {
+                    tx.caller = Address::repeat_byte(0x10);
+                    tx.kind = TxKind::Call(contract);
+                    tx.gas_limit = 1_000_000;
+                })
+                .build_mainnet_with_inspector(HybridTracerInspector::default())
+                .with_precompiles(EthPrecompiles::default()),
+        );
+        let result = evm.inspect_replay().unwrap();
+        assert!(result.result.is_success(), "{:?}", result.result);
+
+        let frames = std::mem::take(&mut evm.0.data.inspector)
+            .into_trace()
+            .frames;
+        assert_eq!(frames.len(), 1);
+        let frame = &frames[0];
+        assert_eq!(
+            (frame.depth, frame.address, frame.kind),
+            (0, contract, VmKind::Riscv)
+        );
+        assert_eq!(frame.syscalls, ["return"]);
+        assert!(frame.instructions > 0);
+        assert!(frame.instruction_gas > 0);
+        assert!(frame.success);
+        assert!(!riscv_trace::is_recording());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_debug_trace_call_through_rpc() -> eyre::Result<()> {
+        let tasks = TaskManager::current();
+        let node_config = NodeConfig::test()
+            .dev()
+            .with_rpc(RpcServerArgs::default().with_unused_ports().with_http())
+            .with_chain(obtain_specs());
+
+        // The node `run_node` launches, on free ports
+        let handle = NodeBuilder::new(node_config)
+            .testing_node(tasks.executor())
+            .with_types::<EthereumNode>()
+            .with_components(
+                EthereumNode::components()
+                    .executor(HybridExecutorBuilder::default())
+                    .payload(BasicPayloadServiceBuilder::new(
+                        HybridPayloadBuilder::default(),
+                    )),
+            )
+            .with_add_ons(EthereumAddOns::default())
+            .extend_rpc_modules(|ctx| {
+                let hybrid_debug =
+                    HybridDebug::new(ctx.registry.eth_api().clone(), ctx.registry.debug_api());
+                ctx.modules.replace_configured(hybrid_debug.into_rpc())?;
+                Ok(())
+            })
+            .launch()
+            .await?;
+        let client = handle
+            .node
+            .rpc_server_handle()
+            .http_client()
+            .expect("HTTP is enabled");
+
+        let contract = Address::repeat_byte(0x44);
+        let request = json!({ "to": contract, "gas": "0x100000" });
+        let overrides = json!({ contract.to_string(): { "code": riscv_return_contract() } });
+
+        let trace: Value = client
+            .request(
+                "debug_traceCall",
+                rpc_params![
+                    request.clone(),
+                    "latest",
+                    json!({ "tracer": HYBRID_TRACER_NAME, "stateOverrides": overrides })
+                ],
+            )
+            .await?;
+        let frames = trace["frames"].as_array().expect("a hybrid trace");
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0]["kind"], "riscv");
+        assert_eq!(frames[0]["syscalls"], json!(["return"]));
+        assert_eq!(frames[0]["success"], true);
+
+        // Any other tracer is still served by reth's debug API
+        let trace: Value = client
+            .request(
+                "debug_traceCall",
+                rpc_params![
+                    request,
+                    "latest",
+                    json!({ "tracer": "callTracer", "stateOverrides": overrides })
+                ],
+            )
+            .await?;
+        assert_eq!(trace["type"], "CALL");
+        assert!(trace.get("frames").is_none());
+
+        Ok(())
+    }
+}
@@ -58,14 +58,20 @@ macro_rules! syscalls {
 // t0: 0x32, opcode for origin, returns an address
 // t0: 0x33, opcode for caller, returns an address
 // t0: 0x34, opcode for callvalue, a0: first limb, a1: second limb, a2: third limb, a3: fourth limb, returns 256-bit value
+// t0: 0x38, opcode for codesize, returns 64-bit value
+// t0: 0x39, opcode for codecopy, a0: memory offset, a1: code offset, a2: size, returns nothing
 // t0: 0x3A, opcode for gasprice, returns 256-bit value
 // t0: 0x3d, opcode for returndatasize, returns 64-bit value
 // t0: 0x3e, opcode for returndatacopy, a0: memory offset, a1: return data offset, a2: return data size, returns nothing
 // t0: 0x54, opcode for sload, a0: storage key, returns 256-bit value
 // t0: 0x55, opcode for sstore, a0-a3: 256-bit storage key, a4-a7: 256-bit storage value, returns nothing
-// t0: 0xf0, opcode for create, args: a0: 64-bit value, a1: calldata offset, a2: calldata size, returns an address
-// t0: 0xf1, opcode for call, args: a0-a2: address, a3: 64-bit value, a4: calldata offset, a5: calldata size
-// t0: 0xfa, opcode for staticcall, args: a0-a2: address, a3: 64-bit value, a4: calldata offset, a5: calldata size
+// t0: 0xf0, opcode for create, args: a0: value pointer, a1: calldata offset, a2: calldata size, returns an address
+// t0: 0xf1, opcode for call, args: a0-a2: address, a3: value pointer, a4: calldata offset, a5: calldata size, a6: gas limit, returns 1 in a0 if the call succeeded, 0 otherwise
+// t0: 0xfa, opcode for staticcall, args: a0-a2: address, a3: value pointer, a4: calldata offset, a5: calldata size, a6: gas limit, returns 1 in a0 if the call succeeded, 0 otherwise
+//
+// Call values are 256-bit, passed as a pointer to their four 64-bit limbs in little-endian order.
+// The gas limit of a call is capped to all but one 64th of the remaining gas (EIP-150), so
+// `u64::MAX` forwards as much gas as possible.
 // t0: 0xf3, opcode for return, a0: memory address of data, a1: length of data in bytes, doesn't return
 // t0: 0xfd, opcode for revert, doesn't return
 //
@@ -1,4 +1,4 @@
-use super::{evm::HybridEvm, handler::HybridHandler};
+use super::{evm::HybridEvm, handler::HybridHandler, hybrid_execute::clear_suspended};
 use reth::revm::{
     context::{
         result::{HaltReason, InvalidTransaction},
@@ -37,7 +37,9 @@ where
     }
 
     fn replay(&mut self) -> Self::Output {
+        clear_suspended();
         let out = HybridHandler::default().run(self);
+        clear_suspended();
         out
     }
 }
@@ -70,7 +72,10 @@ where
     }
 
     fn inspect_replay(&mut self) -> Self::Output {
-        HybridHandler::default().inspect_run(self)
+        clear_suspended();
+        let out = HybridHandler::default().inspect_run(self);
+        clear_suspended();
+        out
     }
 }
 
@@ -9,10 +9,16 @@ pub const CALL_EMPTY_ACCOUNT: u64 = 25000;
 pub const CALL_NEW_ACCOUNT: u64 = 2600;
 pub const CALL_VALUE: u64 = 9000;
 pub const CALL_BASE: u64 = 100;
+pub const CALL_STIPEND: u64 = 2300;
 
 // Create-related costs
 pub const CREATE_BASE: u64 = 32000;
 
+/// Maximum gas a call or create may forward out of `remaining`: all but one 64th (EIP-150)
+pub const fn max_forwarded_gas(remaining: u64) -> u64 {
+    remaining - remaining / 64
+}
+
 // Macro to handle gas accounting for syscalls.
 // Returns OutOfGas InterpreterResult if gas limit is exceeded.
 #[macro_export]
@@ -5,48 +5,77 @@ use reth::revm::{
 };
 use reth::revm::{
     interpreter::{
-        CallInputs, CallScheme, CallValue, Host, InstructionResult, Interpreter, InterpreterAction,
-        InterpreterResult,
+        interpreter_types::RuntimeFlag, CallInputs, CallScheme, CallValue, Host, InstructionResult,
+        Interpreter, InterpreterAction, InterpreterResult,
     },
     primitives::{Address, Bytes, U256},
 };
 use rvemu::emulator::Emulator;
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    execution::gas::{CALL_BASE, CALL_EMPTY_ACCOUNT, CALL_NEW_ACCOUNT, CALL_VALUE, CREATE_BASE},
+    execution::gas::{
+        max_forwarded_gas, CALL_BASE, CALL_EMPTY_ACCOUNT, CALL_NEW_ACCOUNT, CALL_STIPEND,
+        CALL_VALUE, CREATE_BASE,
+    },
+    hybrid_execute::SNAPSHOT_DIR_ENV,
     syscall_gas,
 };
 
+/// Gas of the RISC-V instructions retired, by mnemonic as counted in `Cpu::inst_counter`
 pub fn hybrid_gas_used(inst_count: &BTreeMap<String, u64>) -> u64 {
-    let total_cost = inst_count
+    inst_count
         .iter()
-        .map(|(inst_name, count)|
-            // Gas cost = number of instructions * cycles per instruction
-            match inst_name.as_str() {
-                // Gas map to approximate cost of each instruction
-                // References:
-                // http://ithare.com/infographics-operation-costs-in-cpu-clock-cycles/
-                // https://www.evm.codes/?fork=cancun#54
-                // Division and remainder
-                s if s.starts_with("div") || s.starts_with("rem") => count * 25,
-                // Multiplications
-                s if s.starts_with("mul") => count * 5,
-                // Loads
-                "lb" | "lh" | "lw" | "ld" | "lbu" | "lhu" | "lwu" => count * 3, // Cost analagous to `MLOAD`
-                // Stores
-                "sb" | "sh" | "sw" | "sd" | "sc.w" | "sc.d" => count * 3, // Cost analagous to `MSTORE`
-                // Branching
-                "beq" | "bne" | "blt" | "bge" | "bltu" | "bgeu" | "jal" | "jalr" => count * 3,
-                _ => *count, // All other instructions including `add` and `sub`
-        })
-        .sum::<u64>();
-
-    // This is the minimum 'gas used' to ABI decode 'empty' calldata into Rust type arguments. Real calldata will take more gas.
-    // Internalising this would focus gas metering more on the function logic
-    let abi_decode_cost = 9_175_538;
-
-    total_cost - abi_decode_cost
+        .map(|(inst_name, count)| count * instruction_gas(inst_name))
+        .sum()
+}
+
+/// Gas left to a RISC-V frame once the instructions it retired so far are paid for. They are
+/// only charged when the frame returns, so a call or create must not be forwarded them.
+pub fn frame_gas_left(emu: &Emulator, interpreter: &Interpreter) -> u64 {
+    interpreter
+        .control
+        .gas
+        .remaining()
+        .saturating_sub(hybrid_gas_used(&emu.cpu.inst_counter))
+}
+
+/// Gas cost of a single RISC-V instruction, by the mnemonic counted in `Cpu::inst_counter`
+pub fn instruction_gas(inst_name: &str) -> u64 {
+    // Gas cost = number of instructions * cycles per instruction
+    match inst_name {
+        // Gas map to approximate cost of each instruction
+        // References:
+        // http://ithare.com/infographics-operation-costs-in-cpu-clock-cycles/
+        // https://www.evm.codes/?fork=cancun#54
+        // Division and remainder
+        s if s.starts_with("div") || s.starts_with("rem") => 25,
+        // Multiplications
+        s if s.starts_with("mul") => 5,
+        // Loads
+        "lb" | "lh" | "lw" | "ld" | "lbu" | "lhu" | "lwu" => 3, // Cost analagous to `MLOAD`
+        // Stores
+        "sb" | "sh" | "sw" | "sd" | "sc.w" | "sc.d" => 3, // Cost analagous to `MSTORE`
+        // Branching
+        "beq" | "bne" | "blt" | "bge" | "bltu" | "bgeu" | "jal" | "jalr" => 3,
+        _ => 1, // All other instructions including `add` and `sub`
+    }
+}
+
+/// Writes a snapshot of a faulted emulator to the directory in [`SNAPSHOT_DIR_ENV`], if set, and
+/// returns the path of the snapshot
+pub fn dump_snapshot(emu: &Emulator, address: Address) -> Result<Option<PathBuf>, String> {
+    let Ok(dir) = std::env::var(SNAPSHOT_DIR_ENV) else {
+        return Ok(None);
+    };
+
+    let path = Path::new(&dir).join(format!("{}-{:x}.rvsnap", address, emu.cpu.pc));
+    std::fs::write(&path, emu.snapshot().to_bytes())
+        .map_err(|err| format!("Failed to write snapshot to {}: {}", path.display(), err))?;
+    Ok(Some(path))
 }
 
 /// Returns RISC-V DRAM slice in a given size range, starts with a given offset
@@ -75,30 +104,43 @@ pub fn dram_write(emu: &mut Emulator, ret_offset: u64, data: &[u8]) -> Result<()
     }
 }
 
+/// Reads a 256-bit call value from its four little-endian limbs at `value_ptr`
+pub fn read_value(emu: &mut Emulator, value_ptr: u64) -> Result<U256, String> {
+    let bytes = dram_slice(emu, value_ptr, 32)?;
+    Ok(U256::from_le_slice(bytes))
+}
+
+/// Ends the frame as the EVM does when a static call tries to modify state
+/// (SSTORE, LOG, CREATE or a CALL with value while `is_static`)
+pub fn state_change_during_static_call(interpreter: &Interpreter) -> InterpreterAction {
+    InterpreterAction::Return {
+        result: InterpreterResult {
+            result: InstructionResult::StateChangeDuringStaticCall,
+            output: Bytes::new(),
+            gas: interpreter.control.gas,
+        },
+    }
+}
+
 pub fn execute_create(
     emu: &mut Emulator,
     interpreter: &mut Interpreter,
     _host: &mut dyn Host,
 ) -> Result<InterpreterAction, String> {
-    let value: u64 = emu.cpu.xregs.read(10);
+    let value_ptr: u64 = emu.cpu.xregs.read(10);
+    let value = read_value(emu, value_ptr)?;
 
     // Get initcode
     let args_offset: u64 = emu.cpu.xregs.read(11);
     let args_size: u64 = emu.cpu.xregs.read(12);
-    let init_code: Bytes = emu
-        .cpu
-        .bus
-        .get_dram_slice(args_offset..(args_offset + args_size))
-        .unwrap_or(&mut [])
-        .to_vec()
-        .into();
+    let init_code: Bytes = dram_slice(emu, args_offset, args_size)?.to_vec().into();
 
     // TODO: calculate gas cost properly
     let create_gas_cost = CREATE_BASE;
     syscall_gas!(interpreter, create_gas_cost);
 
     // proactively spend gas limit as the remaining will be refunded (otherwise it underflows)
-    let create_gas_limit = interpreter.control.gas.remaining();
+    let create_gas_limit = max_forwarded_gas(frame_gas_left(emu, interpreter));
     syscall_gas!(interpreter, create_gas_limit);
 
     Ok(InterpreterAction::NewFrame(FrameInput::Create(Box::new(
@@ -106,7 +148,7 @@ pub fn execute_create(
             init_code,
             gas_limit: create_gas_limit,
             caller: interpreter.input.target_address,
-            value: U256::from(value),
+            value,
             scheme: CreateScheme::Create,
         },
     ))))
@@ -122,18 +164,18 @@ pub fn execute_call(
     let a1: u64 = emu.cpu.xregs.read(11);
     let a2: u64 = emu.cpu.xregs.read(12);
     let addr = Address::from_word(U256::from_limbs([a0, a1, a2, 0]).into());
-    let value: u64 = emu.cpu.xregs.read(13);
+    let value_ptr: u64 = emu.cpu.xregs.read(13);
+    let value = read_value(emu, value_ptr)?;
+    if interpreter.runtime_flag.is_static() && !value.is_zero() {
+        return Ok(state_change_during_static_call(interpreter));
+    }
 
     // Get calldata
     let args_offset: u64 = emu.cpu.xregs.read(14);
     let args_size: u64 = emu.cpu.xregs.read(15);
-    let calldata: Bytes = emu
-        .cpu
-        .bus
-        .get_dram_slice(args_offset..(args_offset + args_size))
-        .unwrap_or(&mut [])
-        .to_vec()
-        .into();
+    let requested_gas: u64 = emu.cpu.xregs.read(16);
+    // Empty calldata may come with any offset, `send` passes 0
+    let calldata: Bytes = dram_slice(emu, args_offset, args_size)?.to_vec().into();
 
     // Calculate gas cost of the call
     // TODO: check correctness (tried using evm.codes as ref but i'm no gas wizard)
@@ -148,14 +190,22 @@ pub fn execute_call(
         }
         None => (CALL_EMPTY_ACCOUNT, CALL_NEW_ACCOUNT),
     };
-    let value_cost = if value != 0 { CALL_VALUE } else { 0 };
+    let value_cost = if !value.is_zero() { CALL_VALUE } else { 0 };
     let call_gas_cost = empty_account_cost + addr_access_cost + value_cost;
     syscall_gas!(interpreter, call_gas_cost);
 
-    // proactively spend gas limit as the remaining will be refunded (otherwise it underflows)
-    let call_gas_limit = interpreter.control.gas.remaining();
+    // Forward the requested gas, capped to all but one 64th of the remaining gas (EIP-150).
+    // The gas limit is spent proactively as the unused part will be refunded (otherwise it underflows)
+    let call_gas_limit = requested_gas.min(max_forwarded_gas(frame_gas_left(emu, interpreter)));
     syscall_gas!(interpreter, call_gas_limit);
 
+    // Value transfers come with a free stipend for the callee, as with the CALL opcode
+    let call_gas_limit = if value.is_zero() {
+        call_gas_limit
+    } else {
+        call_gas_limit + CALL_STIPEND
+    };
+
     Ok(InterpreterAction::NewFrame(FrameInput::Call(Box::new(
         CallInputs {
             input: calldata,
@@ -163,11 +213,44 @@ pub fn execute_call(
             target_address: addr,
             bytecode_address: addr,
             caller: interpreter.input.target_address,
-            value: CallValue::Transfer(U256::from(value)),
+            value: CallValue::Transfer(value),
             scheme: CallScheme::Call,
-            is_static,
+            // Calls made from a static frame stay static
+            is_static: is_static || interpreter.runtime_flag.is_static(),
             is_eof: false,
             return_memory_offset: 0..0, // handled with RETURNDATACOPY
         },
     ))))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rvemu::bus::DRAM_BASE;
+
+    #[test]
+    fn test_read_value_above_u64() {
+        let mut emu = Emulator::new();
+        let value = U256::from(u64::MAX) * U256::from(1_000u64) + U256::from(7u64);
+        dram_write(&mut emu, DRAM_BASE, &value.to_le_bytes::<32>()).unwrap();
+
+        assert_eq!(read_value(&mut emu, DRAM_BASE).unwrap(), value);
+    }
+
+    #[test]
+    fn test_max_forwarded_gas() {
+        assert_eq!(max_forwarded_gas(0), 0);
+        assert_eq!(max_forwarded_gas(63), 63);
+        assert_eq!(max_forwarded_gas(64), 63);
+        assert_eq!(max_forwarded_gas(6_400_000), 6_300_000);
+        assert_eq!(max_forwarded_gas(u64::MAX), u64::MAX - u64::MAX / 64);
+    }
+
+    #[test]
+    fn test_read_value_max() {
+        let mut emu = Emulator::new();
+        dram_write(&mut emu, DRAM_BASE, &U256::MAX.to_le_bytes::<32>()).unwrap();
+
+        assert_eq!(read_value(&mut emu, DRAM_BASE).unwrap(), U256::MAX);
+    }
+}
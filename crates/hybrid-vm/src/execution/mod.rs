@@ -10,7 +10,7 @@ use reth::{
         interpreter::{
             as_usize_saturated,
             interpreter::EthInterpreter,
-            interpreter_types::{InputsTr, LegacyBytecode, LoopControl, ReturnData},
+            interpreter_types::{InputsTr, LegacyBytecode, LoopControl, ReturnData, RuntimeFlag},
             Host, InstructionResult, Interpreter, InterpreterAction, InterpreterResult,
         },
         primitives::{alloy_primitives::Keccak256, Address, Bytes, B256, U256},
@@ -19,13 +19,18 @@ use reth::{
 use rvemu::{emulator::Emulator, exception::Exception};
 pub mod utils;
 use core::ptr;
+use std::collections::BTreeMap;
+use tracing::warn;
 
 use crate::{
     execution::{
-        helper::{dram_slice, execute_call, execute_create, hybrid_gas_used},
-        utils::__3u64_to_address,
+        helper::{
+            dram_slice, dump_snapshot, execute_call, execute_create, frame_gas_left,
+            hybrid_gas_used, state_change_during_static_call,
+        },
+        utils::{__3u64_to_address, __address_to_3u64},
     },
-    syscall_gas,
+    syscall_gas, trace,
 };
 
 pub mod gas;
@@ -37,6 +42,33 @@ pub fn execute_riscv_contract<EVM>(
     evm: &mut EVM,
     last_created_contract: &Option<Address>,
 ) -> Result<InterpreterAction, String>
+where
+    EVM: EvmTr<
+        Precompiles: PrecompileProvider<EVM::Context, Output = InterpreterResult>,
+        Instructions: InstructionProvider<
+            Context = EVM::Context,
+            InterpreterTypes = EthInterpreter,
+        >,
+    >,
+{
+    // A frame resumed after a child call keeps counting from where it left off, so segments are
+    // recorded against the instructions retired before this run
+    let retired = if trace::is_recording() {
+        emu.cpu.inst_counter.clone()
+    } else {
+        BTreeMap::new()
+    };
+    let action = run_riscv_contract(emu, interpreter, evm, last_created_contract);
+    trace::record_segment(interpreter.input.target_address, emu, &retired);
+    action
+}
+
+fn run_riscv_contract<EVM>(
+    emu: &mut Emulator,
+    interpreter: &mut Interpreter,
+    evm: &mut EVM,
+    last_created_contract: &Option<Address>,
+) -> Result<InterpreterAction, String>
 where
     EVM: EvmTr<
         Precompiles: PrecompileProvider<EVM::Context, Output = InterpreterResult>,
@@ -72,6 +104,14 @@ where
                 let Ok(syscall) = Syscall::try_from(t0 as u8) else {
                     return return_revert(interpreter, interpreter.control.gas.spent());
                 };
+                trace::record_syscall(syscall);
+
+                // Static frames must not modify state, as with the EVM opcodes
+                if interpreter.runtime_flag.is_static()
+                    && matches!(syscall, Syscall::SStore | Syscall::Log | Syscall::Create)
+                {
+                    return Ok(state_change_during_static_call(interpreter));
+                }
 
                 match syscall {
                     Syscall::Keccak256 => {
@@ -118,43 +158,17 @@ where
                         }
                     }
                     Syscall::Origin => {
-                        // Syscall::Origin
-                        let origin = host.tx().caller();
-                        // Break address into 3 u64s and write to registers
-                        let origin_bytes = origin.as_slice();
-
-                        let first_u64 = u64::from_be_bytes(origin_bytes[0..8].try_into().unwrap());
-                        emu.cpu.xregs.write(10, first_u64);
-
-                        let second_u64 =
-                            u64::from_be_bytes(origin_bytes[8..16].try_into().unwrap());
-                        emu.cpu.xregs.write(11, second_u64);
-
-                        let mut padded_bytes = [0u8; 8];
-                        padded_bytes[..4].copy_from_slice(&origin_bytes[16..20]);
-                        let third_u64 = u64::from_be_bytes(padded_bytes);
-                        emu.cpu.xregs.write(12, third_u64);
+                        let (first, second, third) = __address_to_3u64(host.tx().caller());
+                        emu.cpu.xregs.write(10, first);
+                        emu.cpu.xregs.write(11, second);
+                        emu.cpu.xregs.write(12, third);
                     }
                     Syscall::Caller => {
-                        let caller = interpreter.input.caller_address;
-                        // Break address into 3 u64s and write to registers
-                        let caller_bytes = caller.as_slice();
-                        let first_u64 = u64::from_be_bytes(
-                            caller_bytes[0..8]
-                                .try_into()
-                                .map_err(|_| "Error converting caller address to u64")?,
-                        );
-                        emu.cpu.xregs.write(10, first_u64);
-                        let second_u64 = u64::from_be_bytes(
-                            caller_bytes[8..16]
-                                .try_into()
-                                .map_err(|_| "Error converting caller address to u64")?,
-                        );
-                        emu.cpu.xregs.write(11, second_u64);
-                        let mut padded_bytes = [0u8; 8];
-                        padded_bytes[..4].copy_from_slice(&caller_bytes[16..20]);
-                        let third_u64 = u64::from_be_bytes(padded_bytes);
-                        emu.cpu.xregs.write(12, third_u64);
+                        let (first, second, third) =
+                            __address_to_3u64(interpreter.input.caller_address);
+                        emu.cpu.xregs.write(10, first);
+                        emu.cpu.xregs.write(11, second);
+                        emu.cpu.xregs.write(12, third);
                     }
                     Syscall::CallValue => {
                         let value = interpreter.input.call_value;
@@ -214,7 +228,17 @@ where
                         emu.cpu.xregs.write(13, limbs[3]);
                     }
                     Syscall::CodeCopy => {
-                        // There is no need for this
+                        let dest_offset = emu.cpu.xregs.read(10);
+                        let offset = emu.cpu.xregs.read(11) as usize;
+                        let size = emu.cpu.xregs.read(12);
+                        let code = interpreter.bytecode.bytecode_slice();
+
+                        // Bytes past the end of the code are zero, as with the CODECOPY opcode
+                        let start = offset.min(code.len());
+                        let end = offset.saturating_add(size as usize).min(code.len());
+                        let memory = dram_slice(emu, dest_offset, size)?;
+                        memory[..end - start].copy_from_slice(&code[start..end]);
+                        memory[end - start..].fill(0);
                     }
                     Syscall::GasPrice => {
                         let value = host.tx().gas_price();
@@ -248,24 +272,10 @@ where
                     Syscall::ExtCodeHash => {}
                     Syscall::BlockHash => {}
                     Syscall::Coinbase => {
-                        let coinbase = host.beneficiary();
-                        let limbs = coinbase.as_slice();
-                        let first_u64 = u64::from_be_bytes(
-                            limbs[0..8]
-                                .try_into()
-                                .map_err(|_| "Error converting caller address to u64")?,
-                        );
-                        emu.cpu.xregs.write(10, first_u64);
-                        let second_u64 = u64::from_be_bytes(
-                            limbs[8..16]
-                                .try_into()
-                                .map_err(|_| "Error converting caller address to u64")?,
-                        );
-                        emu.cpu.xregs.write(11, second_u64);
-                        let mut padded_bytes = [0u8; 8];
-                        padded_bytes[..4].copy_from_slice(&limbs[16..20]);
-                        let third_u64 = u64::from_be_bytes(padded_bytes);
-                        emu.cpu.xregs.write(12, third_u64);
+                        let (first, second, third) = __address_to_3u64(host.beneficiary());
+                        emu.cpu.xregs.write(10, first);
+                        emu.cpu.xregs.write(11, second);
+                        emu.cpu.xregs.write(12, third);
                     }
                     Syscall::Timestamp => {
                         let timestamp = host.timestamp();
@@ -308,7 +318,7 @@ where
                         emu.cpu.xregs.write(10, u64::from_le_bytes(arr));
                     }
                     Syscall::SelfBalance => {
-                        let address = interpreter.input.caller_address;
+                        let address = interpreter.input.target_address;
                         match host.balance(address) {
                             Some(state_load) => {
                                 let limbs = state_load.data.as_limbs();
@@ -339,7 +349,14 @@ where
                         emu.cpu.xregs.write(13, limbs[3]);
                     }
                     Syscall::BlobHash => {}
-                    Syscall::BlobBaseFee => {}
+                    Syscall::BlobBaseFee => {
+                        let value = host.blob_gasprice();
+                        let limbs = value.as_limbs();
+                        emu.cpu.xregs.write(10, limbs[0]);
+                        emu.cpu.xregs.write(11, limbs[1]);
+                        emu.cpu.xregs.write(12, limbs[2]);
+                        emu.cpu.xregs.write(13, limbs[3]);
+                    }
                     Syscall::SLoad => {
                         let key1: u64 = emu.cpu.xregs.read(10);
                         let key2: u64 = emu.cpu.xregs.read(11);
@@ -394,7 +411,7 @@ where
                         }
                     }
                     Syscall::Gas => {
-                        let gas = U256::from(interpreter.control.gas().remaining());
+                        let gas = U256::from(frame_gas_left(emu, interpreter));
                         let limbs = gas.as_limbs();
                         emu.cpu.xregs.write(10, limbs[0]);
                         emu.cpu.xregs.write(11, limbs[1]);
@@ -492,7 +509,14 @@ where
                 continue;
             }
             Err(e) => {
-                println!("Error On Execute: {:?}", e);
+                // A snapshot that can't be written must not change how the frame ends
+                match dump_snapshot(emu, interpreter.input.target_address) {
+                    Ok(Some(path)) => {
+                        warn!("Error On Execute: {:?} (snapshot: {})", e, path.display())
+                    }
+                    Ok(None) => warn!("Error On Execute: {:?}", e),
+                    Err(err) => warn!("Error On Execute: {:?} ({})", e, err),
+                }
                 syscall_gas!(interpreter, hybrid_gas_used(&emu.cpu.inst_counter));
                 return return_revert(interpreter, interpreter.control.gas.spent());
             }
@@ -8,7 +8,7 @@ use reth::revm::{
         instructions::InstructionProvider, EthFrame, EvmTr, Frame, FrameInitOrResult,
         FrameOrResult, FrameResult, Handler, ItemOrResult, PrecompileProvider,
     },
-    inspector::{Inspector, InspectorEvmTr, InspectorHandler},
+    inspector::{Inspector, InspectorEvmTr, InspectorFrame, InspectorHandler},
     interpreter::{interpreter::EthInterpreter, InterpreterResult},
     Database,
 };
@@ -116,4 +116,18 @@ where
     >,
 {
     type IT = EthInterpreter;
+
+    #[inline]
+    fn inspect_frame_call(
+        &mut self,
+        frame: &mut Self::Frame,
+        evm: &mut Self::Evm,
+    ) -> Result<FrameInitOrResult<Self::Frame>, Self::Error> {
+        // RISC-V frames have no opcodes to step through; they are surfaced to tracers through
+        // `crate::trace` instead of the per-instruction inspector hooks.
+        if frame.interpreter.bytecode.bytecode().first() == Some(&0xFF) {
+            return hybrid_frame_call(frame, evm);
+        }
+        frame.run_inspect(evm)
+    }
 }
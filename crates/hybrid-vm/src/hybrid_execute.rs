@@ -8,11 +8,50 @@ use reth::revm::{
         instructions::InstructionProvider, ContextTrDbError, EthFrame, EvmTr, FrameInitOrResult,
         PrecompileProvider,
     },
-    interpreter::{interpreter::EthInterpreter, FrameInput, InterpreterResult},
-    primitives::alloy_primitives::U32,
+    interpreter::{interpreter::EthInterpreter, FrameInput, InterpreterAction, InterpreterResult},
+    primitives::{alloy_primitives::U32, Address},
 };
 
-use crate::{execution::execute_riscv_contract, setup::setup_from_elf};
+use hybrid_syscalls::Syscall;
+use rvemu::{emulator::Emulator, gdb::GdbStub, profile::Profiler};
+use std::{cell::RefCell, collections::HashMap, sync::OnceLock};
+
+use crate::{
+    execution::{execute_riscv_contract, helper::instruction_gas},
+    setup::{function_symbols, setup_from_elf},
+    trace,
+};
+
+/// Environment variable holding the address a GDB stub listens on for each RISC-V frame.
+///
+/// For debugging contracts locally only: every RISC-V frame blocks until a GDB client connects,
+/// so a node serving RPC or syncing must never run with it set. It is read once per process.
+pub const GDB_LISTEN_ENV: &str = "HYBRID_GDB";
+
+/// Environment variable holding the directory a snapshot of a faulting RISC-V frame is written
+/// to, see `rvemu::snapshot`.
+pub const SNAPSHOT_DIR_ENV: &str = "HYBRID_SNAPSHOT_DIR";
+
+thread_local! {
+    /// Emulators of RISC-V frames waiting on a child call or create, by journal depth. revm
+    /// re-enters the parent frame once the child returns, which resumes its emulator after the
+    /// `ecall`.
+    static SUSPENDED: RefCell<HashMap<usize, Emulator>> = RefCell::new(HashMap::new());
+}
+
+/// Address set in [`GDB_LISTEN_ENV`], if any
+fn gdb_listen_addr() -> Option<&'static str> {
+    static ADDR: OnceLock<Option<String>> = OnceLock::new();
+    ADDR.get_or_init(|| std::env::var(GDB_LISTEN_ENV).ok())
+        .as_deref()
+}
+
+/// Drops the emulators of suspended RISC-V frames. Run before and after every transaction, so
+/// that one aborted while a frame waits on its child neither keeps that emulator alive nor lets
+/// the next transaction resume it.
+pub(crate) fn clear_suspended() {
+    SUSPENDED.with(|suspended| suspended.borrow_mut().clear());
+}
 
 pub fn run_hybrid_interpreter<EVM, ERROR>(
     bytecode: &[u8],
@@ -55,15 +94,62 @@ where
         }
     };
 
-    let mut emulator = match setup_from_elf(code, calldata) {
-        Ok(emulator) => emulator,
-        Err(err) => {
-            return Err(ERROR::from_string(
-                "Error occurred setting up emulator: ".to_string() + &err.to_string(),
-            ));
+    let depth = evm.ctx().journal().depth();
+
+    // `EthFrame::return_result` pushes the outcome of the child frame onto the stack, which a
+    // RISC-V frame otherwise never uses: success (1/0) for calls, the new address for creates
+    let mut emulator = match frame.interpreter.stack.pop() {
+        Ok(outcome) => {
+            let Some(mut emulator) =
+                SUSPENDED.with(|suspended| suspended.borrow_mut().remove(&depth))
+            else {
+                return Err(ERROR::from_string(
+                    "No suspended RISC-V frame to resume".to_string(),
+                ));
+            };
+
+            match Syscall::try_from(emulator.cpu.xregs.read(5) as u8) {
+                Ok(Syscall::Create) => {
+                    last_created_address = Some(Address::from_word(outcome.into()));
+                }
+                // Calls return whether they succeeded in a0
+                _ => emulator.cpu.xregs.write(10, outcome.to::<u64>()),
+            }
+            emulator
+        }
+        Err(_) => {
+            let mut emulator = match setup_from_elf(code, calldata) {
+                Ok(emulator) => emulator,
+                Err(err) => {
+                    return Err(ERROR::from_string(
+                        "Error occurred setting up emulator: ".to_string() + &err.to_string(),
+                    ));
+                }
+            };
+
+            // Setting `HYBRID_GDB=<host:port>` makes every RISC-V frame wait for a GDB client
+            // before running its `main`, see `rvemu::gdb`.
+            if let Some(addr) = gdb_listen_addr() {
+                match GdbStub::listen(addr) {
+                    Ok(stub) => emulator.attach_gdb(stub),
+                    Err(err) => {
+                        return Err(ERROR::from_string(format!(
+                            "Error occurred attaching gdb on {addr}: {err}"
+                        )));
+                    }
+                }
+            }
+            emulator
         }
     };
 
+    // Profiling is requested by debug tracers, see `trace::enable_profiling`. A stripped contract
+    // still profiles, with every frame reported as unknown.
+    if trace::is_profiling() {
+        let symbols = function_symbols(code).unwrap_or_default();
+        emulator.attach_profiler(Profiler::new(symbols).with_cost(instruction_gas));
+    }
+
     let interpreter_action = execute_riscv_contract(
         &mut emulator,
         &mut frame.interpreter,
@@ -72,5 +158,436 @@ where
     )
     .map_err(ERROR::from_string)?;
 
+    if let InterpreterAction::NewFrame(_) = interpreter_action {
+        SUSPENDED.with(|suspended| suspended.borrow_mut().insert(depth, emulator));
+    }
+
     frame.process_next_action(evm, interpreter_action)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        evm::HybridEvm,
+        revm::{
+            context::result::{EVMError, ExecutionResult, Output, ResultAndState},
+            db::{CacheDB, EmptyDB},
+            handler::EthPrecompiles,
+            inspector::NoOpInspector,
+            primitives::{Bytes, TxKind, U256},
+            state::{AccountInfo, Bytecode},
+            Context, ExecuteEvm, MainBuilder, MainContext,
+        },
+    };
+    use core::convert::Infallible;
+    use rvemu::bus::DRAM_BASE;
+
+    // Registers used by the syscall ABI
+    const T0: u32 = 5;
+    const T1: u32 = 6;
+    const T2: u32 = 7;
+    const A0: u32 = 10;
+    const A1: u32 = 11;
+    const A2: u32 = 12;
+    const A3: u32 = 13;
+    const A4: u32 = 14;
+    const A5: u32 = 15;
+    const A6: u32 = 16;
+
+    const ECALL: u32 = 0x73;
+
+    fn addi(rd: u32, rs1: u32, imm: i32) -> u32 {
+        ((imm as u32 & 0xfff) << 20) | (rs1 << 15) | (rd << 7) | 0x13
+    }
+
+    fn lui(rd: u32, imm: u32) -> u32 {
+        (imm << 12) | (rd << 7) | 0x37
+    }
+
+    fn bne(rs1: u32, rs2: u32, offset: i32) -> u32 {
+        let imm = offset as u32;
+        ((imm >> 12 & 1) << 31)
+            | ((imm >> 5 & 0x3f) << 25)
+            | (rs2 << 20)
+            | (rs1 << 15)
+            | (0b001 << 12)
+            | ((imm >> 1 & 0xf) << 8)
+            | ((imm >> 11 & 1) << 7)
+            | 0x63
+    }
+
+    fn auipc(rd: u32) -> u32 {
+        (rd << 7) | 0x17
+    }
+
+    fn sd(rs2: u32, rs1: u32) -> u32 {
+        (rs2 << 20) | (rs1 << 15) | (0b011 << 12) | 0x23
+    }
+
+    /// Code of the contract loaded above the calldata, and where it keeps its data
+    const CODE_BASE: u64 = DRAM_BASE + 0x10_0000;
+    const VALUE_OFFSET: i32 = 0x100;
+    const OUTPUT_OFFSET: i32 = 0x120;
+    const RUNTIME_OFFSET: i32 = 0x100;
+
+    /// Account `0x00..00{id}`, which fits the first address limb of a syscall
+    fn account(id: u8) -> Address {
+        Address::with_last_byte(id)
+    }
+
+    /// A RISC-V contract that CALLs `account(callee)` with `value` and returns the call's
+    /// success flag as a little-endian u64.
+    fn caller_contract(callee: u8, value: U256) -> Bytecode {
+        // empty calldata next to the value, all the gas it can get
+        let call_args = [addi(A4, A3, 0), addi(A5, 0, 0), addi(A6, 0, -1)];
+        riscv_caller(&[], callee, value, call_args)
+    }
+
+    /// A RISC-V contract that runs `prelude`, CALLs `account(callee)` with `value` and the
+    /// calldata and gas set up by `call_args` (a4 to a6), then returns the call's success flag
+    /// as a little-endian u64.
+    fn riscv_caller(prelude: &[u32], callee: u8, value: U256, call_args: [u32; 3]) -> Bytecode {
+        let mut code = prelude.to_vec();
+        // a3 = value pointer
+        let pc = code.len() as i32 * 4;
+        code.extend([
+            auipc(A3),
+            addi(A3, A3, VALUE_OFFSET - pc),
+            addi(A0, 0, callee.into()),
+            addi(A1, 0, 0),
+            addi(A2, 0, 0),
+        ]);
+        code.extend(call_args);
+        code.extend([addi(T0, 0, u8::from(Syscall::Call).into()), ECALL]);
+        // resumed with the success flag in a0
+        let pc = code.len() as i32 * 4;
+        code.extend([
+            auipc(T1),
+            addi(T1, T1, OUTPUT_OFFSET - pc),
+            sd(A0, T1),
+            addi(A0, T1, 0),
+            addi(A1, 0, 8),
+            addi(T0, 0, u8::from(Syscall::Return).into()),
+            ECALL,
+        ]);
+
+        let mut segment = vec![0; OUTPUT_OFFSET as usize + 8];
+        for (i, inst) in code.iter().enumerate() {
+            segment[i * 4..i * 4 + 4].copy_from_slice(&inst.to_le_bytes());
+        }
+        segment[VALUE_OFFSET as usize..OUTPUT_OFFSET as usize]
+            .copy_from_slice(&value.to_le_bytes::<32>());
+
+        let mut code = vec![0xFF];
+        code.extend(elf(&segment));
+        Bytecode::new_raw(code.into())
+    }
+
+    /// A RISC-V contract that CODECOPYs 32 bytes of its own code, from its code size plus
+    /// `offset`, over 32 bytes of `0xAA`, and returns them.
+    fn code_copy_contract(offset: i32) -> Vec<u8> {
+        let mut code = vec![
+            addi(T0, 0, u8::from(Syscall::CodeSize).into()),
+            ECALL,
+            // a1 = code size + offset
+            addi(A1, A0, offset),
+        ];
+        let pc = code.len() as i32 * 4;
+        code.extend([
+            auipc(A0),
+            addi(A0, A0, OUTPUT_OFFSET - pc),
+            addi(A2, 0, 32),
+            addi(T0, 0, u8::from(Syscall::CodeCopy).into()),
+            ECALL,
+        ]);
+        let pc = code.len() as i32 * 4;
+        code.extend([
+            auipc(A0),
+            addi(A0, A0, OUTPUT_OFFSET - pc),
+            addi(A1, 0, 32),
+            addi(T0, 0, u8::from(Syscall::Return).into()),
+            ECALL,
+        ]);
+
+        let mut segment = vec![0; OUTPUT_OFFSET as usize + 32];
+        for (i, inst) in code.iter().enumerate() {
+            segment[i * 4..i * 4 + 4].copy_from_slice(&inst.to_le_bytes());
+        }
+        segment[OUTPUT_OFFSET as usize..].fill(0xAA);
+
+        let mut code = vec![0xFF];
+        code.extend(elf(&segment));
+        code
+    }
+
+    /// Initcode, in the format `cargo hybrid deploy` sends, of a RISC-V constructor that returns
+    /// `runtime` as the code to deploy.
+    fn deployer_initcode(runtime: &[u8]) -> Bytes {
+        let code = [
+            // auipc at the start of the segment
+            auipc(A0),
+            addi(A0, A0, RUNTIME_OFFSET),
+            addi(A1, 0, runtime.len() as i32),
+            addi(T0, 0, u8::from(Syscall::Return).into()),
+            ECALL,
+        ];
+
+        let mut segment = vec![0; RUNTIME_OFFSET as usize + runtime.len()];
+        for (i, inst) in code.iter().enumerate() {
+            segment[i * 4..i * 4 + 4].copy_from_slice(&inst.to_le_bytes());
+        }
+        segment[RUNTIME_OFFSET as usize..].copy_from_slice(runtime);
+
+        let mut bytecode = vec![0xFF];
+        bytecode.extend(elf(&segment));
+
+        // [0xFF][codesize][bytecode], without constructor arguments
+        let mut initcode = vec![0xFF];
+        initcode.extend((bytecode.len() as u32).to_be_bytes());
+        initcode.extend(bytecode);
+        initcode.into()
+    }
+
+    /// A minimal RISC-V executable loading `segment` at [`CODE_BASE`], which is also its entry
+    fn elf(segment: &[u8]) -> Vec<u8> {
+        const EHSIZE: u64 = 64;
+        const PHENTSIZE: u64 = 56;
+
+        let mut elf = Vec::new();
+        // e_ident: 64-bit, little endian, version 1
+        elf.extend([0x7f, b'E', b'L', b'F', 2, 1, 1]);
+        elf.resize(16, 0);
+        elf.extend(2u16.to_le_bytes()); // e_type: executable
+        elf.extend(243u16.to_le_bytes()); // e_machine: RISC-V
+        elf.extend(1u32.to_le_bytes()); // e_version
+        elf.extend(CODE_BASE.to_le_bytes()); // e_entry
+        elf.extend(EHSIZE.to_le_bytes()); // e_phoff
+        elf.extend(0u64.to_le_bytes()); // e_shoff
+        elf.extend(0u32.to_le_bytes()); // e_flags
+        elf.extend((EHSIZE as u16).to_le_bytes()); // e_ehsize
+        elf.extend((PHENTSIZE as u16).to_le_bytes()); // e_phentsize
+        elf.extend(1u16.to_le_bytes()); // e_phnum
+        elf.extend(64u16.to_le_bytes()); // e_shentsize
+        elf.extend(0u16.to_le_bytes()); // e_shnum
+        elf.extend(0u16.to_le_bytes()); // e_shstrndx
+
+        // A single PT_LOAD segment, right after the headers
+        elf.extend(1u32.to_le_bytes()); // p_type
+        elf.extend(7u32.to_le_bytes()); // p_flags: rwx
+        elf.extend((EHSIZE + PHENTSIZE).to_le_bytes()); // p_offset
+        elf.extend(CODE_BASE.to_le_bytes()); // p_vaddr
+        elf.extend(CODE_BASE.to_le_bytes()); // p_paddr
+        elf.extend((segment.len() as u64).to_le_bytes()); // p_filesz
+        elf.extend((segment.len() as u64).to_le_bytes()); // p_memsz
+        elf.extend(8u64.to_le_bytes()); // p_align
+
+        elf.extend(segment);
+        elf
+    }
+
+    fn transact(accounts: &[(Address, U256, Bytecode)], to: Address) -> ResultAndState {
+        try_transact(accounts, to).unwrap()
+    }
+
+    fn try_transact(
+        accounts: &[(Address, U256, Bytecode)],
+        to: Address,
+    ) -> Result<ResultAndState, EVMError<Infallible>> {
+        try_execute(accounts, TxKind::Call(to), Bytes::new())
+    }
+
+    fn try_execute(
+        accounts: &[(Address, U256, Bytecode)],
+        kind: TxKind,
+        data: Bytes,
+    ) -> Result<ResultAndState, EVMError<Infallible>> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        for (address, balance, code) in accounts {
+            db.insert_account_info(
+                *address,
+                AccountInfo {
+                    balance: *balance,
+                    nonce: 1,
+                    code_hash: code.hash_slow(),
+                    code: Some(code.clone()),
+                },
+            );
+        }
+
+        let evm = Context::mainnet()
+            .with_db(db)
+            .modify_tx_chained(|tx| {
+                tx.caller = account(0x10);
+                tx.kind = kind;
+                tx.data = data;
+                tx.gas_limit = 1_000_000;
+            })
+            .build_mainnet_with_inspector(NoOpInspector {})
+            .with_precompiles(EthPrecompiles::default());
+
+        HybridEvm(evm).replay()
+    }
+
+    #[test]
+    fn test_call_result_resumes_caller() {
+        let caller = account(0xc0);
+        // PUSH0 PUSH0 REVERT
+        let reverting = Bytecode::new_raw(Bytes::from_static(&[0x5f, 0x5f, 0xfd]));
+        // STOP
+        let stopping = Bytecode::new_raw(Bytes::from_static(&[0x00]));
+
+        for (callee, success) in [(reverting, 0u64), (stopping, 1)] {
+            let result = transact(
+                &[
+                    (caller, U256::ZERO, caller_contract(0xca, U256::ZERO)),
+                    (account(0xca), U256::ZERO, callee),
+                ],
+                caller,
+            );
+
+            // The caller runs to its RETURN after the child call, whatever its outcome
+            assert!(result.result.is_success(), "{:?}", result.result);
+            assert_eq!(
+                result.result.output().unwrap().as_ref(),
+                success.to_le_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_aborted_transaction_drops_suspended_frames() {
+        let caller = account(0xc0);
+        // Not an ELF, so setting up the callee fails the transaction with the caller suspended
+        let broken = Bytecode::new_raw(Bytes::from_static(&[0xff, 0x00]));
+
+        let result = try_transact(
+            &[
+                (caller, U256::ZERO, caller_contract(0xca, U256::ZERO)),
+                (account(0xca), U256::ZERO, broken),
+            ],
+            caller,
+        );
+
+        assert!(result.is_err());
+        assert!(SUSPENDED.with(|suspended| suspended.borrow().is_empty()));
+    }
+
+    #[test]
+    fn test_send_without_calldata() {
+        let sender = account(0xc0);
+        let recipient = account(0xe0);
+        let balance = U256::from(1_000_000u64);
+        let amount = U256::from(1_000u64);
+        // The syscall `hybrid_contract::send` makes: no calldata at offset 0, only the stipend
+        let send = riscv_caller(
+            &[],
+            0xe0,
+            amount,
+            [addi(A4, 0, 0), addi(A5, 0, 0), addi(A6, 0, 0)],
+        );
+
+        let result = transact(&[(sender, balance, send)], sender);
+
+        assert!(result.result.is_success(), "{:?}", result.result);
+        assert_eq!(result.result.output().unwrap().as_ref(), 1u64.to_le_bytes());
+        assert_eq!(result.state[&sender].info.balance, balance - amount);
+        assert_eq!(result.state[&recipient].info.balance, amount);
+    }
+
+    #[test]
+    fn test_call_gas_after_instructions() {
+        let caller = account(0xc0);
+        // 0x20000 iterations of `addi` and `bne`, 524_288 gas only charged at RETURN
+        let spend_gas = [lui(T2, 0x20), addi(T2, T2, -1), bne(T2, 0, -4)];
+        let caller_code = riscv_caller(
+            &spend_gas,
+            0xca,
+            U256::ZERO,
+            [addi(A4, 0, 0), addi(A5, 0, 0), addi(A6, 0, -1)],
+        );
+        // INVALID, which uses up all the gas forwarded
+        let invalid = Bytecode::new_raw(Bytes::from_static(&[0xfe]));
+
+        let result = transact(
+            &[
+                (caller, U256::ZERO, caller_code),
+                (account(0xca), U256::ZERO, invalid),
+            ],
+            caller,
+        );
+
+        // The caller keeps enough gas to pay for the instructions it ran before the CALL
+        assert!(result.result.is_success(), "{:?}", result.result);
+        assert_eq!(result.result.output().unwrap().as_ref(), 0u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_call_value_above_u64() {
+        let caller = account(0xc0);
+        let recipient = account(0xe0);
+        let balance = U256::from(1) << 70;
+        let value = U256::from(1) << 65;
+
+        let result = transact(&[(caller, balance, caller_contract(0xe0, value))], caller);
+
+        assert!(result.result.is_success(), "{:?}", result.result);
+        assert_eq!(result.result.output().unwrap().as_ref(), 1u64.to_le_bytes());
+        assert_eq!(result.state[&caller].info.balance, balance - value);
+        assert_eq!(result.state[&recipient].info.balance, value);
+    }
+
+    #[test]
+    fn test_immutable_read_back_after_deploy() {
+        // What the constructor of a contract with an `#[immutable]` owner returns: the runtime,
+        // then the owner's word
+        let owner = account(0xab).into_word();
+        let mut runtime = code_copy_contract(-32);
+        runtime.extend(owner.as_slice());
+
+        let deployed = try_execute(&[], TxKind::Create, deployer_initcode(&runtime)).unwrap();
+        let ExecutionResult::Success {
+            output: Output::Create(code, Some(address)),
+            ..
+        } = deployed.result
+        else {
+            panic!("{:?}", deployed.result);
+        };
+        assert_eq!(code.as_ref(), runtime);
+
+        // A later call reads the owner back from the end of its code
+        let code = deployed.state[&address].info.code.clone().unwrap();
+        let result = transact(&[(address, U256::ZERO, code)], address);
+
+        assert!(result.result.is_success(), "{:?}", result.result);
+        assert_eq!(result.result.output().unwrap().as_ref(), owner.as_slice());
+    }
+
+    #[test]
+    fn test_code_copy_past_end_of_code() {
+        let contract = account(0xc0);
+
+        // Entirely past the end: nothing but zeros
+        let result = transact(
+            &[(
+                contract,
+                U256::ZERO,
+                Bytecode::new_raw(code_copy_contract(1).into()),
+            )],
+            contract,
+        );
+        assert!(result.result.is_success(), "{:?}", result.result);
+        assert_eq!(result.result.output().unwrap().as_ref(), [0; 32]);
+
+        // Across the end: the last bytes of the code, then zeros
+        let code = code_copy_contract(-8);
+        let result = transact(
+            &[(contract, U256::ZERO, Bytecode::new_raw(code.clone().into()))],
+            contract,
+        );
+        assert!(result.result.is_success(), "{:?}", result.result);
+        let output = result.result.output().unwrap();
+        assert_eq!(output[..8], code[code.len() - 8..]);
+        assert_eq!(output[8..], [0; 24]);
+    }
+}
@@ -7,5 +7,6 @@ pub mod handler;
 pub mod hybrid_execute;
 pub mod mini_evm_coding;
 pub mod setup;
+pub mod trace;
 pub mod utils;
 pub use reth::revm;
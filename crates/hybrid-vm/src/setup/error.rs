@@ -7,4 +7,7 @@ pub enum Error {
     /// [`goblin`] crate error representation
     #[error(transparent)]
     GoblinError(#[from] goblin::error::Error),
+    /// The contract faulted before reaching `main`
+    #[error("contract startup faulted: {0:?}")]
+    StartupError(rvemu::exception::Exception),
 }
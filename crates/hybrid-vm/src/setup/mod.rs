@@ -1,6 +1,13 @@
 mod error;
 pub use error::{Error, Result};
-use rvemu::{bus::DRAM_BASE, dram::DRAM_SIZE, emulator::Emulator};
+use rvemu::{bus::DRAM_BASE, dram::DRAM_SIZE, emulator::Emulator, profile::Symbol};
+
+/// Size of the heap `hybrid_contract::allocator` reserves in `.bss`
+const CONTRACT_HEAP_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Instructions the runtime startup may run uncharged: an `sd`, `c.addi` and `bltu` per word of
+/// the heap it clears, and the few hundred around them
+const STARTUP_INSTRUCTIONS: u64 = CONTRACT_HEAP_SIZE / 8 * 3 + 4096;
 
 pub fn setup_from_elf(elf_data: &[u8], call_data: &[u8]) -> Result<Emulator> {
     let elf = goblin::elf::Elf::parse(elf_data)?;
@@ -21,6 +28,7 @@ pub fn setup_from_elf(elf_data: &[u8], call_data: &[u8]) -> Result<Emulator> {
 
     emu.initialize_dram(mem);
     emu.initialize_pc(elf.header.e_entry);
+    run_startup(&mut emu, &elf)?;
 
     Ok(emu)
 }
@@ -49,6 +57,47 @@ pub fn setup_from_mini_elf(elf_data: &[u8], call_data: &[u8]) -> Result<Emulator
     Ok(emu)
 }
 
+/// Returns the demangled function symbols of a contract ELF, for profiling.
+pub fn function_symbols(elf_data: &[u8]) -> Result<Vec<Symbol>> {
+    let elf = goblin::elf::Elf::parse(elf_data)?;
+
+    Ok(elf
+        .syms
+        .iter()
+        .filter(|sym| sym.st_type() == goblin::elf::sym::STT_FUNC && sym.st_value != 0)
+        .filter_map(|sym| {
+            let name = elf.strtab.get_at(sym.st_name)?;
+            Some(Symbol {
+                name: format!("{:#}", rustc_demangle::demangle(name)),
+                address: sym.st_value,
+                size: sym.st_size,
+            })
+        })
+        .collect())
+}
+
+/// Runs the riscv-rt startup of a contract up to its `main`, without counting the instructions.
+///
+/// The startup clears `.bss`, almost all of it the heap, while every frame is handed zeroed
+/// memory already. Whatever runs past [`STARTUP_INSTRUCTIONS`], or every instruction of a
+/// contract without a `main` symbol, is charged with the contract.
+fn run_startup(emu: &mut Emulator, elf: &goblin::elf::Elf) -> Result<()> {
+    let Some(main) = elf.syms.iter().find(|sym| {
+        sym.st_type() == goblin::elf::sym::STT_FUNC
+            && elf.strtab.get_at(sym.st_name) == Some("main")
+    }) else {
+        return Ok(());
+    };
+
+    for _ in 0..STARTUP_INSTRUCTIONS {
+        if emu.cpu.pc == main.st_value {
+            break;
+        }
+        emu.cpu.eexecute().map_err(Error::StartupError)?;
+    }
+    Ok(())
+}
+
 fn load_sections(mem: &mut Vec<u8>, elf: &goblin::elf::Elf, elf_data: &[u8]) {
     for ph in &elf.program_headers {
         if ph.p_type == goblin::elf::program_header::PT_LOAD {
@@ -71,3 +120,24 @@ fn load_sections(mem: &mut Vec<u8>, elf: &goblin::elf::Elf, elf_data: &[u8]) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup_stops_at_main() {
+        // Built from `bins/mini-evm-interpreter`, with the same riscv-rt startup as contracts
+        let elf_data: &[u8] = include_bytes!("../../mini-evm-interpreter");
+        let main = function_symbols(elf_data)
+            .unwrap()
+            .into_iter()
+            .find(|sym| sym.name == "main")
+            .unwrap();
+
+        let emu = setup_from_elf(elf_data, &[]).unwrap();
+
+        assert_eq!(emu.cpu.pc, main.address);
+        assert!(emu.cpu.inst_counter.is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! Recording of RISC-V execution for debug tracers.
+//!
+//! RISC-V frames never go through revm's per-instruction inspector hooks, so a tracer cannot
+//! observe them the way it observes EVM opcodes. Instead, while recording is enabled on the
+//! current thread, the RISC-V interpreter logs every syscall it services and closes a
+//! [`RiscvSegment`] each time the emulator hands control back to revm (return, revert, call or
+//! create). Inspectors run on the same thread as the interpreter and drain those segments from
+//! their `call`/`create` hooks.
+//!
+//! With profiling enabled, each segment additionally carries the folded call stacks of the
+//! contract's functions, see [`rvemu::profile`].
+use hybrid_syscalls::Syscall;
+use reth::revm::primitives::Address;
+use rvemu::{emulator::Emulator, profile::Cost};
+use std::{cell::RefCell, collections::BTreeMap};
+
+use crate::execution::helper::hybrid_gas_used;
+
+/// A contiguous run of the RISC-V emulator, from (re)entry until it yields back to revm.
+#[derive(Clone, Default)]
+pub struct RiscvSegment {
+    /// Address of the contract being executed.
+    pub address: Address,
+    /// Syscalls serviced during this run, in order.
+    pub syscalls: Vec<Syscall>,
+    /// Number of RISC-V instructions retired.
+    pub instructions: u64,
+    /// Gas derived from the instruction mix (see [`hybrid_gas_used`]).
+    pub gas_used: u64,
+    /// Instructions and gas per call stack, keyed by `;`-separated function names. Empty unless
+    /// profiling is enabled.
+    pub folded_stacks: BTreeMap<String, Cost>,
+}
+
+#[derive(Default)]
+struct Recorder {
+    profiling: bool,
+    pending_syscalls: Vec<Syscall>,
+    segments: Vec<RiscvSegment>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Enables recording on the current thread. Calling it while already recording is a no-op.
+pub fn start_recording() {
+    RECORDER.with(|recorder| {
+        recorder.borrow_mut().get_or_insert_with(Recorder::default);
+    });
+}
+
+/// Disables recording on the current thread, returning any segments not yet drained.
+pub fn stop_recording() -> Vec<RiscvSegment> {
+    RECORDER.with(|recorder| {
+        recorder
+            .borrow_mut()
+            .take()
+            .map(|recorder| recorder.segments)
+            .unwrap_or_default()
+    })
+}
+
+/// Enables profiling of RISC-V frames while recording on the current thread. Has no effect
+/// unless recording is enabled.
+pub fn enable_profiling() {
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.profiling = true;
+        }
+    });
+}
+
+/// Returns whether RISC-V frames should be profiled on the current thread.
+pub fn is_profiling() -> bool {
+    RECORDER.with(|recorder| {
+        recorder
+            .borrow()
+            .as_ref()
+            .is_some_and(|recorder| recorder.profiling)
+    })
+}
+
+/// Returns whether recording is enabled on the current thread.
+pub fn is_recording() -> bool {
+    RECORDER.with(|recorder| recorder.borrow().is_some())
+}
+
+/// Drains the segments recorded so far, leaving recording enabled.
+pub fn take_segments() -> Vec<RiscvSegment> {
+    RECORDER.with(|recorder| {
+        recorder
+            .borrow_mut()
+            .as_mut()
+            .map(|recorder| std::mem::take(&mut recorder.segments))
+            .unwrap_or_default()
+    })
+}
+
+/// Logs a serviced syscall against the segment currently being executed. Called by the RISC-V
+/// interpreter.
+pub fn record_syscall(syscall: Syscall) {
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            recorder.pending_syscalls.push(syscall);
+        }
+    });
+}
+
+/// Closes the segment currently being executed, detaching the emulator's profiler if any. Called
+/// by the RISC-V interpreter each time it yields back to revm, with the instruction counts the
+/// emulator had already retired when the segment started.
+pub fn record_segment(address: Address, emu: &mut Emulator, retired: &BTreeMap<String, u64>) {
+    let profiler = emu.detach_profiler();
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            let syscalls = std::mem::take(&mut recorder.pending_syscalls);
+            let inst_counter: BTreeMap<String, u64> = emu
+                .cpu
+                .inst_counter
+                .iter()
+                .map(|(inst, count)| {
+                    let before = retired.get(inst).copied().unwrap_or_default();
+                    (inst.clone(), count - before)
+                })
+                .collect();
+            recorder.segments.push(RiscvSegment {
+                address,
+                syscalls,
+                instructions: inst_counter.values().sum(),
+                gas_used: hybrid_gas_used(&inst_counter),
+                folded_stacks: profiler
+                    .map(|profiler| profiler.folded_stacks())
+                    .unwrap_or_default(),
+            });
+        }
+    });
+}
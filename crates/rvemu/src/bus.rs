@@ -53,7 +53,7 @@ pub struct Bus {
     pub plic: Plic,
     pub uart: Uart,
     pub virtio: Virtio,
-    dram: Dram,
+    pub(crate) dram: Dram,
     pub rom: Rom,
 }
 
@@ -107,6 +107,8 @@ impl Bus {
 
     pub fn get_dram_slice(&mut self, range: Range<u64>) -> Result<&mut [u8], Exception> {
         let range = (range.start - DRAM_BASE) as usize..(range.end as usize - DRAM_BASE as usize);
+        // The caller may write through the slice.
+        self.dram.mark_dirty(range.clone());
         self.dram
             .dram
             .get_mut(range)
@@ -119,6 +121,7 @@ impl Bus {
         if end > self.dram.dram.len() || end - start != data.len() {
             return Err(Exception::StoreAMOAccessFault);
         }
+        self.dram.mark_dirty(start..end);
         let dram_slice = self
             .dram
             .dram
@@ -225,12 +225,12 @@ pub struct Cpu {
     /// System bus.
     pub bus: Bus,
     /// SV39 paging flag.
-    enable_paging: bool,
+    pub(crate) enable_paging: bool,
     /// Physical page number (PPN) × PAGE_SIZE (4096).
-    page_table: u64,
+    pub(crate) page_table: u64,
     /// A set of bytes that subsumes the bytes in the addressed word used in
     /// load-reserved/store-conditional instructions.
-    reservation_set: Vec<u64>,
+    pub(crate) reservation_set: Vec<u64>,
     /// Idle state. True when WFI is called, and becomes false when an interrupt happens.
     pub idle: bool,
     /// Counter of each instructions for debug.
@@ -172,7 +172,7 @@ pub const MEIP_BIT: u64 = 1 << 11;
 /// The state to contains all the CSRs.
 #[derive(Debug)]
 pub struct State {
-    csrs: [u64; CSR_SIZE],
+    pub(crate) csrs: [u64; CSR_SIZE],
 }
 
 impl fmt::Display for State {
@@ -0,0 +1,813 @@
+//! The decode module identifies RV64GC instructions and extracts their operands.
+//!
+//! The disassembler and the profiler print and classify what [`decode`] returns, so they always
+//! agree on what an encoding means. Field extraction follows `Cpu::execute_general` and
+//! `Cpu::execute_compressed`, and mnemonics are the names the CPU records in `Cpu::inst_counter`.
+
+macro_rules! ops {
+    ($($op:ident => $name:literal,)*) => {
+        /// An instruction implemented by the CPU.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Op {
+            $($op,)*
+        }
+
+        impl Op {
+            /// The mnemonic, e.g. `addi` or `c.lw`. This is also the key of the instruction in
+            /// `Cpu::inst_counter`.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Op::$op => $name,)*
+                }
+            }
+        }
+    };
+}
+
+ops! {
+    // RV64C.
+    CAddi4spn => "c.addi4spn",
+    CFld => "c.fld",
+    CLw => "c.lw",
+    CLd => "c.ld",
+    CFsd => "c.fsd",
+    CSw => "c.sw",
+    CSd => "c.sd",
+    CAddi => "c.addi",
+    CAddiw => "c.addiw",
+    CLi => "c.li",
+    CAddi16sp => "c.addi16sp",
+    CLui => "c.lui",
+    CSrli => "c.srli",
+    CSrai => "c.srai",
+    CAndi => "c.andi",
+    CSub => "c.sub",
+    CXor => "c.xor",
+    COr => "c.or",
+    CAnd => "c.and",
+    CSubw => "c.subw",
+    CAddw => "c.addw",
+    CJ => "c.j",
+    CBeqz => "c.beqz",
+    CBnez => "c.bnez",
+    CSlli => "c.slli",
+    CFldsp => "c.fldsp",
+    CLwsp => "c.lwsp",
+    CLdsp => "c.ldsp",
+    CJr => "c.jr",
+    CMv => "c.mv",
+    CEbreak => "c.ebreak",
+    CJalr => "c.jalr",
+    CAdd => "c.add",
+    CFsdsp => "c.fsdsp",
+    CSwsp => "c.swsp",
+    CSdsp => "c.sdsp",
+    // RV64I.
+    Lb => "lb",
+    Lh => "lh",
+    Lw => "lw",
+    Ld => "ld",
+    Lbu => "lbu",
+    Lhu => "lhu",
+    Lwu => "lwu",
+    Fence => "fence",
+    FenceI => "fence.i",
+    Addi => "addi",
+    Slli => "slli",
+    Slti => "slti",
+    Sltiu => "sltiu",
+    Xori => "xori",
+    Srli => "srli",
+    Srai => "srai",
+    Ori => "ori",
+    Andi => "andi",
+    Auipc => "auipc",
+    Addiw => "addiw",
+    Slliw => "slliw",
+    Srliw => "srliw",
+    Sraiw => "sraiw",
+    Sb => "sb",
+    Sh => "sh",
+    Sw => "sw",
+    Sd => "sd",
+    Add => "add",
+    Sub => "sub",
+    Sll => "sll",
+    Slt => "slt",
+    Sltu => "sltu",
+    Xor => "xor",
+    Srl => "srl",
+    Sra => "sra",
+    Or => "or",
+    And => "and",
+    Lui => "lui",
+    Addw => "addw",
+    Subw => "subw",
+    Sllw => "sllw",
+    Srlw => "srlw",
+    Sraw => "sraw",
+    Beq => "beq",
+    Bne => "bne",
+    Blt => "blt",
+    Bge => "bge",
+    Bltu => "bltu",
+    Bgeu => "bgeu",
+    Jalr => "jalr",
+    Jal => "jal",
+    // RV64M.
+    Mul => "mul",
+    Mulh => "mulh",
+    Mulhsu => "mulhsu",
+    Mulhu => "mulhu",
+    Div => "div",
+    Divu => "divu",
+    Rem => "rem",
+    Remu => "remu",
+    Mulw => "mulw",
+    Divw => "divw",
+    Divuw => "divuw",
+    Remw => "remw",
+    Remuw => "remuw",
+    // RV64A.
+    AmoaddW => "amoadd.w",
+    AmoaddD => "amoadd.d",
+    AmoswapW => "amoswap.w",
+    AmoswapD => "amoswap.d",
+    LrW => "lr.w",
+    LrD => "lr.d",
+    ScW => "sc.w",
+    ScD => "sc.d",
+    AmoxorW => "amoxor.w",
+    AmoxorD => "amoxor.d",
+    AmoorW => "amoor.w",
+    AmoorD => "amoor.d",
+    AmoandW => "amoand.w",
+    AmoandD => "amoand.d",
+    AmominW => "amomin.w",
+    AmominD => "amomin.d",
+    AmomaxW => "amomax.w",
+    AmomaxD => "amomax.d",
+    AmominuW => "amominu.w",
+    AmominuD => "amominu.d",
+    AmomaxuW => "amomaxu.w",
+    AmomaxuD => "amomaxu.d",
+    // RV64F and RV64D.
+    Flw => "flw",
+    Fld => "fld",
+    Fsw => "fsw",
+    Fsd => "fsd",
+    FmaddS => "fmadd.s",
+    FmaddD => "fmadd.d",
+    FmsubS => "fmsub.s",
+    FmsubD => "fmsub.d",
+    FnmaddS => "fnmadd.s",
+    FnmaddD => "fnmadd.d",
+    FnmsubS => "fnmsub.s",
+    FnmsubD => "fnmsub.d",
+    FaddS => "fadd.s",
+    FaddD => "fadd.d",
+    FsubS => "fsub.s",
+    FsubD => "fsub.d",
+    FmulS => "fmul.s",
+    FmulD => "fmul.d",
+    FdivS => "fdiv.s",
+    FdivD => "fdiv.d",
+    FsgnjS => "fsgnj.s",
+    FsgnjnS => "fsgnjn.s",
+    FsgnjxS => "fsgnjx.s",
+    FsgnjD => "fsgnj.d",
+    FsgnjnD => "fsgnjn.d",
+    FsgnjxD => "fsgnjx.d",
+    FminS => "fmin.s",
+    FmaxS => "fmax.s",
+    FminD => "fmin.d",
+    FmaxD => "fmax.d",
+    FcvtSD => "fcvt.s.d",
+    FcvtDS => "fcvt.d.s",
+    FsqrtS => "fsqrt.s",
+    FsqrtD => "fsqrt.d",
+    FleS => "fle.s",
+    FltS => "flt.s",
+    FeqS => "feq.s",
+    FleD => "fle.d",
+    FltD => "flt.d",
+    FeqD => "feq.d",
+    FcvtWS => "fcvt.w.s",
+    FcvtWuS => "fcvt.wu.s",
+    FcvtLS => "fcvt.l.s",
+    FcvtLuS => "fcvt.lu.s",
+    FcvtWD => "fcvt.w.d",
+    FcvtWuD => "fcvt.wu.d",
+    FcvtLD => "fcvt.l.d",
+    FcvtLuD => "fcvt.lu.d",
+    FcvtSW => "fcvt.s.w",
+    FcvtSWu => "fcvt.s.wu",
+    FcvtSL => "fcvt.s.l",
+    FcvtSLu => "fcvt.s.lu",
+    FcvtDW => "fcvt.d.w",
+    FcvtDWu => "fcvt.d.wu",
+    FcvtDL => "fcvt.d.l",
+    FcvtDLu => "fcvt.d.lu",
+    FmvXW => "fmv.x.w",
+    FclassS => "fclass.s",
+    FmvXD => "fmv.x.d",
+    FclassD => "fclass.d",
+    FmvWX => "fmv.w.x",
+    FmvDX => "fmv.d.x",
+    // Privileged, RVZicsr and RVZifencei.
+    Ecall => "ecall",
+    Ebreak => "ebreak",
+    Uret => "uret",
+    Sret => "sret",
+    Mret => "mret",
+    Wfi => "wfi",
+    SfenceVma => "sfence.vma",
+    HfenceBvma => "hfence.bvma",
+    HfenceGvma => "hfence.gvma",
+    Csrrw => "csrrw",
+    Csrrs => "csrrs",
+    Csrrc => "csrrc",
+    Csrrwi => "csrrwi",
+    Csrrsi => "csrrsi",
+    Csrrci => "csrrci",
+}
+
+/// A decoded instruction.
+///
+/// Compressed instructions are decoded to the operands of the instruction they expand to, e.g.
+/// `c.lwsp rd, offset` has `rs1` = 2 (`sp`) and `c.jalr rs1` has `rd` = 1 (`ra`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded {
+    pub op: Op,
+    /// The destination register.
+    pub rd: u64,
+    /// The first source register. This is also the 5-bit immediate of `csrr*i`.
+    pub rs1: u64,
+    /// The second source register.
+    pub rs2: u64,
+    /// The third source register of the fused multiply-add instructions.
+    pub rs3: u64,
+    /// The immediate, sign-extended to 64 bits when the encoding is signed: the offset of a load,
+    /// store, branch or jump, the shift amount of an immediate shift, the shifted upper immediate
+    /// of `lui`, `auipc` and `c.lui`, and the CSR address of a CSR instruction.
+    pub imm: u64,
+}
+
+impl Decoded {
+    /// The size of the instruction in bytes, 2 or 4.
+    pub fn size(&self) -> u8 {
+        if self.op.name().starts_with("c.") {
+            2
+        } else {
+            4
+        }
+    }
+}
+
+/// Decode an instruction. It is a 16-bit compressed instruction if its two lowest bits are not
+/// `0b11`, and a 32-bit one otherwise. Returns `None` if the CPU does not implement the encoding.
+pub fn decode(inst: u64) -> Option<Decoded> {
+    if inst & 0b11 != 0b11 {
+        decode_compressed(inst & 0xffff)
+    } else {
+        decode_general(inst & 0xffffffff)
+    }
+}
+
+/// Sign extend the lowest `bits` bits of `value`.
+fn sext(value: u64, bits: u32) -> u64 {
+    let shift = 64 - bits;
+    (((value << shift) as i64) >> shift) as u64
+}
+
+fn decode_compressed(inst: u64) -> Option<Decoded> {
+    let opcode = inst & 0x3;
+    let funct3 = (inst >> 13) & 0x7;
+
+    // Full-size register fields.
+    let rd = (inst >> 7) & 0x1f;
+    let rs2 = (inst >> 2) & 0x1f;
+    // Compressed instructions have 3-bit fields for popular registers, which correspond to
+    // registers x8 to x15: rd'/rs2' in inst[4:2] and rs1'/rd' in inst[9:7].
+    let rd_p = ((inst >> 2) & 0x7) + 8;
+    let rs1_p = ((inst >> 7) & 0x7) + 8;
+
+    // imm[5|4:0] = inst[12|6:2]
+    let imm6 = ((inst >> 7) & 0x20) | ((inst >> 2) & 0x1f);
+    // offset[5:3|2|6] = inst[12:10|6|5]
+    let offset_w = ((inst << 1) & 0x40) | ((inst >> 7) & 0x38) | ((inst >> 4) & 0x4);
+    // offset[5:3|7:6] = inst[12:10|6:5]
+    let offset_d = ((inst << 1) & 0xc0) | ((inst >> 7) & 0x38);
+    // offset[8|4:3|7:6|2:1|5] = inst[12|11:10|6:5|4:3|2]
+    let offset_b = sext(
+        ((inst >> 4) & 0x100)
+            | ((inst << 1) & 0xc0)
+            | ((inst << 3) & 0x20)
+            | ((inst >> 7) & 0x18)
+            | ((inst >> 2) & 0x6),
+        9,
+    );
+
+    let decoded = |op, rd, rs1, rs2, imm| {
+        Some(Decoded {
+            op,
+            rd,
+            rs1,
+            rs2,
+            rs3: 0,
+            imm,
+        })
+    };
+
+    match (opcode, funct3) {
+        // Quadrant 0.
+        (0, 0x0) => {
+            // nzuimm[5:4|9:6|2|3] = inst[12:11|10:7|6|5]
+            let nzuimm = ((inst >> 1) & 0x3c0)
+                | ((inst >> 7) & 0x30)
+                | ((inst >> 2) & 0x8)
+                | ((inst >> 4) & 0x4);
+            if nzuimm == 0 {
+                return None;
+            }
+            decoded(Op::CAddi4spn, rd_p, 2, 0, nzuimm)
+        }
+        (0, 0x1) => decoded(Op::CFld, rd_p, rs1_p, 0, offset_d),
+        (0, 0x2) => decoded(Op::CLw, rd_p, rs1_p, 0, offset_w),
+        (0, 0x3) => decoded(Op::CLd, rd_p, rs1_p, 0, offset_d),
+        (0, 0x5) => decoded(Op::CFsd, 0, rs1_p, rd_p, offset_d),
+        (0, 0x6) => decoded(Op::CSw, 0, rs1_p, rd_p, offset_w),
+        (0, 0x7) => decoded(Op::CSd, 0, rs1_p, rd_p, offset_d),
+        // Quadrant 1.
+        (1, 0x0) => decoded(Op::CAddi, rd, rd, 0, sext(imm6, 6)),
+        (1, 0x1) => decoded(Op::CAddiw, rd, rd, 0, sext(imm6, 6)),
+        (1, 0x2) => decoded(Op::CLi, rd, 0, 0, sext(imm6, 6)),
+        (1, 0x3) if rd == 2 => {
+            // nzimm[9|4|6|8:7|5] = inst[12|6|5|4:3|2]
+            let nzimm = ((inst >> 3) & 0x200)
+                | ((inst >> 2) & 0x10)
+                | ((inst << 1) & 0x40)
+                | ((inst << 4) & 0x180)
+                | ((inst << 3) & 0x20);
+            decoded(Op::CAddi16sp, 2, 2, 0, sext(nzimm, 10))
+        }
+        (1, 0x3) => {
+            // nzimm[17|16:12] = inst[12|6:2]
+            let nzimm = ((inst << 5) & 0x20000) | ((inst << 10) & 0x1f000);
+            decoded(Op::CLui, rd, 0, 0, sext(nzimm, 18))
+        }
+        (1, 0x4) => match (inst >> 10) & 0x3 {
+            0x0 => decoded(Op::CSrli, rs1_p, rs1_p, 0, imm6),
+            0x1 => decoded(Op::CSrai, rs1_p, rs1_p, 0, imm6),
+            0x2 => decoded(Op::CAndi, rs1_p, rs1_p, 0, sext(imm6, 6)),
+            _ => {
+                let op = match ((inst >> 12) & 0x1, (inst >> 5) & 0x3) {
+                    (0x0, 0x0) => Op::CSub,
+                    (0x0, 0x1) => Op::CXor,
+                    (0x0, 0x2) => Op::COr,
+                    (0x0, 0x3) => Op::CAnd,
+                    (0x1, 0x0) => Op::CSubw,
+                    (0x1, 0x1) => Op::CAddw,
+                    _ => return None,
+                };
+                decoded(op, rs1_p, rs1_p, rd_p, 0)
+            }
+        },
+        (1, 0x5) => {
+            // offset[11|4|9:8|10|6|7|3:1|5] = inst[12|11|10:9|8|7|6|5:3|2]
+            let offset = ((inst >> 1) & 0x800)
+                | ((inst << 2) & 0x400)
+                | ((inst >> 1) & 0x300)
+                | ((inst << 1) & 0x80)
+                | ((inst >> 1) & 0x40)
+                | ((inst << 3) & 0x20)
+                | ((inst >> 7) & 0x10)
+                | ((inst >> 2) & 0xe);
+            decoded(Op::CJ, 0, 0, 0, sext(offset, 12))
+        }
+        (1, 0x6) => decoded(Op::CBeqz, 0, rs1_p, 0, offset_b),
+        (1, 0x7) => decoded(Op::CBnez, 0, rs1_p, 0, offset_b),
+        // Quadrant 2.
+        (2, 0x0) => decoded(Op::CSlli, rd, rd, 0, imm6),
+        (2, 0x1) => {
+            // offset[5|4:3|8:6] = inst[12|6:5|4:2]
+            let offset = ((inst << 4) & 0x1c0) | ((inst >> 7) & 0x20) | ((inst >> 2) & 0x18);
+            decoded(Op::CFldsp, rd, 2, 0, offset)
+        }
+        (2, 0x2) => {
+            // offset[5|4:2|7:6] = inst[12|6:4|3:2]
+            let offset = ((inst << 4) & 0xc0) | ((inst >> 7) & 0x20) | ((inst >> 2) & 0x1c);
+            decoded(Op::CLwsp, rd, 2, 0, offset)
+        }
+        (2, 0x3) => {
+            // offset[5|4:3|8:6] = inst[12|6:5|4:2]
+            let offset = ((inst << 4) & 0x1c0) | ((inst >> 7) & 0x20) | ((inst >> 2) & 0x18);
+            decoded(Op::CLdsp, rd, 2, 0, offset)
+        }
+        (2, 0x4) => match ((inst >> 12) & 0x1, rd, rs2) {
+            // c.jr and c.jalr keep their source register in inst[11:7].
+            (0, _, 0) => decoded(Op::CJr, 0, rd, 0, 0),
+            (0, _, _) => decoded(Op::CMv, rd, 0, rs2, 0),
+            (_, 0, 0) => decoded(Op::CEbreak, 0, 0, 0, 0),
+            (_, _, 0) => decoded(Op::CJalr, 1, rd, 0, 0),
+            (_, _, _) => decoded(Op::CAdd, rd, rd, rs2, 0),
+        },
+        (2, 0x5) => {
+            // offset[5:3|8:6] = inst[12:10|9:7]
+            let offset = ((inst >> 1) & 0x1c0) | ((inst >> 7) & 0x38);
+            decoded(Op::CFsdsp, 0, 2, rs2, offset)
+        }
+        (2, 0x6) => {
+            // offset[5:2|7:6] = inst[12:9|8:7]
+            let offset = ((inst >> 1) & 0xc0) | ((inst >> 7) & 0x3c);
+            decoded(Op::CSwsp, 0, 2, rs2, offset)
+        }
+        (2, 0x7) => {
+            // offset[5:3|8:6] = inst[12:10|9:7]
+            let offset = ((inst >> 1) & 0x1c0) | ((inst >> 7) & 0x38);
+            decoded(Op::CSdsp, 0, 2, rs2, offset)
+        }
+        _ => None,
+    }
+}
+
+fn decode_general(inst: u64) -> Option<Decoded> {
+    let opcode = inst & 0x0000007f;
+    let rd = (inst & 0x00000f80) >> 7;
+    let rs1 = (inst & 0x000f8000) >> 15;
+    let rs2 = (inst & 0x01f00000) >> 20;
+    let rs3 = (inst & 0xf8000000) >> 27;
+    let funct3 = (inst & 0x00007000) >> 12;
+    let funct7 = (inst & 0xfe000000) >> 25;
+
+    // imm[11:0] = inst[31:20]
+    let imm_i = sext(inst >> 20, 12);
+    // imm[11:5|4:0] = inst[31:25|11:7]
+    let imm_s = sext(((inst >> 20) & 0xfe0) | ((inst >> 7) & 0x1f), 12);
+    // imm[12|10:5|4:1|11] = inst[31|30:25|11:8|7]
+    let imm_b = sext(
+        ((inst >> 19) & 0x1000)
+            | ((inst << 4) & 0x800)
+            | ((inst >> 20) & 0x7e0)
+            | ((inst >> 7) & 0x1e),
+        13,
+    );
+    // imm[31:12] = inst[31:12]
+    let imm_u = sext(inst & 0xfffff000, 32);
+    // imm[20|10:1|11|19:12] = inst[31|30:21|20|19:12]
+    let imm_j = sext(
+        ((inst >> 11) & 0x100000)
+            | (inst & 0xff000)
+            | ((inst >> 9) & 0x800)
+            | ((inst >> 20) & 0x7fe),
+        21,
+    );
+
+    let decoded = |op, imm| {
+        Some(Decoded {
+            op,
+            rd,
+            rs1,
+            rs2,
+            rs3,
+            imm,
+        })
+    };
+
+    match opcode {
+        0x03 => {
+            let op = match funct3 {
+                0x0 => Op::Lb,
+                0x1 => Op::Lh,
+                0x2 => Op::Lw,
+                0x3 => Op::Ld,
+                0x4 => Op::Lbu,
+                0x5 => Op::Lhu,
+                0x6 => Op::Lwu,
+                _ => return None,
+            };
+            decoded(op, imm_i)
+        }
+        0x07 => match funct3 {
+            0x2 => decoded(Op::Flw, imm_i),
+            0x3 => decoded(Op::Fld, imm_i),
+            _ => None,
+        },
+        0x0f => match funct3 {
+            0x0 => decoded(Op::Fence, 0),
+            0x1 => decoded(Op::FenceI, 0),
+            _ => None,
+        },
+        0x13 => {
+            // The shift amount is 6 bits in RV64I.
+            let shamt = (inst >> 20) & 0x3f;
+            match funct3 {
+                0x0 => decoded(Op::Addi, imm_i),
+                0x1 => decoded(Op::Slli, shamt),
+                0x2 => decoded(Op::Slti, imm_i),
+                0x3 => decoded(Op::Sltiu, imm_i),
+                0x4 => decoded(Op::Xori, imm_i),
+                0x5 => match funct7 >> 1 {
+                    0x00 => decoded(Op::Srli, shamt),
+                    0x10 => decoded(Op::Srai, shamt),
+                    _ => None,
+                },
+                0x6 => decoded(Op::Ori, imm_i),
+                0x7 => decoded(Op::Andi, imm_i),
+                _ => None,
+            }
+        }
+        0x17 => decoded(Op::Auipc, imm_u),
+        0x1b => {
+            // "SLLIW, SRLIW, and SRAIW encodings with imm[5] ̸= 0 are reserved."
+            let shamt = (inst >> 20) & 0x1f;
+            match (funct3, funct7) {
+                (0x0, _) => decoded(Op::Addiw, imm_i),
+                (0x1, _) => decoded(Op::Slliw, shamt),
+                (0x5, 0x00) => decoded(Op::Srliw, shamt),
+                (0x5, 0x20) => decoded(Op::Sraiw, shamt),
+                _ => None,
+            }
+        }
+        0x23 => {
+            let op = match funct3 {
+                0x0 => Op::Sb,
+                0x1 => Op::Sh,
+                0x2 => Op::Sw,
+                0x3 => Op::Sd,
+                _ => return None,
+            };
+            decoded(op, imm_s)
+        }
+        0x27 => match funct3 {
+            0x2 => decoded(Op::Fsw, imm_s),
+            0x3 => decoded(Op::Fsd, imm_s),
+            _ => None,
+        },
+        0x2f => {
+            // The `aq` and `rl` bits in funct7[1:0] are ignored.
+            let op = match (funct3, funct7 >> 2) {
+                (0x2, 0x00) => Op::AmoaddW,
+                (0x3, 0x00) => Op::AmoaddD,
+                (0x2, 0x01) => Op::AmoswapW,
+                (0x3, 0x01) => Op::AmoswapD,
+                (0x2, 0x02) => Op::LrW,
+                (0x3, 0x02) => Op::LrD,
+                (0x2, 0x03) => Op::ScW,
+                (0x3, 0x03) => Op::ScD,
+                (0x2, 0x04) => Op::AmoxorW,
+                (0x3, 0x04) => Op::AmoxorD,
+                (0x2, 0x08) => Op::AmoorW,
+                (0x3, 0x08) => Op::AmoorD,
+                (0x2, 0x0c) => Op::AmoandW,
+                (0x3, 0x0c) => Op::AmoandD,
+                (0x2, 0x10) => Op::AmominW,
+                (0x3, 0x10) => Op::AmominD,
+                (0x2, 0x14) => Op::AmomaxW,
+                (0x3, 0x14) => Op::AmomaxD,
+                (0x2, 0x18) => Op::AmominuW,
+                (0x3, 0x18) => Op::AmominuD,
+                (0x2, 0x1c) => Op::AmomaxuW,
+                (0x3, 0x1c) => Op::AmomaxuD,
+                _ => return None,
+            };
+            decoded(op, 0)
+        }
+        0x33 => {
+            let op = match (funct3, funct7) {
+                (0x0, 0x00) => Op::Add,
+                (0x0, 0x01) => Op::Mul,
+                (0x0, 0x20) => Op::Sub,
+                (0x1, 0x00) => Op::Sll,
+                (0x1, 0x01) => Op::Mulh,
+                (0x2, 0x00) => Op::Slt,
+                (0x2, 0x01) => Op::Mulhsu,
+                (0x3, 0x00) => Op::Sltu,
+                (0x3, 0x01) => Op::Mulhu,
+                (0x4, 0x00) => Op::Xor,
+                (0x4, 0x01) => Op::Div,
+                (0x5, 0x00) => Op::Srl,
+                (0x5, 0x01) => Op::Divu,
+                (0x5, 0x20) => Op::Sra,
+                (0x6, 0x00) => Op::Or,
+                (0x6, 0x01) => Op::Rem,
+                (0x7, 0x00) => Op::And,
+                (0x7, 0x01) => Op::Remu,
+                _ => return None,
+            };
+            decoded(op, 0)
+        }
+        0x37 => decoded(Op::Lui, imm_u),
+        0x3b => {
+            let op = match (funct3, funct7) {
+                (0x0, 0x00) => Op::Addw,
+                (0x0, 0x01) => Op::Mulw,
+                (0x0, 0x20) => Op::Subw,
+                (0x1, 0x00) => Op::Sllw,
+                (0x4, 0x01) => Op::Divw,
+                (0x5, 0x00) => Op::Srlw,
+                (0x5, 0x01) => Op::Divuw,
+                (0x5, 0x20) => Op::Sraw,
+                (0x6, 0x01) => Op::Remw,
+                (0x7, 0x01) => Op::Remuw,
+                _ => return None,
+            };
+            decoded(op, 0)
+        }
+        0x43 | 0x47 | 0x4b | 0x4f => {
+            // The format is in funct2 = inst[26:25], 0 for single and 1 for double precision.
+            let op = match (opcode, (inst & 0x06000000) >> 25) {
+                (0x43, 0x0) => Op::FmaddS,
+                (0x43, 0x1) => Op::FmaddD,
+                (0x47, 0x0) => Op::FmsubS,
+                (0x47, 0x1) => Op::FmsubD,
+                (0x4b, 0x0) => Op::FnmaddS,
+                (0x4b, 0x1) => Op::FnmaddD,
+                (0x4f, 0x0) => Op::FnmsubS,
+                (0x4f, 0x1) => Op::FnmsubD,
+                _ => return None,
+            };
+            decoded(op, 0)
+        }
+        0x53 => {
+            let op = match funct7 {
+                0x00 => Op::FaddS,
+                0x01 => Op::FaddD,
+                0x04 => Op::FsubS,
+                0x05 => Op::FsubD,
+                0x08 => Op::FmulS,
+                0x09 => Op::FmulD,
+                0x0c => Op::FdivS,
+                0x0d => Op::FdivD,
+                0x10 => match funct3 {
+                    0x0 => Op::FsgnjS,
+                    0x1 => Op::FsgnjnS,
+                    0x2 => Op::FsgnjxS,
+                    _ => return None,
+                },
+                0x11 => match funct3 {
+                    0x0 => Op::FsgnjD,
+                    0x1 => Op::FsgnjnD,
+                    0x2 => Op::FsgnjxD,
+                    _ => return None,
+                },
+                0x14 => match funct3 {
+                    0x0 => Op::FminS,
+                    0x1 => Op::FmaxS,
+                    _ => return None,
+                },
+                0x15 => match funct3 {
+                    0x0 => Op::FminD,
+                    0x1 => Op::FmaxD,
+                    _ => return None,
+                },
+                0x20 => Op::FcvtSD,
+                0x21 => Op::FcvtDS,
+                0x2c => Op::FsqrtS,
+                0x2d => Op::FsqrtD,
+                0x50 => match funct3 {
+                    0x0 => Op::FleS,
+                    0x1 => Op::FltS,
+                    0x2 => Op::FeqS,
+                    _ => return None,
+                },
+                0x51 => match funct3 {
+                    0x0 => Op::FleD,
+                    0x1 => Op::FltD,
+                    0x2 => Op::FeqD,
+                    _ => return None,
+                },
+                0x60 => match rs2 {
+                    0x0 => Op::FcvtWS,
+                    0x1 => Op::FcvtWuS,
+                    0x2 => Op::FcvtLS,
+                    0x3 => Op::FcvtLuS,
+                    _ => return None,
+                },
+                0x61 => match rs2 {
+                    0x0 => Op::FcvtWD,
+                    0x1 => Op::FcvtWuD,
+                    0x2 => Op::FcvtLD,
+                    0x3 => Op::FcvtLuD,
+                    _ => return None,
+                },
+                0x68 => match rs2 {
+                    0x0 => Op::FcvtSW,
+                    0x1 => Op::FcvtSWu,
+                    0x2 => Op::FcvtSL,
+                    0x3 => Op::FcvtSLu,
+                    _ => return None,
+                },
+                0x69 => match rs2 {
+                    0x0 => Op::FcvtDW,
+                    0x1 => Op::FcvtDWu,
+                    0x2 => Op::FcvtDL,
+                    0x3 => Op::FcvtDLu,
+                    _ => return None,
+                },
+                0x70 => match funct3 {
+                    0x0 => Op::FmvXW,
+                    0x1 => Op::FclassS,
+                    _ => return None,
+                },
+                0x71 => match funct3 {
+                    0x0 => Op::FmvXD,
+                    0x1 => Op::FclassD,
+                    _ => return None,
+                },
+                0x78 => Op::FmvWX,
+                0x79 => Op::FmvDX,
+                _ => return None,
+            };
+            decoded(op, 0)
+        }
+        0x63 => {
+            let op = match funct3 {
+                0x0 => Op::Beq,
+                0x1 => Op::Bne,
+                0x4 => Op::Blt,
+                0x5 => Op::Bge,
+                0x6 => Op::Bltu,
+                0x7 => Op::Bgeu,
+                _ => return None,
+            };
+            decoded(op, imm_b)
+        }
+        0x67 => decoded(Op::Jalr, imm_i),
+        0x6f => decoded(Op::Jal, imm_j),
+        0x73 => {
+            let csr = (inst >> 20) & 0xfff;
+            match funct3 {
+                0x0 => {
+                    let op = match (rs2, funct7) {
+                        (0x0, 0x0) => Op::Ecall,
+                        (0x1, 0x0) => Op::Ebreak,
+                        (0x2, 0x0) => Op::Uret,
+                        (0x2, 0x8) => Op::Sret,
+                        (0x2, 0x18) => Op::Mret,
+                        (0x5, 0x8) => Op::Wfi,
+                        (_, 0x9) => Op::SfenceVma,
+                        (_, 0x11) => Op::HfenceBvma,
+                        (_, 0x51) => Op::HfenceGvma,
+                        _ => return None,
+                    };
+                    decoded(op, 0)
+                }
+                0x1 => decoded(Op::Csrrw, csr),
+                0x2 => decoded(Op::Csrrs, csr),
+                0x3 => decoded(Op::Csrrc, csr),
+                0x5 => decoded(Op::Csrrwi, csr),
+                0x6 => decoded(Op::Csrrsi, csr),
+                0x7 => decoded(Op::Csrrci, csr),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn general() {
+        // addi t0, zero, -1
+        let addi = decode(0xfff00293).unwrap();
+        assert_eq!(
+            (Op::Addi, 5, 0, u64::MAX),
+            (addi.op, addi.rd, addi.rs1, addi.imm)
+        );
+        // sd ra, -8(sp)
+        let sd = decode(0xfe113c23).unwrap();
+        assert_eq!(
+            (Op::Sd, 2, 1, -8i64 as u64),
+            (sd.op, sd.rs1, sd.rs2, sd.imm)
+        );
+        // jal ra, -4
+        let jal = decode(0xffdff0ef).unwrap();
+        assert_eq!((Op::Jal, 1, -4i64 as u64), (jal.op, jal.rd, jal.imm));
+        // lui a0, 0x80000
+        assert_eq!(0xffffffff80000000, decode(0x80000537).unwrap().imm);
+        assert_eq!(4, decode(0x00000073).unwrap().size());
+        assert_eq!(None, decode(0xffffffff));
+    }
+
+    #[test]
+    fn compressed() {
+        // c.lwsp a0, 4(sp)
+        let lwsp = decode(0x4512).unwrap();
+        assert_eq!(
+            (Op::CLwsp, 10, 2, 4),
+            (lwsp.op, lwsp.rd, lwsp.rs1, lwsp.imm)
+        );
+        // c.jalr a5
+        let jalr = decode(0x9782).unwrap();
+        assert_eq!((Op::CJalr, 1, 15), (jalr.op, jalr.rd, jalr.rs1));
+        // c.j -2
+        assert_eq!(-2i64 as u64, decode(0xbffd).unwrap().imm);
+        assert_eq!(2, decode(0x0001).unwrap().size());
+        // All zeros is defined to be illegal.
+        assert_eq!(None, decode(0x0000));
+    }
+}
@@ -43,12 +43,12 @@ const MTIME_END: u64 = MTIME + 0x8;
 pub struct Clint {
     /// Machine mode software interrupt pending register, used to assert a software interrupt for
     /// a CPU.
-    msip: u32,
+    pub(crate) msip: u32,
     /// Memory mapped machine mode timer compare register, used to trigger an interrupt when
     /// mtimecmp is greater than or equal to mtime. There is an mtimecmp dedicated to each CPU.
-    mtimecmp: u64,
+    pub(crate) mtimecmp: u64,
     /// Machine mode timer register which runs at a constant frequency.
-    mtime: u64,
+    pub(crate) mtime: u64,
 }
 
 impl Clint {
@@ -0,0 +1,304 @@
+//! The disasm module decodes RV64GC instructions, including the compressed (C) extension, into
+//! human readable assembly.
+//!
+//! Instructions are decoded by [`crate::decode`], which the profiler shares, and mnemonics are the
+//! same names the CPU records in `Cpu::inst_counter`, so a disassembly can be matched against an
+//! instruction count.
+
+use std::fmt;
+
+use crate::decode::{self, Decoded, Op};
+
+/// The ABI names of the integer registers.
+const XREG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// The ABI names of the floating-point registers.
+const FREG_NAMES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2",
+    "fa3", "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
+    "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+/// A decoded instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    /// The address the instruction was decoded at.
+    pub address: u64,
+    /// The raw encoding. Compressed instructions only use the lower 16 bits.
+    pub raw: u32,
+    /// The size of the instruction in bytes, 2 or 4.
+    pub size: u8,
+    /// The mnemonic, e.g. `addi` or `c.lw`. `unknown` if the encoding is not recognized.
+    pub mnemonic: &'static str,
+    /// The operands in assembly syntax, e.g. `a0, 8(sp)`.
+    pub operands: String,
+    /// The absolute target address of a branch or direct jump.
+    pub target: Option<u64>,
+}
+
+impl Instruction {
+    /// Whether this is an `ecall`.
+    pub fn is_ecall(&self) -> bool {
+        self.mnemonic == "ecall"
+    }
+
+    /// If the instruction loads a constant into integer register `rd` (`li rd, imm` expanded to
+    /// `addi rd, zero, imm` or `c.li`), return the constant.
+    pub fn loaded_immediate(&self, rd: u64) -> Option<i64> {
+        let reg = XREG_NAMES[rd as usize];
+        let imm = match self.mnemonic {
+            "c.li" => self.operands.strip_prefix(reg)?.strip_prefix(", ")?,
+            "addi" => self.operands.strip_prefix(reg)?.strip_prefix(", zero, ")?,
+            _ => return None,
+        };
+        imm.parse().ok()
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.operands.is_empty() {
+            write!(f, "{}", self.mnemonic)
+        } else {
+            write!(f, "{:<10} {}", self.mnemonic, self.operands)
+        }
+    }
+}
+
+/// Decode all instructions in `code`, which is placed at `address`. Decoding stops at the first
+/// truncated instruction.
+pub fn disassemble(code: &[u8], address: u64) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while let Some(instruction) = decode_at(code, offset, address + offset as u64) {
+        offset += instruction.size as usize;
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+/// Decode the instruction starting at `code[offset]`, which is placed at `address`.
+pub fn decode_at(code: &[u8], offset: usize, address: u64) -> Option<Instruction> {
+    let low = u16::from_le_bytes(code.get(offset..offset + 2)?.try_into().ok()?) as u32;
+    if low & 0b11 != 0b11 {
+        return Some(decode(low, address));
+    }
+    let high = u16::from_le_bytes(code.get(offset + 2..offset + 4)?.try_into().ok()?) as u32;
+    Some(decode(low | (high << 16), address))
+}
+
+/// Decode a single instruction placed at `address`. The size is derived from the two lowest bits.
+pub fn decode(raw: u32, address: u64) -> Instruction {
+    let (raw, size) = if raw & 0b11 != 0b11 {
+        (raw & 0xffff, 2)
+    } else {
+        (raw, 4)
+    };
+    let (mnemonic, operands, offset) = match decode::decode(raw as u64) {
+        Some(decoded) => {
+            let (operands, offset) = operands(&decoded);
+            (decoded.op.name(), operands, offset)
+        }
+        None => ("unknown", String::new(), None),
+    };
+    let target = offset.map(|offset| address.wrapping_add(offset));
+    Instruction {
+        address,
+        raw,
+        size,
+        mnemonic,
+        target,
+        operands: match target {
+            Some(target) if operands.is_empty() => format!("{:#x}", target),
+            Some(target) => format!("{}, {:#x}", operands, target),
+            None => operands,
+        },
+    }
+}
+
+fn x(reg: u64) -> &'static str {
+    XREG_NAMES[(reg & 0x1f) as usize]
+}
+
+fn f(reg: u64) -> &'static str {
+    FREG_NAMES[(reg & 0x1f) as usize]
+}
+
+/// Render the operands of `decoded`, and return the pc-relative offset of a branch or jump target,
+/// which the caller appends as an absolute address.
+fn operands(decoded: &Decoded) -> (String, Option<u64>) {
+    let Decoded {
+        op,
+        rd,
+        rs1,
+        rs2,
+        rs3,
+        imm,
+    } = *decoded;
+    let simm = imm as i64;
+    let operands = match op {
+        Op::CAddi4spn => format!("{}, sp, {}", x(rd), imm),
+        Op::CFld | Op::CFldsp | Op::Flw | Op::Fld => format!("{}, {}({})", f(rd), simm, x(rs1)),
+        Op::CLw
+        | Op::CLd
+        | Op::CLwsp
+        | Op::CLdsp
+        | Op::Lb
+        | Op::Lh
+        | Op::Lw
+        | Op::Ld
+        | Op::Lbu
+        | Op::Lhu
+        | Op::Lwu
+        | Op::Jalr => format!("{}, {}({})", x(rd), simm, x(rs1)),
+        Op::CFsd | Op::CFsdsp | Op::Fsw | Op::Fsd => format!("{}, {}({})", f(rs2), simm, x(rs1)),
+        Op::CSw | Op::CSd | Op::CSwsp | Op::CSdsp | Op::Sb | Op::Sh | Op::Sw | Op::Sd => {
+            format!("{}, {}({})", x(rs2), simm, x(rs1))
+        }
+        Op::CAddi | Op::CAddiw | Op::CLi | Op::CAndi => format!("{}, {}", x(rd), simm),
+        Op::CAddi16sp => format!("sp, {}", simm),
+        Op::CLui | Op::Lui | Op::Auipc => format!("{}, {:#x}", x(rd), (imm >> 12) & 0xfffff),
+        Op::CSrli | Op::CSrai | Op::CSlli => format!("{}, {}", x(rd), imm),
+        Op::CSub | Op::CXor | Op::COr | Op::CAnd | Op::CSubw | Op::CAddw | Op::CMv | Op::CAdd => {
+            format!("{}, {}", x(rd), x(rs2))
+        }
+        Op::CJ => return (String::new(), Some(imm)),
+        Op::CBeqz | Op::CBnez => return (x(rs1).to_string(), Some(imm)),
+        Op::CJr | Op::CJalr => x(rs1).to_string(),
+        Op::Jal => return (x(rd).to_string(), Some(imm)),
+        Op::Beq | Op::Bne | Op::Blt | Op::Bge | Op::Bltu | Op::Bgeu => {
+            return (format!("{}, {}", x(rs1), x(rs2)), Some(imm))
+        }
+        Op::Addi
+        | Op::Slti
+        | Op::Sltiu
+        | Op::Xori
+        | Op::Ori
+        | Op::Andi
+        | Op::Addiw
+        | Op::Slli
+        | Op::Srli
+        | Op::Srai
+        | Op::Slliw
+        | Op::Srliw
+        | Op::Sraiw => format!("{}, {}, {}", x(rd), x(rs1), simm),
+        Op::Add
+        | Op::Sub
+        | Op::Sll
+        | Op::Slt
+        | Op::Sltu
+        | Op::Xor
+        | Op::Srl
+        | Op::Sra
+        | Op::Or
+        | Op::And
+        | Op::Addw
+        | Op::Subw
+        | Op::Sllw
+        | Op::Srlw
+        | Op::Sraw
+        | Op::Mul
+        | Op::Mulh
+        | Op::Mulhsu
+        | Op::Mulhu
+        | Op::Div
+        | Op::Divu
+        | Op::Rem
+        | Op::Remu
+        | Op::Mulw
+        | Op::Divw
+        | Op::Divuw
+        | Op::Remw
+        | Op::Remuw => format!("{}, {}, {}", x(rd), x(rs1), x(rs2)),
+        Op::LrW | Op::LrD => format!("{}, ({})", x(rd), x(rs1)),
+        Op::AmoaddW
+        | Op::AmoaddD
+        | Op::AmoswapW
+        | Op::AmoswapD
+        | Op::ScW
+        | Op::ScD
+        | Op::AmoxorW
+        | Op::AmoxorD
+        | Op::AmoorW
+        | Op::AmoorD
+        | Op::AmoandW
+        | Op::AmoandD
+        | Op::AmominW
+        | Op::AmominD
+        | Op::AmomaxW
+        | Op::AmomaxD
+        | Op::AmominuW
+        | Op::AmominuD
+        | Op::AmomaxuW
+        | Op::AmomaxuD => format!("{}, {}, ({})", x(rd), x(rs2), x(rs1)),
+        Op::FmaddS
+        | Op::FmaddD
+        | Op::FmsubS
+        | Op::FmsubD
+        | Op::FnmaddS
+        | Op::FnmaddD
+        | Op::FnmsubS
+        | Op::FnmsubD => format!("{}, {}, {}, {}", f(rd), f(rs1), f(rs2), f(rs3)),
+        Op::FaddS
+        | Op::FaddD
+        | Op::FsubS
+        | Op::FsubD
+        | Op::FmulS
+        | Op::FmulD
+        | Op::FdivS
+        | Op::FdivD
+        | Op::FsgnjS
+        | Op::FsgnjnS
+        | Op::FsgnjxS
+        | Op::FsgnjD
+        | Op::FsgnjnD
+        | Op::FsgnjxD
+        | Op::FminS
+        | Op::FmaxS
+        | Op::FminD
+        | Op::FmaxD => format!("{}, {}, {}", f(rd), f(rs1), f(rs2)),
+        Op::FcvtSD | Op::FcvtDS | Op::FsqrtS | Op::FsqrtD => format!("{}, {}", f(rd), f(rs1)),
+        Op::FleS | Op::FltS | Op::FeqS | Op::FleD | Op::FltD | Op::FeqD => {
+            format!("{}, {}, {}", x(rd), f(rs1), f(rs2))
+        }
+        Op::FcvtWS
+        | Op::FcvtWuS
+        | Op::FcvtLS
+        | Op::FcvtLuS
+        | Op::FcvtWD
+        | Op::FcvtWuD
+        | Op::FcvtLD
+        | Op::FcvtLuD
+        | Op::FmvXW
+        | Op::FclassS
+        | Op::FmvXD
+        | Op::FclassD => format!("{}, {}", x(rd), f(rs1)),
+        Op::FcvtSW
+        | Op::FcvtSWu
+        | Op::FcvtSL
+        | Op::FcvtSLu
+        | Op::FcvtDW
+        | Op::FcvtDWu
+        | Op::FcvtDL
+        | Op::FcvtDLu
+        | Op::FmvWX
+        | Op::FmvDX => format!("{}, {}", f(rd), x(rs1)),
+        Op::SfenceVma | Op::HfenceBvma | Op::HfenceGvma => format!("{}, {}", x(rs1), x(rs2)),
+        Op::Csrrw | Op::Csrrs | Op::Csrrc => format!("{}, {:#x}, {}", x(rd), imm, x(rs1)),
+        Op::Csrrwi | Op::Csrrsi | Op::Csrrci => format!("{}, {:#x}, {}", x(rd), imm, rs1),
+        Op::CEbreak
+        | Op::Fence
+        | Op::FenceI
+        | Op::Ecall
+        | Op::Ebreak
+        | Op::Uret
+        | Op::Sret
+        | Op::Mret
+        | Op::Wfi => String::new(),
+    };
+    (operands, None)
+}
@@ -1,17 +1,24 @@
 //! The memory module contains the memory structure and implementation to read/write the memory.
 
+use std::ops::Range;
+
 use crate::bus::DRAM_BASE;
 use crate::cpu::{BYTE, DOUBLEWORD, HALFWORD, WORD};
 use crate::exception::Exception;
 
 /// Default memory size (1GiB).
 pub const DRAM_SIZE: u64 = 1024 * 1024 * 1024;
+/// The granularity (4 KiB) at which written memory is tracked.
+pub const DRAM_PAGE_SIZE: u64 = 4096;
 
 /// The memory used by the emulator.
 #[derive(Debug)]
 pub struct Dram {
     pub dram: Vec<u8>,
-    code_size: u64,
+    pub(crate) code_size: u64,
+    /// One bit per page, set once the page has been written, if tracked (see
+    /// [`Dram::track_dirty_pages`]). Untouched pages are all zeros.
+    dirty: Option<Vec<u64>>,
 }
 
 impl Dram {
@@ -20,13 +27,66 @@ impl Dram {
         Self {
             dram: vec![0; DRAM_SIZE as usize],
             code_size: 0,
+            dirty: None,
         }
     }
 
+    /// Track the pages written from now on, so that snapshots only copy those and restoring one
+    /// only clears those. Untracked memory is scanned whole instead, which keeps writes free of
+    /// any bookkeeping. Only called before the memory is first written, see
+    /// `Emulator::with_dirty_tracking`.
+    pub(crate) fn track_dirty_pages(&mut self) {
+        self.dirty = Some(vec![0; (DRAM_SIZE / DRAM_PAGE_SIZE / 64) as usize]);
+    }
+
     /// Set the binary in the memory.
     pub fn initialize(&mut self, binary: Vec<u8>) {
         self.code_size = binary.len() as u64;
         self.dram.splice(..binary.len(), binary.iter().cloned());
+        self.mark_dirty(0..binary.len());
+    }
+
+    /// Mark the pages overlapping `range`, given as offsets from `DRAM_BASE`, as written.
+    pub(crate) fn mark_dirty(&mut self, range: Range<usize>) {
+        let Some(dirty) = self.dirty.as_mut() else {
+            return;
+        };
+        let end = range.end.min(self.dram.len());
+        if range.start >= end {
+            return;
+        }
+        let first = range.start / DRAM_PAGE_SIZE as usize;
+        let last = (end - 1) / DRAM_PAGE_SIZE as usize;
+        for page in first..=last {
+            if let Some(bits) = dirty.get_mut(page / 64) {
+                *bits |= 1 << (page % 64);
+            }
+        }
+    }
+
+    /// The indices of the pages written so far, in ascending order. Every page may have been
+    /// written when they are not tracked.
+    pub(crate) fn dirty_pages(&self) -> Vec<usize> {
+        match &self.dirty {
+            Some(dirty) => set_bits(dirty),
+            None => (0..self.dram.len().div_ceil(DRAM_PAGE_SIZE as usize)).collect(),
+        }
+    }
+
+    /// Zero every written page and forget that it was written.
+    pub(crate) fn clear(&mut self) {
+        match &mut self.dirty {
+            Some(dirty) => {
+                for page in set_bits(dirty) {
+                    let start = page * DRAM_PAGE_SIZE as usize;
+                    let end = (start + DRAM_PAGE_SIZE as usize).min(self.dram.len());
+                    self.dram[start..end].fill(0);
+                }
+                dirty.fill(0);
+            }
+            None => self.dram = vec![0; self.dram.len()],
+        }
+        self.code_size = 0;
     }
 
     /// Load `size`-bit data from the memory.
@@ -42,6 +102,8 @@ impl Dram {
 
     /// Store `size`-bit data to the memory.
     pub fn write(&mut self, addr: u64, value: u64, size: u8) -> Result<(), Exception> {
+        let index = (addr - DRAM_BASE) as usize;
+        self.mark_dirty(index..index + (size / 8) as usize);
         match size {
             BYTE => self.write8(addr, value),
             HALFWORD => self.write16(addr, value),
@@ -121,3 +183,16 @@ impl Dram {
             | ((self.dram[index + 7] as u64) << 56);
     }
 }
+
+/// The indices of the bits set in `bitmap`, in ascending order.
+fn set_bits(bitmap: &[u64]) -> Vec<usize> {
+    let mut indices = Vec::new();
+    for (word, &bits) in bitmap.iter().enumerate() {
+        let mut bits = bits;
+        while bits != 0 {
+            indices.push(word * 64 + bits.trailing_zeros() as usize);
+            bits &= bits - 1;
+        }
+    }
+    indices
+}
@@ -2,6 +2,8 @@
 
 use crate::cpu::Cpu;
 use crate::exception::{Exception, Trap};
+use crate::gdb::GdbStub;
+use crate::profile::Profiler;
 
 /// The emulator to hold a CPU.
 #[derive(Debug)]
@@ -10,6 +12,10 @@ pub struct Emulator {
     pub cpu: Cpu,
     /// The debug flag. Output messages if it's true, otherwise output nothing.
     pub is_debug: bool,
+    /// The GDB stub driving `estart`, if a debugger is attached.
+    pub(crate) gdb: Option<GdbStub>,
+    /// The profiler recording every instruction run by `estart`, if attached.
+    pub(crate) profiler: Option<Profiler>,
 }
 
 impl Emulator {
@@ -18,9 +24,19 @@ impl Emulator {
         Self {
             cpu: Cpu::new(),
             is_debug: false,
+            gdb: None,
+            profiler: None,
         }
     }
 
+    /// Constructor for an emulator tracking the memory pages it writes, which makes snapshots
+    /// of it and restoring them cheaper at the cost of some bookkeeping on every write.
+    pub fn with_dirty_tracking() -> Emulator {
+        let mut emu = Self::new();
+        emu.cpu.bus.dram.track_dirty_pages();
+        emu
+    }
+
     /// Reset CPU state.
     pub fn reset(&mut self) {
         self.cpu.reset()
@@ -153,6 +169,12 @@ impl Emulator {
 
     /// Start executing the emulator.
     pub fn estart(&mut self) -> Result<(), Exception> {
+        if let Some(mut gdb) = self.gdb.take() {
+            let result = gdb.run(self);
+            self.gdb = Some(gdb);
+            return result;
+        }
+
         if self.is_debug {
             self.debug_start();
         }
@@ -167,6 +189,10 @@ impl Emulator {
                 None => {}
             }
 
+            if let Some(profiler) = self.profiler.as_mut() {
+                profiler.record(&mut self.cpu);
+            }
+
             // Execute an instruction.
             match self.cpu.eexecute() {
                 Ok(0) => {
@@ -0,0 +1,458 @@
+//! The gdb module implements a GDB remote serial protocol (RSP) stub, so a program running in the
+//! emulator can be debugged with `riscv64-unknown-elf-gdb` (or `gdb-multiarch`).
+//!
+//! The stub is attached to an [`Emulator`] and takes over [`Emulator::estart`]: before every
+//! instruction it checks for breakpoints and single-steps, and an `ecall` is reported as a
+//! `syscall_entry` stop carrying the syscall number from `t0`, so `catch syscall` works on the
+//! GDB side. Faults are reported as `SIGSEGV`/`SIGILL` before the exception is handed back to the
+//! caller.
+//!
+//! ```text
+//! (gdb) target remote 127.0.0.1:9001
+//! (gdb) break my_contract::transfer
+//! (gdb) catch syscall 85
+//! (gdb) continue
+//! ```
+//!
+//! Supported packets: `?`, `g`, `G`, `p`, `P`, `m`, `M`, `c`, `s`, `Z0`/`z0`, `Z1`/`z1`, `D`,
+//! `k`, `H`, `qSupported`, `qAttached`, `qC`, `qfThreadInfo`, `qsThreadInfo`. Anything else is
+//! answered with an empty packet, which GDB reads as "unsupported".
+
+use std::collections::BTreeSet;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::cpu::{BYTE, REGISTERS_COUNT, WORD};
+use crate::emulator::Emulator;
+use crate::exception::Exception;
+
+/// The encoding of `ecall`.
+const ECALL: u64 = 0x0000_0073;
+/// GDB's register number of the program counter (`x0`-`x31` come first).
+const PC_REGNUM: usize = 32;
+/// GDB's register number of `f0`.
+const FIRST_FP_REGNUM: usize = 33;
+/// GDB's register number of the first CSR. CSR `n` is register `FIRST_CSR_REGNUM + n`.
+const FIRST_CSR_REGNUM: usize = 65;
+/// The number of CSRs addressable through the stub.
+const CSR_COUNT: usize = 4096;
+/// The register holding the syscall id on `ecall`.
+const SYSCALL_REG: u64 = 5;
+/// How many instructions run between polls for a GDB interrupt (Ctrl-C) while continuing.
+const INTERRUPT_POLL_INTERVAL: u64 = 4096;
+
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+/// Why the emulator stopped and handed control to GDB.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    /// A single step finished or a breakpoint was hit.
+    Trap,
+    /// The instruction at `pc` is an `ecall` with the given syscall id in `t0`.
+    Syscall(u64),
+    /// GDB sent an interrupt (Ctrl-C).
+    Interrupt,
+    /// The program raised an exception other than `ecall`.
+    Fault(u8),
+}
+
+/// What to do once GDB resumes the target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Resume {
+    Continue,
+    Step,
+    Detach,
+}
+
+/// A GDB remote serial protocol server bound to a single client connection.
+#[derive(Debug)]
+pub struct GdbStub {
+    stream: TcpStream,
+    /// Addresses of software and hardware breakpoints. Both kinds behave the same since the stub
+    /// never patches program memory.
+    breakpoints: BTreeSet<u64>,
+    /// Stop before the next instruction.
+    stepping: bool,
+    /// The client detached or the connection broke: run freely from now on.
+    detached: bool,
+    /// Report `ecall`s as stops.
+    pub stop_on_syscall: bool,
+}
+
+impl GdbStub {
+    /// Listen on `addr` and block until a GDB client connects.
+    pub fn listen<A: ToSocketAddrs>(addr: A) -> io::Result<GdbStub> {
+        let listener = TcpListener::bind(addr)?;
+        let (stream, _) = listener.accept()?;
+        Ok(GdbStub::new(stream))
+    }
+
+    /// Create a stub over an already connected client. The target starts halted, as GDB expects
+    /// right after `target remote`.
+    pub fn new(stream: TcpStream) -> GdbStub {
+        Self {
+            stream,
+            breakpoints: BTreeSet::new(),
+            stepping: true,
+            detached: false,
+            stop_on_syscall: true,
+        }
+    }
+
+    /// Run the emulator under the debugger. Behaves like [`Emulator::estart`], except that
+    /// execution stops (and GDB is served) at breakpoints, single steps, `ecall`s and faults.
+    pub fn run(&mut self, emu: &mut Emulator) -> Result<(), Exception> {
+        let mut count: u64 = 0;
+        // A stop is never reported twice for the same instruction: after GDB resumes, the
+        // instruction at `pc` executes before breakpoints are checked again.
+        let mut resumed_at = None;
+
+        loop {
+            if !self.detached {
+                let pc = emu.cpu.pc;
+                let stop = if resumed_at == Some(pc) {
+                    None
+                } else if self.stepping || self.breakpoints.contains(&pc) {
+                    Some(StopReason::Trap)
+                } else if self.stop_on_syscall && is_ecall(emu, pc) {
+                    Some(StopReason::Syscall(emu.cpu.xregs.read(SYSCALL_REG)))
+                } else if count % INTERRUPT_POLL_INTERVAL == 0 && self.interrupted() {
+                    Some(StopReason::Interrupt)
+                } else {
+                    None
+                };
+                resumed_at = None;
+
+                if let Some(reason) = stop {
+                    self.stop(emu, reason);
+                    resumed_at = Some(pc);
+                }
+            }
+            count += 1;
+
+            // Run a cycle on peripheral devices.
+            emu.cpu.devices_increment();
+
+            // Take an interrupt.
+            if let Some(interrupt) = emu.cpu.check_pending_interrupt() {
+                interrupt.take_trap(&mut emu.cpu);
+            }
+
+            // Execute an instruction.
+            match emu.cpu.eexecute() {
+                Ok(0) => return Ok(()),
+                Ok(_) => {}
+                Err(Exception::EnvironmentCallFromMMode) => {
+                    return Err(Exception::EnvironmentCallFromMMode)
+                }
+                Err(exception) => {
+                    if !self.detached {
+                        self.stop(emu, StopReason::Fault(signal_of(&exception)));
+                    }
+                    return Err(exception);
+                }
+            }
+        }
+    }
+
+    /// Report a stop to GDB and serve its requests until it resumes the target.
+    fn stop(&mut self, emu: &mut Emulator, reason: StopReason) {
+        let resume = self
+            .send_packet(&stop_reply(reason))
+            .and_then(|_| self.serve(emu));
+
+        match resume {
+            Ok(Resume::Continue) => self.stepping = false,
+            Ok(Resume::Step) => self.stepping = true,
+            // A broken connection is treated as a detach so the execution can still complete.
+            Ok(Resume::Detach) | Err(_) => {
+                self.detached = true;
+                self.stepping = false;
+            }
+        }
+    }
+
+    /// Serve packets until one of them resumes the target.
+    fn serve(&mut self, emu: &mut Emulator) -> io::Result<Resume> {
+        loop {
+            let packet = self.read_packet()?;
+            let (reply, resume) = self.handle(emu, &packet);
+            if let Some(reply) = reply {
+                self.send_packet(&reply)?;
+            }
+            if let Some(resume) = resume {
+                return Ok(resume);
+            }
+        }
+    }
+
+    /// Handle a single packet, returning the reply to send (if any) and whether the target
+    /// should resume.
+    fn handle(&mut self, emu: &mut Emulator, packet: &str) -> (Option<String>, Option<Resume>) {
+        let (command, args) = packet.split_at(packet.len().min(1));
+        let reply = match command {
+            "?" => stop_reply(StopReason::Trap),
+            "g" => (0..=PC_REGNUM)
+                .map(|regnum| to_hex_le(read_register(emu, regnum).unwrap_or(0)))
+                .collect(),
+            "G" => {
+                let values = from_hex_le_words(args);
+                for (regnum, value) in values.into_iter().take(PC_REGNUM + 1).enumerate() {
+                    write_register(emu, regnum, value);
+                }
+                "OK".to_string()
+            }
+            "p" => match usize::from_str_radix(args, 16)
+                .ok()
+                .and_then(|regnum| read_register(emu, regnum))
+            {
+                Some(value) => to_hex_le(value),
+                None => "E01".to_string(),
+            },
+            "P" => match parse_register_write(args) {
+                Some((regnum, value)) if write_register(emu, regnum, value) => "OK".to_string(),
+                _ => "E01".to_string(),
+            },
+            "m" => match parse_addr_len(args).and_then(|(addr, len)| read_memory(emu, addr, len)) {
+                Some(bytes) => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+                None => "E14".to_string(),
+            },
+            "M" => match parse_memory_write(args) {
+                Some((addr, bytes)) if write_memory(emu, addr, &bytes) => "OK".to_string(),
+                _ => "E14".to_string(),
+            },
+            "c" => return (None, Some(Resume::Continue)),
+            "s" => return (None, Some(Resume::Step)),
+            "Z" | "z" => self.handle_breakpoint(command == "Z", args),
+            "D" => return (Some("OK".to_string()), Some(Resume::Detach)),
+            // The connection is closed right after, so nothing is sent back.
+            "k" => return (None, Some(Resume::Detach)),
+            "H" => "OK".to_string(),
+            "q" => match args {
+                a if a.starts_with("Supported") => "PacketSize=4000".to_string(),
+                "Attached" => "1".to_string(),
+                "C" => "QC1".to_string(),
+                "fThreadInfo" => "m1".to_string(),
+                "sThreadInfo" => "l".to_string(),
+                _ => String::new(),
+            },
+            _ => String::new(),
+        };
+        (Some(reply), None)
+    }
+
+    /// Handle `Z<type>,<addr>,<kind>` and `z<type>,<addr>,<kind>`.
+    fn handle_breakpoint(&mut self, insert: bool, args: &str) -> String {
+        let mut fields = args.split(',');
+        let (Some(kind), Some(addr)) = (fields.next(), fields.next()) else {
+            return "E01".to_string();
+        };
+        // Only software (0) and hardware (1) breakpoints are supported, not watchpoints.
+        if kind != "0" && kind != "1" {
+            return String::new();
+        }
+        let Ok(addr) = u64::from_str_radix(addr, 16) else {
+            return "E01".to_string();
+        };
+
+        if insert {
+            self.breakpoints.insert(addr);
+        } else {
+            self.breakpoints.remove(&addr);
+        }
+        "OK".to_string()
+    }
+
+    /// Check, without blocking, whether GDB sent an interrupt byte.
+    fn interrupted(&mut self) -> bool {
+        let mut byte = [0u8; 1];
+        if self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let interrupted = matches!(self.stream.read(&mut byte), Ok(1) if byte[0] == 0x03);
+        let _ = self.stream.set_nonblocking(false);
+        interrupted
+    }
+
+    /// Read one `$<data>#<checksum>` packet and acknowledge it. Acks and interrupt bytes received
+    /// in between packets are skipped.
+    fn read_packet(&mut self) -> io::Result<String> {
+        loop {
+            if self.read_byte()? != b'$' {
+                continue;
+            }
+
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte()? {
+                    b'#' => break,
+                    byte => data.push(byte),
+                }
+            }
+            let checksum = [self.read_byte()?, self.read_byte()?];
+            let expected = u8::from_str_radix(&String::from_utf8_lossy(&checksum), 16).ok();
+
+            if expected == Some(checksum_of(&data)) {
+                self.stream.write_all(b"+")?;
+                return Ok(String::from_utf8_lossy(&data).into_owned());
+            }
+            self.stream.write_all(b"-")?;
+        }
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0u8; 1];
+        self.stream.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    /// Send a packet and wait for GDB to acknowledge it, resending on a negative ack.
+    fn send_packet(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        loop {
+            self.stream.write_all(packet.as_bytes())?;
+            self.stream.flush()?;
+            match self.read_byte()? {
+                b'+' => return Ok(()),
+                b'-' => continue,
+                // GDB may skip acks entirely; treat anything else as an implicit ack.
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+impl Emulator {
+    /// Attach a GDB stub. Subsequent calls to [`Emulator::estart`] run under the debugger.
+    pub fn attach_gdb(&mut self, stub: GdbStub) {
+        self.gdb = Some(stub);
+    }
+
+    /// Detach the GDB stub, if any, and return it.
+    pub fn detach_gdb(&mut self) -> Option<GdbStub> {
+        self.gdb.take()
+    }
+}
+
+fn stop_reply(reason: StopReason) -> String {
+    match reason {
+        StopReason::Trap => format!("S{:02x}", SIGTRAP),
+        StopReason::Syscall(id) => format!("T{:02x}syscall_entry:{:x};", SIGTRAP, id),
+        StopReason::Interrupt => format!("S{:02x}", SIGINT),
+        StopReason::Fault(signal) => format!("S{:02x}", signal),
+    }
+}
+
+fn signal_of(exception: &Exception) -> u8 {
+    match exception {
+        Exception::IllegalInstruction(_) => SIGILL,
+        Exception::Breakpoint => SIGTRAP,
+        _ => SIGSEGV,
+    }
+}
+
+fn is_ecall(emu: &mut Emulator, pc: u64) -> bool {
+    emu.cpu.bus.read(pc, WORD) == Ok(ECALL)
+}
+
+fn read_register(emu: &Emulator, regnum: usize) -> Option<u64> {
+    match regnum {
+        r if r < REGISTERS_COUNT => Some(emu.cpu.xregs.read(r as u64)),
+        PC_REGNUM => Some(emu.cpu.pc),
+        r if r < FIRST_CSR_REGNUM => {
+            Some(emu.cpu.fregs.read((r - FIRST_FP_REGNUM) as u64).to_bits())
+        }
+        r if r < FIRST_CSR_REGNUM + CSR_COUNT => {
+            Some(emu.cpu.state.read((r - FIRST_CSR_REGNUM) as u16))
+        }
+        _ => None,
+    }
+}
+
+fn write_register(emu: &mut Emulator, regnum: usize, value: u64) -> bool {
+    match regnum {
+        r if r < REGISTERS_COUNT => emu.cpu.xregs.write(r as u64, value),
+        PC_REGNUM => emu.cpu.pc = value,
+        r if r < FIRST_CSR_REGNUM => emu
+            .cpu
+            .fregs
+            .write((r - FIRST_FP_REGNUM) as u64, f64::from_bits(value)),
+        r if r < FIRST_CSR_REGNUM + CSR_COUNT => {
+            emu.cpu.state.write((r - FIRST_CSR_REGNUM) as u16, value)
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn read_memory(emu: &mut Emulator, addr: u64, len: u64) -> Option<Vec<u8>> {
+    (addr..addr.checked_add(len)?)
+        .map(|a| emu.cpu.bus.read(a, BYTE).ok().map(|byte| byte as u8))
+        .collect()
+}
+
+fn write_memory(emu: &mut Emulator, addr: u64, bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .zip(addr..)
+        .all(|(byte, a)| emu.cpu.bus.write(a, *byte as u64, BYTE).is_ok())
+}
+
+/// Parse `<addr>,<len>`.
+fn parse_addr_len(args: &str) -> Option<(u64, u64)> {
+    let (addr, len) = args.split_once(',')?;
+    Some((
+        u64::from_str_radix(addr, 16).ok()?,
+        u64::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+/// Parse `<addr>,<len>:<hex bytes>`.
+fn parse_memory_write(args: &str) -> Option<(u64, Vec<u8>)> {
+    let (addr_len, data) = args.split_once(':')?;
+    let (addr, len) = parse_addr_len(addr_len)?;
+    let bytes = from_hex_bytes(data)?;
+    (bytes.len() as u64 == len).then_some((addr, bytes))
+}
+
+/// Parse `<regnum>=<little-endian hex value>`.
+fn parse_register_write(args: &str) -> Option<(usize, u64)> {
+    let (regnum, value) = args.split_once('=')?;
+    let regnum = usize::from_str_radix(regnum, 16).ok()?;
+    let value = from_hex_le_words(value).into_iter().next()?;
+    Some((regnum, value))
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Register values travel as target-endian (little-endian) hex.
+fn to_hex_le(value: u64) -> String {
+    value
+        .to_le_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn from_hex_bytes(data: &str) -> Option<Vec<u8>> {
+    if data.len() % 2 != 0 {
+        return None;
+    }
+    (0..data.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(data.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn from_hex_le_words(data: &str) -> Vec<u64> {
+    from_hex_bytes(data)
+        .unwrap_or_default()
+        .chunks_exact(8)
+        .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+        .collect()
+}
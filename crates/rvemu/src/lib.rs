@@ -34,9 +34,14 @@
 pub mod bus;
 pub mod cpu;
 pub mod csr;
+pub mod decode;
 pub mod devices;
+pub mod disasm;
 pub mod dram;
 pub mod emulator;
 pub mod exception;
+pub mod gdb;
 pub mod interrupt;
+pub mod profile;
 pub mod rom;
+pub mod snapshot;
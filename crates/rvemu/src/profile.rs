@@ -0,0 +1,264 @@
+//! The profile module attributes executed instructions to the functions of the running program.
+//!
+//! A [`Profiler`] is attached to an [`Emulator`] and sampled before every instruction retired by
+//! [`Emulator::estart`], so the profile is exact rather than statistical. The call stack is
+//! rebuilt from the standard calling convention: `jal`/`jalr`/`c.jalr` linking through `ra`
+//! push a frame, `jalr zero, 0(ra)`/`c.jr ra` pop one, and any other transfer of control into a
+//! different function (a tail call) replaces the innermost frame.
+//!
+//! The profile is reported as folded stacks (`outer;inner count`), the input format of
+//! `flamegraph.pl` and `inferno-flamegraph`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::cpu::{Cpu, HALFWORD, WORD};
+use crate::decode::{decode, Decoded, Op};
+use crate::emulator::Emulator;
+
+/// The return address register.
+const RA: u64 = 1;
+/// The frame name used for code not covered by any symbol.
+const UNKNOWN_FUNCTION: &str = "[unknown]";
+/// Index of [`UNKNOWN_FUNCTION`] in the call stack.
+const UNKNOWN: usize = usize::MAX;
+
+/// A function of the profiled program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    /// The (demangled) function name.
+    pub name: String,
+    /// The address of the first instruction.
+    pub address: u64,
+    /// The size in bytes. A size of 0 extends the function up to the next symbol.
+    pub size: u64,
+}
+
+/// Instructions and gas attributed to a stack or a function.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Cost {
+    /// The number of instructions retired.
+    pub instructions: u64,
+    /// The gas of those instructions, according to the profiler's cost function.
+    pub gas: u64,
+}
+
+impl Cost {
+    fn add(&mut self, other: Cost) {
+        self.instructions += other.instructions;
+        self.gas += other.gas;
+    }
+}
+
+/// The cost of a single function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionCost {
+    /// The function name.
+    pub name: String,
+    /// The cost of the instructions of the function itself.
+    pub self_cost: Cost,
+    /// The cost of the function and everything it called.
+    pub total_cost: Cost,
+}
+
+/// How an instruction moves the call stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transfer {
+    Call,
+    Return,
+    Other,
+}
+
+/// An exact, symbol-aware execution profiler.
+#[derive(Debug)]
+pub struct Profiler {
+    /// Symbols sorted by address, with sizes resolved.
+    symbols: Vec<Symbol>,
+    /// Returns the gas of an instruction from its mnemonic.
+    cost: fn(&str) -> u64,
+    /// Decoded instructions, keyed by address.
+    decoded: HashMap<u64, (u64, Transfer)>,
+    /// The current call stack, as indices into `symbols`.
+    stack: Vec<usize>,
+    /// The transfer of the previously executed instruction, applied once the next pc is known.
+    pending: Transfer,
+    /// The cost of each distinct call stack.
+    stacks: BTreeMap<Vec<usize>, Cost>,
+}
+
+impl Profiler {
+    /// Create a profiler for a program with the given function symbols. Every instruction costs 1
+    /// gas until a cost function is set with [`Profiler::with_cost`].
+    pub fn new(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by_key(|symbol| symbol.address);
+        symbols.dedup_by_key(|symbol| symbol.address);
+        for i in 0..symbols.len() {
+            if symbols[i].size == 0 {
+                symbols[i].size = match symbols.get(i + 1) {
+                    Some(next) => next.address - symbols[i].address,
+                    None => u64::MAX - symbols[i].address,
+                };
+            }
+        }
+
+        Self {
+            symbols,
+            cost: |_| 1,
+            decoded: HashMap::new(),
+            stack: Vec::new(),
+            pending: Transfer::Other,
+            stacks: BTreeMap::new(),
+        }
+    }
+
+    /// Set the function returning the gas of an instruction from its mnemonic (as counted in
+    /// `Cpu::inst_counter`).
+    pub fn with_cost(mut self, cost: fn(&str) -> u64) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// Account for the instruction about to be executed at `pc`.
+    pub(crate) fn record(&mut self, cpu: &mut Cpu) {
+        let pc = cpu.pc;
+        let function = self.function_at(pc);
+
+        match self.pending {
+            Transfer::Call => self.stack.push(function),
+            Transfer::Return if self.stack.len() > 1 => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+        // After a return, or a jump into another function (a tail call), the innermost frame is
+        // whichever function the pc is in now.
+        match self.stack.last_mut() {
+            Some(current) => *current = function,
+            None => self.stack.push(function),
+        }
+
+        let (gas, transfer) = match self.decoded.get(&pc) {
+            Some(decoded) => *decoded,
+            None => {
+                let decoded = self.decode(cpu, pc);
+                self.decoded.insert(pc, decoded);
+                decoded
+            }
+        };
+        self.pending = transfer;
+
+        let cost = Cost {
+            instructions: 1,
+            gas,
+        };
+        match self.stacks.get_mut(self.stack.as_slice()) {
+            Some(total) => total.add(cost),
+            None => {
+                self.stacks.insert(self.stack.clone(), cost);
+            }
+        }
+    }
+
+    /// The cost of every call stack seen, keyed by the `;`-separated function names from the
+    /// outermost frame inwards.
+    pub fn folded_stacks(&self) -> BTreeMap<String, Cost> {
+        let mut folded = BTreeMap::new();
+        for (stack, cost) in &self.stacks {
+            let names: Vec<&str> = stack.iter().map(|&index| self.name(index)).collect();
+            let total: &mut Cost = folded.entry(names.join(";")).or_default();
+            total.add(*cost);
+        }
+        folded
+    }
+
+    /// The profile in the folded stack format, one `stack gas` line per call stack.
+    pub fn folded(&self) -> String {
+        self.folded_stacks()
+            .iter()
+            .map(|(stack, cost)| format!("{} {}\n", stack, cost.gas))
+            .collect()
+    }
+
+    /// The cost of every function seen, most expensive (by its own gas) first.
+    pub fn functions(&self) -> Vec<FunctionCost> {
+        let mut functions: BTreeMap<usize, FunctionCost> = BTreeMap::new();
+        for (stack, cost) in &self.stacks {
+            for (depth, &index) in stack.iter().enumerate() {
+                let function = functions.entry(index).or_insert_with(|| FunctionCost {
+                    name: self.name(index).to_string(),
+                    self_cost: Cost::default(),
+                    total_cost: Cost::default(),
+                });
+                // Recursive functions appear several times in a stack but are only charged once.
+                if !stack[..depth].contains(&index) {
+                    function.total_cost.add(*cost);
+                }
+                if depth == stack.len() - 1 {
+                    function.self_cost.add(*cost);
+                }
+            }
+        }
+
+        let mut functions: Vec<FunctionCost> = functions.into_values().collect();
+        functions.sort_by(|a, b| b.self_cost.gas.cmp(&a.self_cost.gas));
+        functions
+    }
+
+    /// The cost of everything recorded so far.
+    pub fn total(&self) -> Cost {
+        let mut total = Cost::default();
+        for cost in self.stacks.values() {
+            total.add(*cost);
+        }
+        total
+    }
+
+    fn function_at(&self, address: u64) -> usize {
+        let index = self
+            .symbols
+            .partition_point(|symbol| symbol.address <= address);
+        match index.checked_sub(1) {
+            Some(index) if address - self.symbols[index].address < self.symbols[index].size => {
+                index
+            }
+            _ => UNKNOWN,
+        }
+    }
+
+    fn name(&self, index: usize) -> &str {
+        match self.symbols.get(index) {
+            Some(symbol) => &symbol.name,
+            None => UNKNOWN_FUNCTION,
+        }
+    }
+
+    fn decode(&self, cpu: &mut Cpu, pc: u64) -> (u64, Transfer) {
+        let raw = match cpu.bus.read(pc, WORD) {
+            Ok(raw) => raw,
+            Err(_) => cpu.bus.read(pc, HALFWORD).unwrap_or(0),
+        };
+        let Some(Decoded { op, rd, rs1, .. }) = decode(raw) else {
+            return ((self.cost)("unknown"), Transfer::Other);
+        };
+
+        let transfer = match op {
+            Op::Jal | Op::Jalr | Op::CJalr if rd == RA => Transfer::Call,
+            Op::Jalr | Op::CJr if rd == 0 && rs1 == RA => Transfer::Return,
+            _ => Transfer::Other,
+        };
+
+        ((self.cost)(op.name()), transfer)
+    }
+}
+
+impl Emulator {
+    /// Attach a profiler. Instructions executed by subsequent calls to [`Emulator::estart`] are
+    /// recorded until the profiler is detached.
+    pub fn attach_profiler(&mut self, profiler: Profiler) {
+        self.profiler = Some(profiler);
+    }
+
+    /// Detach the profiler, if any, and return it.
+    pub fn detach_profiler(&mut self) -> Option<Profiler> {
+        self.profiler.take()
+    }
+}
@@ -0,0 +1,371 @@
+//! The snapshot module saves and restores the complete state of an [`Emulator`], so a failing
+//! execution can be captured, attached to a bug report and resumed elsewhere.
+//!
+//! A [`Snapshot`] holds the registers, the CSRs, the privilege mode, the paging and reservation
+//! state of the CPU, the CLINT timers and every DRAM page that is not all zeros. An emulator built
+//! with [`Emulator::with_dirty_tracking`] only has the pages it wrote checked, instead of the whole
+//! 1GiB DRAM. The UART, PLIC and virtio devices are not captured.
+//!
+//! [`Snapshot::to_bytes`] encodes a snapshot in a compact little-endian binary format and
+//! [`Snapshot::from_bytes`] decodes it again.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::bus::DRAM_BASE;
+use crate::cpu::{Mode, REGISTERS_COUNT};
+use crate::csr::CSR_SIZE;
+use crate::dram::{DRAM_PAGE_SIZE, DRAM_SIZE};
+use crate::emulator::Emulator;
+
+/// The first bytes of an encoded snapshot.
+const MAGIC: &[u8; 8] = b"RVEMUSNP";
+/// The version of the encoding produced by [`Snapshot::to_bytes`].
+const VERSION: u32 = 1;
+
+/// An error decoding or restoring a snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    /// The data does not start with the snapshot magic bytes.
+    InvalidMagic,
+    /// The snapshot was encoded with an unknown version of the format.
+    UnsupportedVersion(u32),
+    /// The data ended before the snapshot was complete.
+    Truncated,
+    /// The data continues after the end of the snapshot.
+    TrailingBytes,
+    /// The privilege mode is not a valid `Mode`.
+    InvalidMode(u8),
+    /// The snapshot does not hold exactly `CSR_SIZE` CSRs.
+    InvalidCsrCount(usize),
+    /// A memory page is not page-aligned, not `DRAM_PAGE_SIZE` long or outside of DRAM.
+    InvalidPage(u64),
+    /// An instruction name is not valid UTF-8.
+    InvalidString,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::InvalidMagic => write!(f, "not an emulator snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::Truncated => write!(f, "snapshot is truncated"),
+            SnapshotError::TrailingBytes => write!(f, "unexpected data after the snapshot"),
+            SnapshotError::InvalidMode(mode) => write!(f, "invalid privilege mode {}", mode),
+            SnapshotError::InvalidCsrCount(count) => {
+                write!(f, "expected {} CSRs, found {}", CSR_SIZE, count)
+            }
+            SnapshotError::InvalidPage(addr) => write!(f, "invalid memory page at {:#x}", addr),
+            SnapshotError::InvalidString => write!(f, "invalid instruction name"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// The complete state of an emulator at a point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// Program counter.
+    pub pc: u64,
+    /// Privilege level.
+    pub mode: Mode,
+    /// Integer registers.
+    pub xregs: [u64; REGISTERS_COUNT],
+    /// Floating-point registers, as raw bits.
+    pub fregs: [u64; REGISTERS_COUNT],
+    /// All `CSR_SIZE` CSRs, indexed by address.
+    pub csrs: Vec<u64>,
+    /// SV39 paging flag.
+    pub enable_paging: bool,
+    /// Root page table address.
+    pub page_table: u64,
+    /// Reservation set of load-reserved/store-conditional instructions.
+    pub reservation_set: Vec<u64>,
+    /// Whether the CPU waits for an interrupt.
+    pub idle: bool,
+    /// Previous instruction.
+    pub pre_inst: u64,
+    /// Whether instructions are counted.
+    pub is_count: bool,
+    /// Counter of each instruction executed.
+    pub inst_counter: BTreeMap<String, u64>,
+    /// CLINT software interrupt pending register.
+    pub msip: u32,
+    /// CLINT timer compare register.
+    pub mtimecmp: u64,
+    /// CLINT timer register.
+    pub mtime: u64,
+    /// Size of the binary loaded into DRAM.
+    pub code_size: u64,
+    /// Written, non-zero DRAM pages keyed by physical address. Every page is `DRAM_PAGE_SIZE`
+    /// bytes long.
+    pub pages: BTreeMap<u64, Vec<u8>>,
+}
+
+impl Snapshot {
+    /// Encode the snapshot.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        put_u32(&mut out, VERSION);
+
+        put_u64(&mut out, self.pc);
+        out.push(self.mode as u8);
+        for value in self.xregs.iter().chain(self.fregs.iter()) {
+            put_u64(&mut out, *value);
+        }
+        put_u64(&mut out, self.csrs.len() as u64);
+        for value in &self.csrs {
+            put_u64(&mut out, *value);
+        }
+        out.push(self.enable_paging as u8);
+        put_u64(&mut out, self.page_table);
+        put_u64(&mut out, self.reservation_set.len() as u64);
+        for addr in &self.reservation_set {
+            put_u64(&mut out, *addr);
+        }
+        out.push(self.idle as u8);
+        put_u64(&mut out, self.pre_inst);
+        out.push(self.is_count as u8);
+        put_u64(&mut out, self.inst_counter.len() as u64);
+        for (name, count) in &self.inst_counter {
+            put_u64(&mut out, name.len() as u64);
+            out.extend_from_slice(name.as_bytes());
+            put_u64(&mut out, *count);
+        }
+        put_u32(&mut out, self.msip);
+        put_u64(&mut out, self.mtimecmp);
+        put_u64(&mut out, self.mtime);
+        put_u64(&mut out, self.code_size);
+        put_u64(&mut out, self.pages.len() as u64);
+        for (addr, page) in &self.pages {
+            put_u64(&mut out, *addr);
+            out.extend_from_slice(page);
+        }
+        out
+    }
+
+    /// Decode a snapshot produced by [`Snapshot::to_bytes`].
+    pub fn from_bytes(data: &[u8]) -> Result<Snapshot, SnapshotError> {
+        let mut reader = Reader { data };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let pc = reader.u64()?;
+        let mode = match reader.u8()? {
+            0b00 => Mode::User,
+            0b01 => Mode::Supervisor,
+            0b11 => Mode::Machine,
+            mode if mode == Mode::Debug as u8 => Mode::Debug,
+            mode => return Err(SnapshotError::InvalidMode(mode)),
+        };
+        let mut xregs = [0; REGISTERS_COUNT];
+        for value in xregs.iter_mut() {
+            *value = reader.u64()?;
+        }
+        let mut fregs = [0; REGISTERS_COUNT];
+        for value in fregs.iter_mut() {
+            *value = reader.u64()?;
+        }
+        let csr_count = reader.len(8)?;
+        if csr_count != CSR_SIZE {
+            return Err(SnapshotError::InvalidCsrCount(csr_count));
+        }
+        let csrs = (0..csr_count)
+            .map(|_| reader.u64())
+            .collect::<Result<_, _>>()?;
+        let enable_paging = reader.u8()? != 0;
+        let page_table = reader.u64()?;
+        let reservation_count = reader.len(8)?;
+        let reservation_set = (0..reservation_count)
+            .map(|_| reader.u64())
+            .collect::<Result<_, _>>()?;
+        let idle = reader.u8()? != 0;
+        let pre_inst = reader.u64()?;
+        let is_count = reader.u8()? != 0;
+        let mut inst_counter = BTreeMap::new();
+        for _ in 0..reader.len(16)? {
+            let len = reader.len(1)?;
+            let name =
+                std::str::from_utf8(reader.take(len)?).map_err(|_| SnapshotError::InvalidString)?;
+            inst_counter.insert(name.to_string(), reader.u64()?);
+        }
+        let msip = reader.u32()?;
+        let mtimecmp = reader.u64()?;
+        let mtime = reader.u64()?;
+        let code_size = reader.u64()?;
+        let mut pages = BTreeMap::new();
+        for _ in 0..reader.len(8 + DRAM_PAGE_SIZE as usize)? {
+            let addr = reader.u64()?;
+            let page = reader.take(DRAM_PAGE_SIZE as usize)?;
+            check_page(addr, page)?;
+            pages.insert(addr, page.to_vec());
+        }
+
+        if !reader.data.is_empty() {
+            return Err(SnapshotError::TrailingBytes);
+        }
+
+        Ok(Snapshot {
+            pc,
+            mode,
+            xregs,
+            fregs,
+            csrs,
+            enable_paging,
+            page_table,
+            reservation_set,
+            idle,
+            pre_inst,
+            is_count,
+            inst_counter,
+            msip,
+            mtimecmp,
+            mtime,
+            code_size,
+            pages,
+        })
+    }
+}
+
+impl Emulator {
+    /// Capture the current state of the emulator.
+    pub fn snapshot(&self) -> Snapshot {
+        let cpu = &self.cpu;
+        let dram = &cpu.bus.dram;
+
+        let mut pages = BTreeMap::new();
+        for page in dram.dirty_pages() {
+            let start = page * DRAM_PAGE_SIZE as usize;
+            let Some(data) = dram.dram.get(start..start + DRAM_PAGE_SIZE as usize) else {
+                continue;
+            };
+            if data != [0; DRAM_PAGE_SIZE as usize] {
+                pages.insert(DRAM_BASE + start as u64, data.to_vec());
+            }
+        }
+
+        Snapshot {
+            pc: cpu.pc,
+            mode: cpu.mode,
+            xregs: std::array::from_fn(|i| cpu.xregs.read(i as u64)),
+            fregs: std::array::from_fn(|i| cpu.fregs.read(i as u64).to_bits()),
+            csrs: cpu.state.csrs.to_vec(),
+            enable_paging: cpu.enable_paging,
+            page_table: cpu.page_table,
+            reservation_set: cpu.reservation_set.clone(),
+            idle: cpu.idle,
+            pre_inst: cpu.pre_inst,
+            is_count: cpu.is_count,
+            inst_counter: cpu.inst_counter.clone(),
+            msip: cpu.bus.clint.msip,
+            mtimecmp: cpu.bus.clint.mtimecmp,
+            mtime: cpu.bus.clint.mtime,
+            code_size: dram.code_size,
+            pages,
+        }
+    }
+
+    /// Replace the state of the emulator with `snapshot`. Memory written since the emulator was
+    /// created is cleared first, so the result does not depend on what the emulator ran before.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        if snapshot.csrs.len() != CSR_SIZE {
+            return Err(SnapshotError::InvalidCsrCount(snapshot.csrs.len()));
+        }
+        for (addr, page) in &snapshot.pages {
+            check_page(*addr, page)?;
+        }
+
+        let cpu = &mut self.cpu;
+        cpu.pc = snapshot.pc;
+        cpu.mode = snapshot.mode;
+        for i in 0..REGISTERS_COUNT {
+            cpu.xregs.write(i as u64, snapshot.xregs[i]);
+            cpu.fregs.write(i as u64, f64::from_bits(snapshot.fregs[i]));
+        }
+        cpu.state.csrs.copy_from_slice(&snapshot.csrs);
+        cpu.enable_paging = snapshot.enable_paging;
+        cpu.page_table = snapshot.page_table;
+        cpu.reservation_set = snapshot.reservation_set.clone();
+        cpu.idle = snapshot.idle;
+        cpu.pre_inst = snapshot.pre_inst;
+        cpu.is_count = snapshot.is_count;
+        cpu.inst_counter = snapshot.inst_counter.clone();
+        cpu.bus.clint.msip = snapshot.msip;
+        cpu.bus.clint.mtimecmp = snapshot.mtimecmp;
+        cpu.bus.clint.mtime = snapshot.mtime;
+
+        let dram = &mut cpu.bus.dram;
+        dram.clear();
+        dram.code_size = snapshot.code_size;
+        for (addr, page) in &snapshot.pages {
+            let start = (addr - DRAM_BASE) as usize;
+            dram.dram[start..start + page.len()].copy_from_slice(page);
+            dram.mark_dirty(start..start + page.len());
+        }
+
+        Ok(())
+    }
+}
+
+fn check_page(addr: u64, page: &[u8]) -> Result<(), SnapshotError> {
+    let in_dram = addr >= DRAM_BASE && addr - DRAM_BASE + DRAM_PAGE_SIZE <= DRAM_SIZE;
+    if !in_dram || addr % DRAM_PAGE_SIZE != 0 || page.len() as u64 != DRAM_PAGE_SIZE {
+        return Err(SnapshotError::InvalidPage(addr));
+    }
+    Ok(())
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Reads the encoding of a snapshot front to back.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.data.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Read a length prefix, rejecting lengths the remaining data cannot hold given that each
+    /// item takes at least `item_size` bytes.
+    fn len(&mut self, item_size: usize) -> Result<usize, SnapshotError> {
+        let len = self.u64()?;
+        if len > (self.data.len() / item_size) as u64 {
+            return Err(SnapshotError::Truncated);
+        }
+        Ok(len as usize)
+    }
+}
@@ -0,0 +1,115 @@
+use rvemu::bus::DRAM_BASE;
+use rvemu::disasm::{decode, disassemble};
+
+/// Decode `bytes` at `DRAM_BASE` and render each instruction as `mnemonic operands`.
+fn render(bytes: &[u8]) -> Vec<String> {
+    disassemble(bytes, DRAM_BASE)
+        .iter()
+        .map(|inst| {
+            format!("{} {}", inst.mnemonic, inst.operands)
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn rv64i() {
+    let data = vec![
+        0x93, 0x02, 0x50, 0x05, // addi t0, zero, 85
+        0x73, 0x00, 0x00, 0x00, // ecall
+        0xef, 0x00, 0x80, 0x00, // jal ra, 8
+        0xe3, 0x0e, 0xb5, 0xfe, // beq a0, a1, -4
+        0x83, 0x30, 0x04, 0xff, // ld ra, -16(s0)
+        0x37, 0x55, 0x34, 0x12, // lui a0, 0x12345
+        0x73, 0x25, 0x00, 0x30, // csrrs a0, mstatus, zero
+    ];
+
+    assert_eq!(
+        vec![
+            "addi t0, zero, 85",
+            "ecall",
+            "jal ra, 0x80000010",
+            "beq a0, a1, 0x80000008",
+            "ld ra, -16(s0)",
+            "lui a0, 0x12345",
+            "csrrs a0, 0x300, zero",
+        ],
+        render(&data)
+    );
+}
+
+#[test]
+fn rv64ma() {
+    let data = vec![
+        0x33, 0xb5, 0xc5, 0x02, // mulhu a0, a1, a2
+        0x2f, 0x35, 0xb6, 0x08, // amoswap.d a0, a1, (a2)
+        0xaf, 0x22, 0x05, 0x10, // lr.w t0, (a0)
+    ];
+
+    assert_eq!(
+        vec![
+            "mulhu a0, a1, a2",
+            "amoswap.d a0, a1, (a2)",
+            "lr.w t0, (a0)"
+        ],
+        render(&data)
+    );
+}
+
+#[test]
+fn rv64c() {
+    let data = vec![
+        0xd5, 0x42, // c.li t0, 21
+        0x3d, 0x71, // c.addi16sp sp, -32
+        0x06, 0xec, // c.sdsp ra, 24(sp)
+        0xe2, 0x60, // c.ldsp ra, 24(sp)
+        0x2e, 0x85, // c.mv a0, a1
+        0xfd, 0xbf, // c.j -2
+        0x19, 0xe1, // c.bnez a0, 6
+        0x08, 0x08, // c.addi4spn a0, sp, 16
+        0xc8, 0x41, // c.lw a0, 4(a1)
+        0x05, 0x65, // c.lui a0, 1
+        0x0d, 0x84, // c.srai s0, 3
+        0x82, 0x80, // c.jr ra
+    ];
+
+    assert_eq!(
+        vec![
+            "c.li t0, 21",
+            "c.addi16sp sp, -32",
+            "c.sdsp ra, 24(sp)",
+            "c.ldsp ra, 24(sp)",
+            "c.mv a0, a1",
+            "c.j 0x80000008",
+            "c.bnez a0, 0x80000012",
+            "c.addi4spn a0, sp, 16",
+            "c.lw a0, 4(a1)",
+            "c.lui a0, 0x1",
+            "c.srai s0, 3",
+            "c.jr ra",
+        ],
+        render(&data)
+    );
+}
+
+#[test]
+fn mixed_sizes_and_helpers() {
+    let data = vec![
+        0xd5, 0x42, // c.li t0, 21
+        0x73, 0x00, 0x00, 0x00, // ecall
+        0x93, 0x02, // truncated
+    ];
+
+    let insts = disassemble(&data, DRAM_BASE);
+    assert_eq!(2, insts.len());
+    assert_eq!((2, DRAM_BASE), (insts[0].size, insts[0].address));
+    assert_eq!((4, DRAM_BASE + 2), (insts[1].size, insts[1].address));
+
+    assert_eq!(Some(21), insts[0].loaded_immediate(5));
+    assert_eq!(None, insts[0].loaded_immediate(6));
+    assert!(insts[1].is_ecall());
+
+    assert_eq!(Some(DRAM_BASE + 8), decode(0x008000ef, DRAM_BASE).target);
+    assert_eq!("unknown", decode(0xffffffff, DRAM_BASE).mnemonic);
+}
@@ -0,0 +1,122 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use rvemu::bus::DRAM_BASE;
+use rvemu::emulator::Emulator;
+use rvemu::exception::Exception;
+use rvemu::gdb::GdbStub;
+
+/// A minimal GDB client speaking the remote serial protocol.
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn send(&mut self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.stream, "${}#{:02x}", data, checksum).unwrap();
+    }
+
+    fn recv(&mut self) -> String {
+        let mut byte = [0u8; 1];
+        // Skip acks until the start of a packet.
+        loop {
+            self.stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'$' {
+                break;
+            }
+        }
+        let mut data = Vec::new();
+        loop {
+            self.stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'#' {
+                break;
+            }
+            data.push(byte[0]);
+        }
+        let mut checksum = [0u8; 2];
+        self.stream.read_exact(&mut checksum).unwrap();
+        self.stream.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    fn request(&mut self, data: &str) -> String {
+        self.send(data);
+        self.recv()
+    }
+}
+
+/// Run `data` under a GDB stub on a separate thread and return a connected client.
+fn debug(data: Vec<u8>) -> (Client, thread::JoinHandle<Result<(), Exception>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut emu = Emulator::new();
+        emu.initialize_dram(data);
+        emu.initialize_pc(DRAM_BASE);
+        emu.attach_gdb(GdbStub::new(stream));
+        emu.estart()
+    });
+
+    let stream = TcpStream::connect(addr).unwrap();
+    (Client { stream }, handle)
+}
+
+fn program() -> Vec<u8> {
+    vec![
+        0x93, 0x02, 0x50, 0x05, // addi x5, x0, 0x55
+        0x93, 0x0f, 0x50, 0x00, // addi x31, x0, 5
+        0x73, 0x00, 0x00, 0x00, // ecall
+    ]
+}
+
+#[test]
+fn breakpoint_step_and_registers() {
+    let (mut client, handle) = debug(program());
+
+    // The target starts halted.
+    assert_eq!("S05", client.recv());
+    assert_eq!("S05", client.request("?"));
+
+    assert_eq!("OK", client.request("Z0,80000004,4"));
+    assert_eq!("S05", client.request("c"));
+    assert_eq!("0400008000000000", client.request("p20"));
+    assert_eq!("5500000000000000", client.request("p5"));
+
+    assert_eq!("S05", client.request("s"));
+    assert_eq!("0800008000000000", client.request("p20"));
+    assert_eq!("0500000000000000", client.request("p1f"));
+
+    // Registers and memory can be written back.
+    assert_eq!("OK", client.request("P1f=2a00000000000000"));
+    assert_eq!("2a00000000000000", client.request("p1f"));
+    assert_eq!("73000000", client.request("m80000008,4"));
+
+    assert_eq!("OK", client.request("D"));
+    assert_eq!(
+        Err(Exception::EnvironmentCallFromMMode),
+        handle.join().unwrap()
+    );
+}
+
+#[test]
+fn ecall_is_reported_as_syscall_stop() {
+    let (mut client, handle) = debug(program());
+
+    assert_eq!("S05", client.recv());
+    assert_eq!("T05syscall_entry:55;", client.request("c"));
+
+    let g = client.request("g");
+    // x0-x31 and pc, 16 hex digits each.
+    assert_eq!(33 * 16, g.len());
+    assert_eq!("0800008000000000", &g[32 * 16..]);
+
+    client.send("c");
+    assert_eq!(
+        Err(Exception::EnvironmentCallFromMMode),
+        handle.join().unwrap()
+    );
+}
@@ -0,0 +1,88 @@
+use rvemu::bus::DRAM_BASE;
+use rvemu::emulator::Emulator;
+use rvemu::exception::Exception;
+use rvemu::profile::{Cost, Profiler, Symbol};
+
+fn symbol(name: &str, offset: u64, size: u64) -> Symbol {
+    Symbol {
+        name: name.to_string(),
+        address: DRAM_BASE + offset,
+        size,
+    }
+}
+
+/// Jumps count 3 gas, everything else 1.
+fn cost(mnemonic: &str) -> u64 {
+    match mnemonic {
+        "jal" | "jalr" => 3,
+        _ => 1,
+    }
+}
+
+fn run(data: Vec<u8>, symbols: Vec<Symbol>) -> Profiler {
+    let mut emu = Emulator::new();
+    emu.initialize_dram(data);
+    emu.initialize_pc(DRAM_BASE);
+    emu.attach_profiler(Profiler::new(symbols).with_cost(cost));
+
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), emu.estart());
+    emu.detach_profiler().unwrap()
+}
+
+#[test]
+fn call_and_return() {
+    let data = vec![
+        0xef, 0x00, 0xc0, 0x00, // main: jal ra, leaf
+        0x93, 0x02, 0x50, 0x05, // addi t0, zero, 85
+        0x73, 0x00, 0x00, 0x00, // ecall
+        0x13, 0x05, 0x15, 0x00, // leaf: addi a0, a0, 1
+        0x67, 0x80, 0x00, 0x00, // jalr zero, 0(ra)
+    ];
+    let profiler = run(data, vec![symbol("leaf", 12, 8), symbol("main", 0, 12)]);
+
+    assert_eq!("main 5\nmain;leaf 4\n", profiler.folded());
+    assert_eq!(
+        Cost {
+            instructions: 5,
+            gas: 9
+        },
+        profiler.total()
+    );
+
+    let functions = profiler.functions();
+    assert_eq!("main", functions[0].name);
+    assert_eq!(
+        Cost {
+            instructions: 3,
+            gas: 5
+        },
+        functions[0].self_cost
+    );
+    assert_eq!(profiler.total(), functions[0].total_cost);
+    assert_eq!("leaf", functions[1].name);
+    assert_eq!(functions[1].self_cost, functions[1].total_cost);
+}
+
+#[test]
+fn compressed_calls_tail_calls_and_unknown_code() {
+    let data = vec![
+        0x97, 0x00, 0x00, 0x00, // main: auipc ra, 0
+        0x93, 0x80, 0xe0, 0x00, // addi ra, ra, 14
+        0x82, 0x90, // c.jalr ra (calls `outer`)
+        0x73, 0x00, 0x00, 0x00, // ecall
+        0x6f, 0x00, 0x40, 0x00, // outer: jal zero, tail (a tail call)
+        0x05, 0x05, // tail: c.addi a0, 1
+        0x82, 0x80, // c.jr ra
+    ];
+    // Only the entry point and the tail-called function have symbols, `outer` does not.
+    let profiler = run(data, vec![symbol("main", 0, 14), symbol("tail", 18, 4)]);
+
+    let folded = profiler.folded_stacks();
+    assert_eq!(
+        vec!["main", "main;[unknown]", "main;tail"],
+        folded.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(4, folded["main"].instructions);
+    assert_eq!(1, folded["main;[unknown]"].instructions);
+    assert_eq!(2, folded["main;tail"].instructions);
+}
@@ -0,0 +1,118 @@
+use rvemu::bus::DRAM_BASE;
+use rvemu::emulator::Emulator;
+use rvemu::exception::Exception;
+use rvemu::snapshot::{Snapshot, SnapshotError};
+
+/// Stores to memory, stops on an `ecall`, then keeps working on the stored value.
+fn program() -> Vec<u8> {
+    vec![
+        0x17, 0x15, 0x00, 0x00, // auipc a0, 0x1
+        0x93, 0x05, 0xa0, 0x02, // addi a1, zero, 42
+        0x23, 0x30, 0xb5, 0x00, // sd a1, 0(a0)
+        0x73, 0x00, 0x00, 0x00, // ecall
+        0x03, 0x36, 0x05, 0x00, // ld a2, 0(a0)
+        0x13, 0x06, 0x16, 0x00, // addi a2, a2, 1
+        0x23, 0x34, 0xc5, 0x00, // sd a2, 8(a0)
+        0x73, 0x00, 0x00, 0x00, // ecall
+    ]
+}
+
+fn emulator() -> Emulator {
+    load(Emulator::with_dirty_tracking())
+}
+
+fn load(mut emu: Emulator) -> Emulator {
+    emu.initialize_dram(program());
+    emu.initialize_pc(DRAM_BASE);
+    emu.cpu.is_count = true;
+    emu
+}
+
+#[test]
+fn resume_from_snapshot() {
+    let mut emu = emulator();
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), emu.estart());
+
+    let snapshot = emu.snapshot();
+    // The code page and the page holding the stored value.
+    assert_eq!(
+        vec![DRAM_BASE, DRAM_BASE + 0x1000],
+        snapshot.pages.keys().copied().collect::<Vec<_>>()
+    );
+
+    let bytes = snapshot.to_bytes();
+    let decoded = Snapshot::from_bytes(&bytes).unwrap();
+    assert_eq!(snapshot, decoded);
+
+    let mut resumed = Emulator::with_dirty_tracking();
+    resumed.restore(&decoded).unwrap();
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), resumed.estart());
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), emu.estart());
+
+    for emu in [&mut emu, &mut resumed] {
+        assert_eq!(43, emu.cpu.xregs.read(12));
+        assert_eq!(Ok(43), emu.cpu.bus.read(DRAM_BASE + 0x1008, 64));
+        assert_eq!(DRAM_BASE + 32, emu.cpu.pc);
+    }
+    assert_eq!(emu.cpu.inst_counter, resumed.cpu.inst_counter);
+    assert_eq!(emu.snapshot(), resumed.snapshot());
+}
+
+#[test]
+fn restore_discards_later_writes() {
+    let mut emu = emulator();
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), emu.estart());
+    let snapshot = emu.snapshot();
+
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), emu.estart());
+    assert_eq!(Ok(43), emu.cpu.bus.read(DRAM_BASE + 0x1008, 64));
+
+    emu.restore(&snapshot).unwrap();
+    assert_eq!(Ok(0), emu.cpu.bus.read(DRAM_BASE + 0x1008, 64));
+    assert_eq!(DRAM_BASE + 16, emu.cpu.pc);
+    assert_eq!(snapshot, emu.snapshot());
+}
+
+#[test]
+fn snapshot_without_dirty_tracking() {
+    let mut tracked = emulator();
+    let mut untracked = load(Emulator::new());
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), tracked.estart());
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), untracked.estart());
+
+    let snapshot = untracked.snapshot();
+    assert_eq!(tracked.snapshot(), snapshot);
+
+    assert_eq!(Err(Exception::EnvironmentCallFromMMode), untracked.estart());
+    untracked.restore(&snapshot).unwrap();
+    assert_eq!(Ok(0), untracked.cpu.bus.read(DRAM_BASE + 0x1008, 64));
+    assert_eq!(DRAM_BASE + 16, untracked.cpu.pc);
+}
+
+#[test]
+fn invalid_snapshots() {
+    let bytes = emulator().snapshot().to_bytes();
+
+    assert_eq!(
+        Err(SnapshotError::InvalidMagic),
+        Snapshot::from_bytes(b"not a snapshot")
+    );
+    assert_eq!(
+        Err(SnapshotError::Truncated),
+        Snapshot::from_bytes(&bytes[..bytes.len() - 1])
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        Err(SnapshotError::TrailingBytes),
+        Snapshot::from_bytes(&trailing)
+    );
+
+    let mut version = bytes.clone();
+    version[8] = 2;
+    assert_eq!(
+        Err(SnapshotError::UnsupportedVersion(2)),
+        Snapshot::from_bytes(&version)
+    );
+}